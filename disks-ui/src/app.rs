@@ -4,9 +4,20 @@ use crate::config::Config;
 use crate::fl;
 use crate::utils::{labelled_info, link_info};
 use crate::views::about::about;
+#[cfg(feature = "dbus")]
+use crate::views::benchmark::{BenchmarkDialog, BenchmarkMessage};
 use crate::views::dialogs;
+#[cfg(feature = "dbus")]
+use crate::views::drive_settings::{self, DriveSettingsDialog, DriveSettingsMessage};
+use crate::views::format_disk::{FormatDiskDialog, FormatDiskMessage};
+#[cfg(feature = "dbus")]
+use crate::views::image::{ImageDialog, ImageMessage, ImageOperation};
 use crate::views::menu::{MenuAction, menu_view};
-use crate::views::volumes::{VolumesControl, VolumesControlMessage};
+#[cfg(feature = "dbus")]
+use crate::views::smart::{self, SmartDialog, SmartMessage};
+use crate::views::volumes::{
+    CreateInfo, DeleteInfo, UnlockInfo, VolumesControl, VolumesControlMessage,
+};
 use cosmic::app::{Core, Task, context_drawer};
 use cosmic::cosmic_config::{self, CosmicConfigEntry};
 use cosmic::iced::alignment::{Horizontal, Vertical};
@@ -15,15 +26,19 @@ use cosmic::widget::text::heading;
 use cosmic::widget::{self, Space, container, icon, menu, nav_bar};
 use cosmic::{Application, ApplicationExt, Apply, Element, iced_widget};
 use futures_util::{SinkExt, StreamExt};
-use hardware::bytes_to_pretty;
-use hardware::disks::{DiskManager, DriveModel};
-use hardware::{CreatePartitionInfo, Drive, Partition};
+use hardware::ByteSize;
+use hardware::{Backend, DiskBackend, Drive, Partition};
 use std::collections::HashMap;
-use std::time::Duration;
 
 pub const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
 pub const APP_ICON: &[u8] = include_bytes!("../resources/icons/hicolor/scalable/apps/icon.svg");
 
+/// The drives of the selected [`Backend`].
+pub type DriveModel = <Backend as DiskBackend>::Drive;
+
+/// The partitions of the selected [`Backend`].
+pub type PartitionModel = <Backend as DiskBackend>::Partition;
+
 /// The application model stores app-specific state used to describe its interface and
 /// drive its logic.
 pub struct AppModel {
@@ -43,8 +58,18 @@ pub struct AppModel {
 
 #[derive(Debug, Clone)]
 pub enum ShowDialog {
-    DeletePartition(String),
-    AddPartition(CreatePartitionInfo),
+    DeletePartition(DeleteInfo),
+    AddPartition(CreateInfo),
+    UnlockPartition(UnlockInfo),
+    #[cfg(feature = "dbus")]
+    Image(ImageDialog),
+    #[cfg(feature = "dbus")]
+    SmartData(SmartDialog),
+    #[cfg(feature = "dbus")]
+    Benchmark(BenchmarkDialog),
+    #[cfg(feature = "dbus")]
+    DriveSettings(DriveSettingsDialog),
+    FormatDisk(FormatDiskDialog),
 }
 
 /// Messages emitted by the application and its widgets.
@@ -56,8 +81,15 @@ pub enum Message {
     UpdateConfig(Config),
     LaunchUrl(String),
     VolumesMessage(VolumesControlMessage),
-    DriveRemoved(String),
-    DriveAdded(String),
+    #[cfg(feature = "dbus")]
+    ImageMessage(ImageMessage),
+    #[cfg(feature = "dbus")]
+    SmartMessage(SmartMessage),
+    #[cfg(feature = "dbus")]
+    BenchmarkMessage(BenchmarkMessage),
+    #[cfg(feature = "dbus")]
+    DriveSettingsMessage(DriveSettingsMessage),
+    FormatDiskMessage(FormatDiskMessage),
     None,
     UpdateNav(Vec<DriveModel>, Option<String>),
     Dialog(ShowDialog),
@@ -65,14 +97,21 @@ pub enum Message {
     Eject,
     PowerOff,
     Format,
+    #[cfg(feature = "dbus")]
     Benchmark,
+    #[cfg(feature = "dbus")]
     SmartData,
+    #[cfg(feature = "dbus")]
     DriveSettings,
     StandbyNow,
     Wakeup,
+    #[cfg(feature = "dbus")]
     NewDiskImage,
+    #[cfg(feature = "dbus")]
     AttachDisk,
+    #[cfg(feature = "dbus")]
     CreateDiskFrom,
+    #[cfg(feature = "dbus")]
     RestoreImageTo,
     Surface(cosmic::surface::Action),
 }
@@ -123,12 +162,19 @@ impl Application for AppModel {
                 .unwrap_or_default(),
         };
 
+        app.config.apply_size_format();
+
         // Create a startup command that sets the window title.
         let command = app.update_title();
 
         let nav_command = Task::perform(
             async {
-                match DriveModel::get_drives().await {
+                let drives = match Backend::connect().await {
+                    Ok(backend) => backend.drives().await,
+                    Err(e) => Err(e),
+                };
+
+                match drives {
                     Ok(drives) => Some(drives),
                     Err(e) => {
                         println!("Error: {}", e);
@@ -153,14 +199,31 @@ impl Application for AppModel {
     fn dialog(&self) -> Option<Element<Self::Message>> {
         match self.dialog {
             Some(ref d) => match d {
-                ShowDialog::DeletePartition(name) => Some(dialogs::confirmation(
-                    format!("Delete {}", name),
-                    format!("Are you sure you wish to delete {}?", name),
-                    VolumesControlMessage::Delete.into(),
-                    Some(Message::CloseDialog),
-                )),
+                ShowDialog::DeletePartition(delete) => {
+                    Some(dialogs::delete_partition(delete.clone()))
+                }
 
                 ShowDialog::AddPartition(create) => Some(dialogs::create_partition(create.clone())),
+
+                ShowDialog::UnlockPartition(unlock) => {
+                    Some(dialogs::unlock_partition(unlock.clone()))
+                }
+
+                #[cfg(feature = "dbus")]
+                ShowDialog::Image(image) => Some(dialogs::disk_image(image.clone())),
+
+                #[cfg(feature = "dbus")]
+                ShowDialog::SmartData(smart) => Some(dialogs::smart_data(smart.clone())),
+
+                #[cfg(feature = "dbus")]
+                ShowDialog::Benchmark(bench) => Some(dialogs::benchmark(bench.clone())),
+
+                #[cfg(feature = "dbus")]
+                ShowDialog::DriveSettings(settings) => {
+                    Some(dialogs::drive_settings(settings.clone()))
+                }
+
+                ShowDialog::FormatDisk(format) => Some(dialogs::format_disk(format.clone())),
             },
             None => None,
         }
@@ -234,78 +297,83 @@ impl Application for AppModel {
                     .unwrap(); //TODO: Handle unwrap.
                 let info = match segment.partition.clone() {
                     Some(p) => {
-                        let mut name = p.name.clone();
+                        let mut name = p.label();
                         if name.len() == 0 {
-                            name = format!("Partition {}", &p.number);
+                            name = format!("Partition {}", p.number());
                         } else {
-                            name = format!("Partition {}: {}", &p.number, name);
+                            name = format!("Partition {}: {}", p.number(), name);
                         }
 
-                        let mut type_str = p.id_type.clone().to_uppercase();
-                        type_str = format!("{} - {}", type_str, p.partition_type.clone());
+                        let mut type_str = p.id_type().to_uppercase();
+                        type_str = format!("{} - {}", type_str, p.partition_type());
+
+                        let usage = match p.cleartext() {
+                            Some(cleartext) => {
+                                type_str = format!(
+                                    "{} (Unlocked: {})",
+                                    type_str,
+                                    cleartext.id_type().to_uppercase()
+                                );
+                                cleartext.usage().cloned()
+                            }
+                            None => p.usage().cloned(),
+                        };
+
+                        let device = p.device_path().unwrap_or_else(|| "Unresolved".into());
 
-                        match &p.usage {
+                        match &usage {
                             Some(usage) => iced_widget::column![
                                 heading(name),
                                 Space::new(0, 10),
-                                labelled_info("Size", bytes_to_pretty(&p.size, true)),
-                                labelled_info("Usage", bytes_to_pretty(&usage.used, false)),
+                                labelled_info("Size", ByteSize(p.size()).pretty_with_bytes()),
+                                labelled_info("Usage", ByteSize(usage.used).pretty()),
                                 link_info(
                                     "Mounted at",
                                     &usage.mount_point,
                                     Message::OpenPath(usage.mount_point.clone())
                                 ),
                                 labelled_info("Contents", &type_str),
-                                labelled_info(
-                                    "Device",
-                                    match p.device_path {
-                                        Some(s) => {
-                                            s
-                                        }
-                                        None => "Unresolved".into(),
-                                    }
-                                ),
-                                labelled_info("UUID", &p.uuid),
+                                labelled_info("Device", device),
+                                labelled_info("UUID", p.uuid()),
                             ]
                             .spacing(5),
 
                             None => iced_widget::column![
                                 heading(name),
                                 Space::new(0, 10),
-                                labelled_info("Size", bytes_to_pretty(&p.size, true)),
+                                labelled_info("Size", ByteSize(p.size()).pretty_with_bytes()),
                                 labelled_info("Contents", &type_str),
-                                labelled_info(
-                                    "Device",
-                                    match p.device_path {
-                                        Some(s) => {
-                                            s
-                                        }
-                                        None => "Unresolved".into(),
-                                    }
-                                ),
-                                labelled_info("UUID", &p.uuid),
+                                labelled_info("Device", device),
+                                labelled_info("UUID", p.uuid()),
                             ]
                             .spacing(5),
                         }
                     }
                     None => iced_widget::column![
                         heading(&segment.label),
-                        labelled_info("Size", bytes_to_pretty(&segment.size, true)),
+                        labelled_info("Size", ByteSize(segment.size).pretty_with_bytes()),
                     ]
                     .spacing(5),
                 };
 
-                let partition_type = match &drive.partition_table_type {
-                    Some(t) => t.clone().to_uppercase(),
+                let partition_type = match drive.partition_table_type() {
+                    Some(t) => t.to_uppercase(),
                     None => "Unknown".into(),
                 };
+
+                //Loop devices have no serial, but the image behind them is more useful anyway.
+                let (serial_label, serial) = match drive.backing_file() {
+                    Some(file) => ("Backing File", file),
+                    None => ("Serial", drive.serial()),
+                };
+
                 iced_widget::column![
                     iced_widget::column![
                         heading(drive.pretty_name()),
                         Space::new(0, 10),
-                        labelled_info("Model", &drive.model),
-                        labelled_info("Serial", &drive.serial),
-                        labelled_info("Size", bytes_to_pretty(&drive.size, true)),
+                        labelled_info("Model", drive.model()),
+                        labelled_info(serial_label, serial),
+                        labelled_info("Size", ByteSize(drive.size()).pretty_with_bytes()),
                         labelled_info("Partitioning", &partition_type),
                     ]
                     .spacing(5)
@@ -340,23 +408,37 @@ impl Application for AppModel {
             Subscription::run_with_id(
                 std::any::TypeId::of::<DiskEventSubscription>(),
                 cosmic::iced::stream::channel(4, move |mut c| async move {
-                    let manager = match DiskManager::new().await {
-                        Ok(m) => m,
+                    let backend = match Backend::connect().await {
+                        Ok(b) => b,
                         Err(e) => {
-                            println!("Error creating DiskManager: {}", e);
+                            println!("Error connecting to the disk backend: {}", e);
+                            return;
+                        }
+                    };
+                    let mut stream = match backend.watch().await {
+                        Ok(s) => s,
+                        Err(e) => {
+                            println!("Error listening for device events: {}", e);
                             return;
                         }
                     };
-                    let mut stream = manager.device_event_stream(Duration::from_secs(1));
+
+                    //Events are applied to this copy in order, so no change is lost when several
+                    //arrive at once.
+                    let mut drives = match backend.drives().await {
+                        Ok(d) => d,
+                        Err(e) => {
+                            println!("Error: {}", e);
+                            vec![]
+                        }
+                    };
 
                     while let Some(event) = stream.next().await {
-                        match event {
-                            hardware::disks::DeviceEvent::Added(s) => {
-                                let _ = c.send(Message::DriveAdded(s)).await;
-                            }
-                            hardware::disks::DeviceEvent::Removed(s) => {
-                                let _ = c.send(Message::DriveRemoved(s)).await;
+                        match backend.apply_event(&mut drives, event).await {
+                            Ok(_) => {
+                                let _ = c.send(Message::UpdateNav(drives.clone(), None)).await;
                             }
+                            Err(e) => println!("Error applying device change: {}", e),
                         }
                     }
                 }),
@@ -397,6 +479,7 @@ impl Application for AppModel {
                 }
             }
             Message::UpdateConfig(config) => {
+                config.apply_size_format();
                 self.config = config;
             }
             Message::LaunchUrl(url) => match open::that_detached(&url) {
@@ -409,48 +492,41 @@ impl Application for AppModel {
                 let volumes_control = self.nav.active_data_mut::<VolumesControl>().unwrap(); //TODO: HANDLE UNWRAP.
                 return volumes_control.update(message, &mut self.dialog);
             }
-            Message::DriveRemoved(_drive_model) => {
-                //TODO: use DeviceManager.apply_change()
-
-                return Task::perform(
-                    async {
-                        match DriveModel::get_drives().await {
-                            Ok(drives) => Some(drives),
-                            Err(e) => {
-                                println!("Error: {}", e);
-                                return None;
-                            }
-                        }
-                    },
-                    move |drives| match drives {
-                        None => return Message::None.into(),
-                        Some(drives) => return Message::UpdateNav(drives, None).into(),
-                    },
-                );
+            #[cfg(feature = "dbus")]
+            Message::ImageMessage(message) => {
+                if let Some(ShowDialog::Image(image)) = &mut self.dialog {
+                    return image.update(message);
+                }
             }
-            Message::DriveAdded(_drive_model) => {
-                return Task::perform(
-                    async {
-                        match DriveModel::get_drives().await {
-                            Ok(drives) => Some(drives),
-                            Err(e) => {
-                                println!("Error: {}", e);
-                                return None;
-                            }
-                        }
-                    },
-                    move |drives| match drives {
-                        None => return Message::None.into(),
-                        Some(drives) => return Message::UpdateNav(drives, None).into(),
-                    },
-                );
+            #[cfg(feature = "dbus")]
+            Message::SmartMessage(message) => {
+                if let Some(ShowDialog::SmartData(smart)) = &mut self.dialog {
+                    return smart.update(message);
+                }
+            }
+            #[cfg(feature = "dbus")]
+            Message::BenchmarkMessage(message) => {
+                if let Some(ShowDialog::Benchmark(bench)) = &mut self.dialog {
+                    return bench.update(message);
+                }
+            }
+            #[cfg(feature = "dbus")]
+            Message::DriveSettingsMessage(message) => {
+                if let Some(ShowDialog::DriveSettings(settings)) = &mut self.dialog {
+                    return settings.update(message);
+                }
+            }
+            Message::FormatDiskMessage(message) => {
+                if let Some(ShowDialog::FormatDisk(format)) = &mut self.dialog {
+                    return format.update(message);
+                }
             }
             Message::None => {}
             Message::UpdateNav(drive_models, selected) => {
                 let selected = match selected {
                     Some(s) => Some(s),
                     None => match self.nav.active_data::<DriveModel>() {
-                        Some(d) => Some(d.block_path.clone()),
+                        Some(d) => Some(d.block_path()),
                         None => None,
                     },
                 };
@@ -461,7 +537,7 @@ impl Application for AppModel {
                     Some(s) => Some(s),
                     None => {
                         if selected.is_none() && drive_models.len() > 0 {
-                            Some(drive_models.first().unwrap().block_path.clone())
+                            Some(drive_models.first().unwrap().block_path())
                         } else {
                             None
                         }
@@ -469,14 +545,14 @@ impl Application for AppModel {
                 };
 
                 for drive in drive_models {
-                    let icon = match drive.removable {
+                    let icon = match drive.removable() {
                         true => "drive-removable-media-symbolic",
                         false => "disks-symbolic",
                     };
 
                     match selected {
                         Some(ref s) => {
-                            if drive.block_path == s.clone() {
+                            if drive.block_path() == *s {
                                 self.nav
                                     .insert()
                                     .text(drive.pretty_name())
@@ -506,40 +582,117 @@ impl Application for AppModel {
             }
             Message::Dialog(show_dialog) => self.dialog = Some(show_dialog),
             Message::CloseDialog => {
+                #[cfg(feature = "dbus")]
+                match &self.dialog {
+                    Some(ShowDialog::Image(image)) => image.cancel.cancel(),
+                    Some(ShowDialog::Benchmark(bench)) => bench.cancel.cancel(),
+                    _ => {}
+                }
+
                 self.dialog = None;
             }
             Message::Eject => {
                 if let Some(drive) = self.nav.active_data::<DriveModel>().cloned() {
-                    return Task::perform(
-                        async move {
-                            let _ = drive.eject().await; //TODO handle error
-                            match DriveModel::get_drives().await {
-                                Ok(drives) => Some(drives),
-                                Err(e) => {
-                                    println!("Error: {}", e);
-                                    return None;
-                                }
-                            }
-                        },
-                        move |drives| match drives {
-                            None => return Message::None.into(),
-                            Some(drives) => return Message::UpdateNav(drives, None).into(),
-                        },
-                    );
-                } else {
+                    return Task::perform(async move { drive.eject().await }, |result| {
+                        if let Err(e) = result {
+                            println!("{e}");
+                        }
+                        Message::None.into()
+                    });
+                }
+            }
+            Message::PowerOff => {
+                if let Some(drive) = self.nav.active_data::<DriveModel>().cloned() {
+                    return Task::perform(async move { drive.power_off().await }, |result| {
+                        if let Err(e) = result {
+                            println!("{e}");
+                        }
+                        Message::None.into()
+                    });
+                }
+            }
+            Message::Format => {
+                if let Some(drive) = self.nav.active_data::<DriveModel>().cloned() {
+                    let mut format = FormatDiskDialog::new(drive);
+                    let task = format.load_plan();
+                    self.dialog = Some(ShowDialog::FormatDisk(format));
+                    return task;
+                }
+            }
+            #[cfg(feature = "dbus")]
+            Message::Benchmark => {
+                if let Some(drive) = self.nav.active_data::<DriveModel>().cloned() {
+                    self.dialog = Some(ShowDialog::Benchmark(BenchmarkDialog::new(drive)));
+                }
+            }
+            #[cfg(feature = "dbus")]
+            Message::SmartData => {
+                if let Some(drive) = self.nav.active_data::<DriveModel>().cloned() {
+                    self.dialog = Some(ShowDialog::SmartData(SmartDialog::new(drive.clone())));
+                    return smart::read_report(drive);
+                }
+            }
+            #[cfg(feature = "dbus")]
+            Message::DriveSettings => {
+                if let Some(drive) = self.nav.active_data::<DriveModel>().cloned() {
+                    self.dialog = Some(ShowDialog::DriveSettings(DriveSettingsDialog::new(
+                        drive.clone(),
+                    )));
+                    return drive_settings::load_settings(drive);
+                }
+            }
+            Message::StandbyNow => {
+                if let Some(drive) = self.nav.active_data::<DriveModel>().cloned() {
+                    return Task::perform(async move { drive.standby().await }, |result| {
+                        if let Err(e) = result {
+                            println!("{e}");
+                        }
+                        Message::None.into()
+                    });
+                }
+            }
+            Message::Wakeup => {
+                if let Some(drive) = self.nav.active_data::<DriveModel>().cloned() {
+                    return Task::perform(async move { drive.wakeup().await }, |result| {
+                        if let Err(e) = result {
+                            println!("{e}");
+                        }
+                        Message::None.into()
+                    });
+                }
+            }
+            #[cfg(feature = "dbus")]
+            Message::NewDiskImage => {
+                self.dialog = Some(ShowDialog::Image(ImageDialog::new(
+                    ImageOperation::New,
+                    None,
+                )));
+            }
+            #[cfg(feature = "dbus")]
+            Message::AttachDisk => {
+                self.dialog = Some(ShowDialog::Image(ImageDialog::new(
+                    ImageOperation::Attach,
+                    None,
+                )));
+            }
+            #[cfg(feature = "dbus")]
+            Message::CreateDiskFrom => {
+                if let Some(drive) = self.nav.active_data::<DriveModel>().cloned() {
+                    self.dialog = Some(ShowDialog::Image(ImageDialog::new(
+                        ImageOperation::CreateFrom,
+                        Some(drive),
+                    )));
+                }
+            }
+            #[cfg(feature = "dbus")]
+            Message::RestoreImageTo => {
+                if let Some(drive) = self.nav.active_data::<DriveModel>().cloned() {
+                    self.dialog = Some(ShowDialog::Image(ImageDialog::new(
+                        ImageOperation::Restore,
+                        Some(drive),
+                    )));
                 }
             }
-            Message::PowerOff => todo!(),
-            Message::Format => todo!(),
-            Message::Benchmark => todo!(),
-            Message::SmartData => todo!(),
-            Message::DriveSettings => todo!(),
-            Message::StandbyNow => todo!(),
-            Message::Wakeup => todo!(),
-            Message::NewDiskImage => todo!(),
-            Message::AttachDisk => todo!(),
-            Message::CreateDiskFrom => todo!(),
-            Message::RestoreImageTo => todo!(),
             Message::Surface(action) => {
                 return cosmic::task::message(cosmic::Action::Cosmic(
                     cosmic::app::Action::Surface(action),
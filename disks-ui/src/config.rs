@@ -1,9 +1,23 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use cosmic::cosmic_config::{self, CosmicConfigEntry, cosmic_config_derive::CosmicConfigEntry};
+use hardware::{SizeFormat, UnitSystem};
 
 #[derive(Debug, Default, Clone, CosmicConfigEntry, Eq, PartialEq)]
 #[version = 1]
 pub struct Config {
     demo: String,
+    /// Whether sizes are shown in KiB, MiB, ... or kB, MB, ...
+    pub units: UnitSystem,
+}
+
+impl Config {
+    /// Makes every size shown or entered in the app use the configured units.
+    pub fn apply_size_format(&self) {
+        SizeFormat {
+            units: self.units,
+            ..SizeFormat::current()
+        }
+        .set_current();
+    }
 }
@@ -1,3 +1,7 @@
+#[cfg(feature = "dbus")]
+mod task;
 mod ui;
 
+#[cfg(feature = "dbus")]
+pub use task::*;
 pub use ui::*;
@@ -0,0 +1,45 @@
+use cosmic::app::Task;
+use futures_util::SinkExt;
+
+use crate::app::Message;
+
+/// Runs a long operation in the background, streaming its progress back to the app. Progress
+/// updates that arrive faster than they can be drawn are coalesced, so only the latest is sent.
+pub fn run_with_progress<P, R, F, Fut>(
+    operation: F,
+    on_progress: fn(P) -> Message,
+    on_finished: fn(Result<R, String>) -> Message,
+) -> Task<Message>
+where
+    P: Send + 'static,
+    R: Send + 'static,
+    F: FnOnce(Box<dyn Fn(P) + Send>) -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<R>> + Send + 'static,
+{
+    Task::run(
+        cosmic::iced::stream::channel(16, move |mut output| async move {
+            let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+            let job = tokio::spawn(operation(Box::new(move |progress| {
+                let _ = sender.send(progress);
+            })));
+
+            while let Some(mut progress) = receiver.recv().await {
+                while let Ok(next) = receiver.try_recv() {
+                    progress = next;
+                }
+
+                let _ = output.send(on_progress(progress)).await;
+            }
+
+            let result = match job.await {
+                Ok(Ok(result)) => Ok(result),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+
+            let _ = output.send(on_finished(result)).await;
+        }),
+        |message: Message| message.into(),
+    )
+}
@@ -1,4 +1,4 @@
-use hardware::pretty_to_bytes;
+use hardware::ByteSize;
 
 use std::borrow::Cow;
 
@@ -25,8 +25,8 @@ pub fn input_spinner<'a, Message: 'static + Clone>(
         widget::text_input("", value_string.into())
             .width(Length::Fill)
             .on_input(move |v| {
-                match pretty_to_bytes(&v) {
-                    Ok(v) => (text_edit)((v as f64).clamp(min, max)),
+                match ByteSize::parse(&v, Some(ByteSize(max as u64))) {
+                    Ok(v) => (text_edit)((v.bytes() as f64).clamp(min, max)),
                     Err(_) => (text_edit)(value), //TODO: Validation
                 }
             }),
@@ -0,0 +1,111 @@
+use cosmic::app::Task;
+use hardware::disks::DriveModel;
+use hardware::{BenchmarkOptions, BenchmarkResult, BenchmarkWrite, CancelToken};
+
+use crate::app::Message;
+use crate::utils::run_with_progress;
+
+pub static WRITE_MODES: [BenchmarkWrite; 3] = [
+    BenchmarkWrite::None,
+    BenchmarkWrite::UnallocatedOnly,
+    BenchmarkWrite::PreserveData,
+];
+
+pub static WRITE_MODE_NAMES: [&str; 3] = [
+    "Read only",
+    "Write to unallocated space",
+    "Write, preserving data",
+];
+
+#[derive(Debug, Clone)]
+pub struct BenchmarkDialog {
+    pub drive: DriveModel,
+    pub write: BenchmarkWrite,
+    pub result: Option<BenchmarkResult>,
+    pub running: bool,
+    pub cancel: CancelToken,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum BenchmarkMessage {
+    WriteUpdate(usize),
+    Start,
+    Progress(BenchmarkResult),
+    Finished(Result<BenchmarkResult, String>),
+    Cancel,
+}
+
+impl Into<Message> for BenchmarkMessage {
+    fn into(self) -> Message {
+        Message::BenchmarkMessage(self)
+    }
+}
+
+impl BenchmarkDialog {
+    pub fn new(drive: DriveModel) -> Self {
+        Self {
+            drive,
+            write: BenchmarkWrite::None,
+            result: None,
+            running: false,
+            cancel: CancelToken::new(),
+            error: None,
+        }
+    }
+
+    pub fn selected_write_mode(&self) -> usize {
+        WRITE_MODES
+            .iter()
+            .position(|w| *w == self.write)
+            .unwrap_or(0)
+    }
+
+    pub fn update(&mut self, message: BenchmarkMessage) -> Task<Message> {
+        match message {
+            BenchmarkMessage::WriteUpdate(index) => {
+                if !self.running {
+                    self.write = WRITE_MODES.get(index).copied().unwrap_or_default();
+                }
+            }
+            BenchmarkMessage::Progress(result) => self.result = Some(result),
+            BenchmarkMessage::Finished(result) => {
+                self.running = false;
+                match result {
+                    Ok(result) => self.result = Some(result),
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            BenchmarkMessage::Cancel => self.cancel.cancel(),
+            BenchmarkMessage::Start => {
+                if self.running {
+                    return Task::none();
+                }
+
+                self.running = true;
+                self.result = None;
+                self.error = None;
+                self.cancel = CancelToken::new();
+
+                let drive = self.drive.clone();
+                let cancel = self.cancel.clone();
+                let options = BenchmarkOptions {
+                    write: self.write,
+                    ..Default::default()
+                };
+
+                return run_with_progress(
+                    move |progress| async move {
+                        drive
+                            .benchmark_with_progress(options, progress, cancel)
+                            .await
+                    },
+                    |progress| BenchmarkMessage::Progress(progress).into(),
+                    |result| BenchmarkMessage::Finished(result).into(),
+                );
+            }
+        }
+
+        Task::none()
+    }
+}
@@ -1,52 +1,107 @@
-use super::volumes::CreateMessage;
+#[cfg(feature = "dbus")]
+use super::benchmark::{BenchmarkDialog, BenchmarkMessage, WRITE_MODE_NAMES};
+#[cfg(feature = "dbus")]
+use super::drive_settings::{DriveSettingsDialog, DriveSettingsMessage};
+use super::format_disk::{FormatDiskDialog, FormatDiskMessage, TABLE_TYPE_NAMES};
+#[cfg(feature = "dbus")]
+use super::image::{ImageDialog, ImageMessage, ImageOperation};
+use super::plan::PlanState;
+#[cfg(feature = "dbus")]
+use super::smart::{SmartDialog, SmartMessage};
+use super::volumes::{CreateInfo, CreateMessage, DeleteInfo, UnlockInfo, VolumesControlMessage};
+#[cfg(feature = "dbus")]
+use crate::utils::labelled_info;
 use crate::utils::labelled_spinner;
 use crate::{app::Message, utils};
+use cosmic::widget::Space;
 use cosmic::{
     Element,
-    iced::{Length, wgpu::util},
+    iced::wgpu::util,
     iced_widget,
-    widget::{
-        button, checkbox, container, dialog, dropdown, slider, spin_button, text_input, toggler,
-    },
+    widget::{button, checkbox, dialog, dropdown, slider, spin_button, text, text_input, toggler},
 };
-use hardware::bytes_to_pretty;
-use hardware::{COMMON_PARTITION_NAMES, CreatePartitionInfo, PARTITION_NAMES};
-use std::borrow::Cow;
-
-pub fn confirmation<'a>(
-    title: impl Into<Cow<'a, str>>,
-    prompt: impl Into<Cow<'a, str>>,
-    ok_message: Message,
-    cancel_message: Option<Message>,
-) -> Element<'a, Message> {
-    let mut dialog = dialog::dialog()
-        .title(title)
-        .body(prompt)
-        .primary_action(button::destructive("Ok").on_press(ok_message.into()));
+#[cfg(feature = "dbus")]
+use cosmic::{
+    iced::{Alignment, Background, Length},
+    widget::{container, progress_bar, scrollable},
+};
+use hardware::ByteSize;
+#[cfg(feature = "dbus")]
+use hardware::{
+    AAM_LEVEL_MAX, AAM_LEVEL_MIN, APM_DISABLED, APM_LEVEL_MIN, SelfTestStatus, SelfTestType,
+    SmartAssessment, SmartAttribute, ThroughputSample, standby_timeout_seconds,
+};
+use hardware::{FILESYSTEM_NAMES, FILESYSTEM_TYPES, PartitionTypeList};
 
-    match cancel_message {
-        Some(c) => dialog = dialog.secondary_action(button::standard("Cancel").on_press(c.into())),
-        None => {}
-    };
+/// Lists the changes an operation will make, for the user to confirm.
+fn plan<'a>(plan: &PlanState) -> Element<'a, Message> {
+    match plan {
+        PlanState::Loading => text("Checking what will change...").into(),
+        PlanState::Failed(e) => text(format!("This can't be done: {e}")).into(),
+        PlanState::Ready(plan) => {
+            let mut changes =
+                iced_widget::column![text::heading("The following changes will be made:")];
+            for (i, change) in plan.changes.iter().enumerate() {
+                changes = changes.push(text(format!("{}. {change}", i + 1)));
+            }
 
-    dialog.into()
+            changes.spacing(4.).into()
+        }
+    }
 }
 
-pub fn create_partition<'a>(create: CreatePartitionInfo) -> Element<'a, Message> {
+pub fn delete_partition<'a>(delete: DeleteInfo) -> Element<'a, Message> {
+    let mut confirm = button::destructive("Delete");
+    if delete.plan.is_ready() {
+        confirm = confirm.on_press(VolumesControlMessage::Delete.into());
+    }
+
+    dialog::dialog()
+        .title(format!("Delete {}", delete.name))
+        .control(plan(&delete.plan))
+        .primary_action(confirm)
+        .secondary_action(button::standard("Cancel").on_press(Message::CloseDialog))
+        .into()
+}
+
+pub fn create_partition<'a>(create: CreateInfo) -> Element<'a, Message> {
+    //Once the user continues, the changes are reviewed before anything is created.
+    if let Some(plan_state) = &create.plan {
+        let mut confirm = button::destructive("Create");
+        if plan_state.is_ready() {
+            confirm = confirm.on_press(CreateMessage::Partition(create.info.clone()).into());
+        }
+
+        return dialog::dialog()
+            .title("Create Partition")
+            .control(plan(plan_state))
+            .primary_action(confirm)
+            .secondary_action(button::standard("Back").on_press(CreateMessage::Back.into()))
+            .into();
+    }
+
+    let table_type = create.table_type;
+    let create = create.info;
     let len = create.max_size as f64;
 
     let size = create.size as f64;
     let free = len - size;
     let free_bytes = free as u64;
 
-    let size_pretty = bytes_to_pretty(&create.size, false);
-    let free_pretty = bytes_to_pretty(&free_bytes, false);
-    let step = hardware::get_step(&create.size);
+    let size_pretty = ByteSize(create.size).pretty();
+    let free_pretty = ByteSize(free_bytes).pretty();
+    let step = ByteSize(create.size).step() as f64;
 
     println!("step: {}", step);
 
     let create_clone = create.clone();
 
+    let filesystem_index = FILESYSTEM_TYPES
+        .iter()
+        .position(|f| f.filesystem == create.filesystem);
+    let partition_types = PartitionTypeList::create(&table_type);
+    let partition_type_index = partition_types.position(create.partition_type_for(&table_type));
+
     let mut content = iced_widget::column![
         text_input("Volume name", create_clone.name)
             .label("Volume Name")
@@ -65,16 +120,28 @@ pub fn create_partition<'a>(create: CreatePartitionInfo) -> Element<'a, Message>
         toggler(create_clone.erase)
             .label("Erase")
             .on_toggle(|v| CreateMessage::EraseUpdate(v).into()),
-        dropdown(
-            &*COMMON_PARTITION_NAMES,
-            Some(create_clone.selected_partitition_type),
-            |v| CreateMessage::PartitionTypeUpdate(v).into()
-        ),
-        checkbox("Password Protected", create.password_protected)
-            .on_toggle(|v| CreateMessage::PasswordProectedUpdate(v).into()),
+        dropdown(&*FILESYSTEM_NAMES, filesystem_index, |v| {
+            CreateMessage::FilesystemUpdate(v).into()
+        }),
     ];
 
-    if create.password_protected {
+    //Only tables with known partition types offer a choice, others get the backend's default.
+    if !partition_types.types.is_empty() {
+        content = content.push(dropdown(
+            &partition_types.names,
+            partition_type_index,
+            |v| CreateMessage::PartitionTypeUpdate(v).into(),
+        ));
+    }
+
+    if create.filesystem.info().options.encrypt {
+        content = content.push(
+            checkbox("Password Protected", create.password_protected)
+                .on_toggle(|v| CreateMessage::PasswordProectedUpdate(v).into()),
+        );
+    }
+
+    if create.is_encrypted() {
         content = content.push(
             text_input::secure_input("", create_clone.password, None, true)
                 .label("Password")
@@ -88,11 +155,11 @@ pub fn create_partition<'a>(create: CreatePartitionInfo) -> Element<'a, Message>
         );
     }
 
-    let mut continue_button = button::destructive("Continue");
+    let mut continue_button = button::suggested("Continue");
 
     // if create.can_continue
     //{
-    continue_button = continue_button.on_press(CreateMessage::Partition(create).into());
+    continue_button = continue_button.on_press(CreateMessage::Continue.into());
     //}
 
     dialog::dialog()
@@ -102,3 +169,501 @@ pub fn create_partition<'a>(create: CreatePartitionInfo) -> Element<'a, Message>
         .secondary_action(button::standard("Cancel").on_press(CreateMessage::Cancel.into()))
         .into()
 }
+
+pub fn unlock_partition<'a>(unlock: UnlockInfo) -> Element<'a, Message> {
+    let content = iced_widget::column![
+        text_input::secure_input("", unlock.passphrase, None, true)
+            .label("Passphrase")
+            .on_input(|v| VolumesControlMessage::UnlockPassphraseUpdate(v).into()),
+    ];
+
+    dialog::dialog()
+        .title(format!("Unlock {}", unlock.name))
+        .control(content.spacing(20.))
+        .primary_action(button::suggested("Unlock").on_press(VolumesControlMessage::Unlock.into()))
+        .secondary_action(button::standard("Cancel").on_press(Message::CloseDialog))
+        .into()
+}
+
+#[cfg(feature = "dbus")]
+pub fn disk_image<'a>(image: ImageDialog) -> Element<'a, Message> {
+    let running = image.is_running();
+    let title = image.title();
+
+    let mut path = text_input("", image.path.clone()).label("Image File");
+    if !running {
+        path = path.on_input(|v| ImageMessage::PathUpdate(v).into());
+    }
+
+    let mut content = iced_widget::column![path];
+
+    if image.operation == ImageOperation::Attach {
+        let mut read_only = toggler(image.read_only).label("Read Only");
+        if !running {
+            read_only = read_only.on_toggle(|v| ImageMessage::ReadOnlyUpdate(v).into());
+        }
+        content = content.push(read_only);
+    }
+
+    if image.operation == ImageOperation::Restore {
+        content = content.push(text(format!(
+            "All data on {} will be overwritten. Mounted filesystems will be unmounted first.",
+            image.target_name()
+        )));
+    }
+
+    if image.operation == ImageOperation::New {
+        let size = image.size as f64;
+        let step = ByteSize(image.size).step() as f64;
+
+        content = content.push(labelled_spinner(
+            "Image Size",
+            ByteSize(image.size).pretty(),
+            size,
+            step,
+            0.,
+            f64::MAX,
+            |v| ImageMessage::SizeUpdate(v as u64).into(),
+        ));
+    }
+
+    if let Some(progress) = image.progress {
+        content = content.push(progress_bar(0.0..=1.0, progress.fraction()));
+        content = content.push(text(format!(
+            "{} of {}",
+            ByteSize(progress.bytes_done).pretty(),
+            ByteSize(progress.total_bytes).pretty()
+        )));
+    }
+
+    match &image.result {
+        Some(Ok(message)) => content = content.push(text(message.clone())),
+        Some(Err(e)) => content = content.push(text(format!("Error: {}", e))),
+        None => {}
+    }
+
+    let mut start = match image.operation {
+        ImageOperation::Restore => button::destructive("Restore"),
+        ImageOperation::Attach => button::suggested("Attach"),
+        _ => button::suggested("Start"),
+    };
+    if !running && image.result.is_none() && !image.path.is_empty() {
+        start = start.on_press(ImageMessage::Start.into());
+    }
+
+    let cancel = match running {
+        true => button::standard("Cancel").on_press(ImageMessage::Cancel.into()),
+        false => button::standard("Close").on_press(Message::CloseDialog),
+    };
+
+    dialog::dialog()
+        .title(title)
+        .control(content.spacing(20.))
+        .primary_action(start)
+        .secondary_action(cancel)
+        .into()
+}
+
+#[cfg(feature = "dbus")]
+pub fn smart_data<'a>(smart: SmartDialog) -> Element<'a, Message> {
+    let title = format!("SMART Data for {}", smart.drive.pretty_name());
+
+    let report = match smart.report {
+        None => {
+            return dialog::dialog()
+                .title(title)
+                .body("Reading SMART data...")
+                .secondary_action(button::standard("Close").on_press(Message::CloseDialog))
+                .into();
+        }
+        Some(Err(e)) => {
+            return dialog::dialog()
+                .title(title)
+                .body(format!("Error: {}", e))
+                .secondary_action(button::standard("Close").on_press(Message::CloseDialog))
+                .into();
+        }
+        Some(Ok(report)) => report,
+    };
+
+    let assessment = match report.assessment {
+        SmartAssessment::Healthy => "Disk is OK",
+        SmartAssessment::Warning => "Disk is OK, but has some problems",
+        SmartAssessment::Failing => "DISK IS LIKELY TO FAIL SOON",
+    };
+
+    let optional = |value: Option<String>| value.unwrap_or("Unknown".into());
+
+    let self_test = match &report.self_test {
+        SelfTestStatus::NotRun => "Never run".into(),
+        SelfTestStatus::InProgress(remaining) => format!("In progress, {}% remaining", remaining),
+        SelfTestStatus::Passed => "Passed".into(),
+        SelfTestStatus::Aborted => "Aborted".into(),
+        SelfTestStatus::Failed(reason) => format!("Failed ({})", reason),
+    };
+
+    let mut attributes = iced_widget::column![smart_attribute_row(
+        "ID".into(),
+        "Attribute".into(),
+        "Value".into(),
+        "Worst".into(),
+        "Threshold".into(),
+        "Raw".into(),
+    )]
+    .spacing(5);
+
+    for attribute in &report.attributes {
+        attributes = attributes.push(smart_attribute(attribute));
+    }
+
+    let self_test_buttons = match report.self_test {
+        SelfTestStatus::InProgress(_) => iced_widget::row![
+            button::destructive("Abort Self-Test").on_press(SmartMessage::AbortSelfTest.into())
+        ],
+        _ => iced_widget::row![
+            button::standard("Short Self-Test")
+                .on_press(SmartMessage::StartSelfTest(SelfTestType::Short).into()),
+            button::standard("Extended Self-Test")
+                .on_press(SmartMessage::StartSelfTest(SelfTestType::Extended).into()),
+        ],
+    };
+
+    let content = iced_widget::column![
+        labelled_info("Assessment", assessment),
+        labelled_info(
+            "Temperature",
+            optional(report.temperature_celsius.map(|t| format!("{:.0} °C", t)))
+        ),
+        labelled_info(
+            "Powered On",
+            optional(report.power_on_hours.map(|h| format!("{} hours", h)))
+        ),
+        labelled_info(
+            "Reallocated Sectors",
+            optional(report.reallocated_sectors.map(|s| s.to_string()))
+        ),
+        labelled_info("Self-Test", self_test),
+        self_test_buttons.spacing(10),
+        scrollable(attributes).height(Length::Fixed(300.)),
+    ];
+
+    dialog::dialog()
+        .title(title)
+        .control(content.spacing(10.))
+        .primary_action(button::suggested("Refresh").on_press(SmartMessage::Refresh.into()))
+        .secondary_action(button::standard("Close").on_press(Message::CloseDialog))
+        .into()
+}
+
+#[cfg(feature = "dbus")]
+fn smart_attribute<'a>(attribute: &SmartAttribute) -> Element<'a, Message> {
+    let optional = |value: Option<i32>| match value {
+        Some(v) => v.to_string(),
+        None => "N/A".into(),
+    };
+
+    let mut name = attribute.name.clone();
+    if attribute.failing {
+        name.push_str(" (FAILING)");
+    } else if attribute.failed_in_past {
+        name.push_str(" (failed in the past)");
+    }
+
+    smart_attribute_row(
+        attribute.id.map(|id| id.to_string()).unwrap_or_default(),
+        name,
+        optional(attribute.value),
+        optional(attribute.worst),
+        optional(attribute.threshold),
+        attribute.pretty.clone(),
+    )
+}
+
+#[cfg(feature = "dbus")]
+fn smart_attribute_row<'a>(
+    id: String,
+    name: String,
+    value: String,
+    worst: String,
+    threshold: String,
+    raw: String,
+) -> Element<'a, Message> {
+    iced_widget::row![
+        text(id).width(Length::FillPortion(1)),
+        text(name).width(Length::FillPortion(5)),
+        text(value).width(Length::FillPortion(1)),
+        text(worst).width(Length::FillPortion(1)),
+        text(threshold).width(Length::FillPortion(2)),
+        text(raw).width(Length::FillPortion(3)),
+    ]
+    .spacing(10)
+    .into()
+}
+
+/// Height of the benchmark throughput charts.
+#[cfg(feature = "dbus")]
+const CHART_HEIGHT: f32 = 120.;
+
+#[cfg(feature = "dbus")]
+pub fn benchmark<'a>(bench: BenchmarkDialog) -> Element<'a, Message> {
+    let write_mode = dropdown(&WRITE_MODE_NAMES, Some(bench.selected_write_mode()), |i| {
+        BenchmarkMessage::WriteUpdate(i).into()
+    });
+
+    let mut content = iced_widget::column![write_mode];
+
+    if let Some(result) = &bench.result {
+        let rate = |rate: Option<f64>| match rate {
+            Some(r) => format!("{}/s", ByteSize(r as u64).pretty()),
+            None => "-".into(),
+        };
+
+        let access_time = match result.average_access_time() {
+            Some(t) => format!("{:.2} ms", t * 1000.),
+            None => "-".into(),
+        };
+
+        content = content
+            .push(labelled_info(
+                "Average Read Rate",
+                rate(result.average_read_rate()),
+            ))
+            .push(throughput_chart(&result.read, result.size))
+            .push(labelled_info(
+                "Average Write Rate",
+                rate(result.average_write_rate()),
+            ))
+            .push(throughput_chart(&result.write, result.size))
+            .push(labelled_info("Average Access Time", access_time))
+            .push(text(format!("{} access samples", result.access.len())));
+    }
+
+    if let Some(e) = &bench.error {
+        content = content.push(text(format!("Error: {}", e)));
+    }
+
+    let mut start = button::suggested("Start Benchmark");
+    if !bench.running {
+        start = start.on_press(BenchmarkMessage::Start.into());
+    }
+
+    let cancel = match bench.running {
+        true => button::standard("Cancel").on_press(BenchmarkMessage::Cancel.into()),
+        false => button::standard("Close").on_press(Message::CloseDialog),
+    };
+
+    dialog::dialog()
+        .title(format!("Benchmark {}", bench.drive.pretty_name()))
+        .control(content.spacing(10.))
+        .primary_action(start)
+        .secondary_action(cancel)
+        .into()
+}
+
+/// A bar chart of transfer rate against position on the drive, scaled to the fastest sample.
+#[cfg(feature = "dbus")]
+fn throughput_chart<'a>(samples: &[ThroughputSample], size: u64) -> Element<'a, Message> {
+    let max = samples
+        .iter()
+        .map(|s| s.bytes_per_second)
+        .fold(0., f64::max);
+
+    let mut sorted = samples.to_vec();
+    sorted.sort_by_key(|s| s.offset);
+
+    let bars = sorted.iter().map(|sample| {
+        let height = match max > 0. {
+            true => (sample.bytes_per_second / max) as f32 * CHART_HEIGHT,
+            false => 0.,
+        };
+
+        container(Space::new(Length::Fill, Length::Fixed(height)))
+            .class(cosmic::style::Container::custom(|theme| {
+                iced_widget::container::Style {
+                    background: Some(Background::Color(theme.cosmic().accent_color().into())),
+                    ..Default::default()
+                }
+            }))
+            .width(Length::Fill)
+            .into()
+    });
+
+    let label = match sorted.last() {
+        Some(last) if size > 0 => format!("0% - {:.0}%", last.offset as f64 / size as f64 * 100.),
+        _ => String::new(),
+    };
+
+    iced_widget::column![
+        container(
+            iced_widget::Row::with_children(bars)
+                .spacing(1)
+                .align_y(Alignment::End)
+                .height(Length::Fixed(CHART_HEIGHT)),
+        )
+        .class(cosmic::style::Container::Card)
+        .width(Length::Fill),
+        text(label),
+    ]
+    .into()
+}
+
+/// The longest standby timeout that can be set, 5.5 hours.
+#[cfg(feature = "dbus")]
+const MAX_STANDBY_SECONDS: f64 = 5.5 * 60. * 60.;
+
+#[cfg(feature = "dbus")]
+pub fn drive_settings<'a>(settings: DriveSettingsDialog) -> Element<'a, Message> {
+    let title = format!("Drive Settings for {}", settings.drive.pretty_name());
+    let support = settings.support;
+    let values = settings.settings;
+
+    let mut content = iced_widget::column![];
+
+    if !settings.loaded {
+        content = content.push(text("Reading drive settings..."));
+    } else if !(support.standby || support.apm || support.aam || support.write_cache) {
+        content = content.push(text("This drive has no configurable settings."));
+    }
+
+    if support.standby {
+        content = content.push(
+            toggler(values.standby_timeout.is_some())
+                .label("Apply Standby Timeout")
+                .on_toggle(|v| DriveSettingsMessage::StandbyToggle(v).into()),
+        );
+
+        if let Some(timeout) = values.standby_timeout {
+            let seconds = standby_timeout_seconds(timeout).unwrap_or(0);
+            let pretty = match seconds {
+                0 => "Never".into(),
+                s if s < 60 => format!("{} seconds", s),
+                s => format!("{} minutes", s / 60),
+            };
+
+            content = content.push(labelled_spinner(
+                "Standby Timeout",
+                pretty,
+                seconds as f64,
+                60.,
+                0.,
+                MAX_STANDBY_SECONDS,
+                |v| DriveSettingsMessage::StandbyUpdate(v as u64).into(),
+            ));
+        }
+    }
+
+    if support.apm {
+        content = content.push(
+            toggler(values.apm_level.is_some())
+                .label("Apply Advanced Power Management")
+                .on_toggle(|v| DriveSettingsMessage::ApmToggle(v).into()),
+        );
+
+        if let Some(level) = values.apm_level {
+            let pretty = match level {
+                APM_DISABLED => "Disabled".into(),
+                l => l.to_string(),
+            };
+
+            content = content.push(labelled_spinner(
+                "APM Level",
+                pretty,
+                level as f64,
+                1.,
+                APM_LEVEL_MIN as f64,
+                APM_DISABLED as f64,
+                |v| DriveSettingsMessage::ApmUpdate(v as u8).into(),
+            ));
+        }
+    }
+
+    if support.aam {
+        content = content.push(
+            toggler(values.aam_level.is_some())
+                .label("Apply Automatic Acoustic Management")
+                .on_toggle(|v| DriveSettingsMessage::AamToggle(v).into()),
+        );
+
+        if let Some(level) = values.aam_level {
+            content = content.push(labelled_spinner(
+                "AAM Level",
+                level.to_string(),
+                level as f64,
+                1.,
+                AAM_LEVEL_MIN as f64,
+                AAM_LEVEL_MAX as f64,
+                |v| DriveSettingsMessage::AamUpdate(v as u8).into(),
+            ));
+        }
+    }
+
+    if support.write_cache {
+        content = content.push(
+            toggler(values.write_cache.is_some())
+                .label("Apply Write Cache Setting")
+                .on_toggle(|v| DriveSettingsMessage::WriteCacheToggle(v).into()),
+        );
+
+        if let Some(enabled) = values.write_cache {
+            content = content.push(
+                toggler(enabled)
+                    .label("Enable Write Cache")
+                    .on_toggle(|v| DriveSettingsMessage::WriteCacheUpdate(v).into()),
+            );
+        }
+    }
+
+    if let Some(e) = &settings.error {
+        content = content.push(text(format!("Error: {}", e)));
+    }
+
+    let mut save = button::suggested("Save");
+    if settings.loaded {
+        save = save.on_press(DriveSettingsMessage::Save.into());
+    }
+
+    dialog::dialog()
+        .title(title)
+        .control(content.spacing(10.))
+        .primary_action(save)
+        .secondary_action(button::standard("Cancel").on_press(Message::CloseDialog))
+        .into()
+}
+
+pub fn format_disk<'a>(format: FormatDiskDialog) -> Element<'a, Message> {
+    let table_type = dropdown(&TABLE_TYPE_NAMES, Some(format.table_type), |i| {
+        FormatDiskMessage::TableTypeUpdate(i).into()
+    });
+
+    let mut content = iced_widget::column![
+        table_type,
+        toggler(format.erase)
+            .label("Overwrite existing data with zeroes (slow)")
+            .on_toggle(|v| FormatDiskMessage::EraseUpdate(v).into()),
+        plan(&format.plan),
+    ];
+
+    if format.running {
+        content = content.push(text("Formatting..."));
+    }
+
+    if let Some(e) = &format.error {
+        content = content.push(text(format!("Error: {}", e)));
+    }
+
+    let mut confirm = button::destructive("Format");
+    let mut cancel = button::standard("Cancel");
+    if !format.running {
+        if format.plan.is_ready() {
+            confirm = confirm.on_press(FormatDiskMessage::Confirm.into());
+        }
+        cancel = cancel.on_press(Message::CloseDialog);
+    }
+
+    dialog::dialog()
+        .title(format!("Format {}", format.drive.pretty_name()))
+        .control(content.spacing(10.))
+        .primary_action(confirm)
+        .secondary_action(cancel)
+        .into()
+}
@@ -0,0 +1,126 @@
+use cosmic::app::Task;
+use hardware::disks::DriveModel;
+use hardware::{
+    AAM_LEVEL_MIN, Drive, DriveSettings, DriveSettingsSupport, standby_timeout_from_seconds,
+};
+
+use crate::app::Message;
+
+/// Values used when a setting is first turned on.
+const DEFAULT_STANDBY_SECONDS: u64 = 10 * 60;
+const DEFAULT_APM_LEVEL: u8 = 127;
+
+#[derive(Debug, Clone)]
+pub struct DriveSettingsDialog {
+    pub drive: DriveModel,
+    pub settings: DriveSettings,
+    pub support: DriveSettingsSupport,
+    pub loaded: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum DriveSettingsMessage {
+    Loaded(Result<(DriveSettings, DriveSettingsSupport), String>),
+    StandbyToggle(bool),
+    StandbyUpdate(u64),
+    ApmToggle(bool),
+    ApmUpdate(u8),
+    AamToggle(bool),
+    AamUpdate(u8),
+    WriteCacheToggle(bool),
+    WriteCacheUpdate(bool),
+    Save,
+    Saved(Result<(), String>),
+}
+
+impl Into<Message> for DriveSettingsMessage {
+    fn into(self) -> Message {
+        Message::DriveSettingsMessage(self)
+    }
+}
+
+impl DriveSettingsDialog {
+    pub fn new(drive: DriveModel) -> Self {
+        Self {
+            drive,
+            settings: DriveSettings::default(),
+            support: DriveSettingsSupport::default(),
+            loaded: false,
+            error: None,
+        }
+    }
+
+    pub fn update(&mut self, message: DriveSettingsMessage) -> Task<Message> {
+        let settings = &mut self.settings;
+
+        match message {
+            DriveSettingsMessage::Loaded(result) => {
+                self.loaded = true;
+                match result {
+                    Ok((settings, support)) => {
+                        self.settings = settings;
+                        self.support = support;
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            DriveSettingsMessage::StandbyToggle(enabled) => {
+                settings.standby_timeout =
+                    enabled.then(|| standby_timeout_from_seconds(DEFAULT_STANDBY_SECONDS));
+            }
+            DriveSettingsMessage::StandbyUpdate(seconds) => {
+                settings.standby_timeout = Some(standby_timeout_from_seconds(seconds));
+            }
+            DriveSettingsMessage::ApmToggle(enabled) => {
+                settings.apm_level = enabled.then_some(DEFAULT_APM_LEVEL);
+            }
+            DriveSettingsMessage::ApmUpdate(level) => settings.apm_level = Some(level),
+            DriveSettingsMessage::AamToggle(enabled) => {
+                let recommended = self.support.aam_vendor_recommended.unwrap_or(AAM_LEVEL_MIN);
+                settings.aam_level = enabled.then_some(recommended);
+            }
+            DriveSettingsMessage::AamUpdate(level) => settings.aam_level = Some(level),
+            DriveSettingsMessage::WriteCacheToggle(enabled) => {
+                settings.write_cache = enabled.then_some(true);
+            }
+            DriveSettingsMessage::WriteCacheUpdate(enabled) => settings.write_cache = Some(enabled),
+            DriveSettingsMessage::Save => {
+                let drive = self.drive.clone();
+                let settings = self.settings;
+
+                return Task::perform(
+                    async move { drive.set_drive_settings(settings).await },
+                    |result| {
+                        Message::DriveSettingsMessage(DriveSettingsMessage::Saved(
+                            result.map_err(|e| e.to_string()),
+                        ))
+                        .into()
+                    },
+                );
+            }
+            DriveSettingsMessage::Saved(result) => match result {
+                Ok(_) => return cosmic::task::message(cosmic::Action::App(Message::CloseDialog)),
+                Err(e) => self.error = Some(e),
+            },
+        }
+
+        Task::none()
+    }
+}
+
+pub fn load_settings(drive: DriveModel) -> Task<Message> {
+    Task::perform(
+        async move {
+            let settings = drive.drive_settings().await?;
+            let support = drive.settings_support().await?;
+            anyhow::Ok((settings, support))
+        },
+        |result| {
+            Message::DriveSettingsMessage(DriveSettingsMessage::Loaded(
+                result.map_err(|e| e.to_string()),
+            ))
+            .into()
+        },
+    )
+}
@@ -0,0 +1,141 @@
+use cosmic::app::Task;
+use hardware::{Backend, ChangePlan, DiskBackend, EraseMode};
+
+use super::plan::PlanState;
+use crate::app::{DriveModel, Message};
+
+pub static TABLE_TYPES: [&str; 3] = ["gpt", "dos", ""];
+
+pub static TABLE_TYPE_NAMES: [&str; 3] = [
+    "GUID Partition Table (GPT)",
+    "Master Boot Record (MBR / DOS)",
+    "No partition table",
+];
+
+#[derive(Debug, Clone)]
+pub struct FormatDiskDialog {
+    pub drive: DriveModel,
+    pub table_type: usize,
+    pub erase: bool,
+    pub plan: PlanState,
+    pub running: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum FormatDiskMessage {
+    TableTypeUpdate(usize),
+    EraseUpdate(bool),
+    /// The plan for the table type and erase setting it was worked out for.
+    PlanLoaded(usize, bool, Result<ChangePlan, String>),
+    Confirm,
+    Finished(Result<(), String>),
+}
+
+impl Into<Message> for FormatDiskMessage {
+    fn into(self) -> Message {
+        Message::FormatDiskMessage(self)
+    }
+}
+
+impl FormatDiskDialog {
+    pub fn new(drive: DriveModel) -> Self {
+        Self {
+            drive,
+            table_type: 0,
+            erase: false,
+            plan: PlanState::Loading,
+            running: false,
+            error: None,
+        }
+    }
+
+    pub fn update(&mut self, message: FormatDiskMessage) -> Task<Message> {
+        match message {
+            FormatDiskMessage::TableTypeUpdate(index) => {
+                if !self.running && index < TABLE_TYPES.len() {
+                    self.table_type = index;
+                    return self.load_plan();
+                }
+            }
+            FormatDiskMessage::EraseUpdate(erase) => {
+                if !self.running {
+                    self.erase = erase;
+                    return self.load_plan();
+                }
+            }
+            FormatDiskMessage::PlanLoaded(table_type, erase, plan) => {
+                //A plan for options that have since changed is stale.
+                if table_type == self.table_type && erase == self.erase {
+                    self.plan = plan.into();
+                }
+            }
+            FormatDiskMessage::Confirm => {
+                if self.running || !self.plan.is_ready() {
+                    return Task::none();
+                }
+
+                self.running = true;
+                self.error = None;
+
+                let drive = self.drive.clone();
+                let table_type = TABLE_TYPES[self.table_type].to_owned();
+                let erase = EraseMode::from(self.erase);
+
+                return Task::perform(
+                    async move {
+                        let backend = Backend::connect().await?;
+                        backend.format_disk(&drive, table_type, erase).await
+                    },
+                    |result| {
+                        Message::FormatDiskMessage(FormatDiskMessage::Finished(
+                            result.map_err(|e| e.to_string()),
+                        ))
+                        .into()
+                    },
+                );
+            }
+            FormatDiskMessage::Finished(result) => {
+                self.running = false;
+                match result {
+                    Ok(()) => {
+                        return cosmic::task::message(cosmic::Action::App(Message::CloseDialog));
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+        }
+
+        Task::none()
+    }
+
+    /// Works out what formatting with the current options would change.
+    pub fn load_plan(&mut self) -> Task<Message> {
+        self.plan = PlanState::Loading;
+
+        let drive = self.drive.clone();
+        let table_type = self.table_type;
+        let erase = self.erase;
+
+        Task::perform(
+            async move {
+                Backend::connect()
+                    .await?
+                    .plan_format_disk(
+                        &drive,
+                        TABLE_TYPES[table_type].to_owned(),
+                        EraseMode::from(erase),
+                    )
+                    .await
+            },
+            move |result| {
+                Message::FormatDiskMessage(FormatDiskMessage::PlanLoaded(
+                    table_type,
+                    erase,
+                    result.map_err(|e| e.to_string()),
+                ))
+                .into()
+            },
+        )
+    }
+}
@@ -0,0 +1,204 @@
+use cosmic::app::Task;
+use hardware::disks::{DriveModel, PartitionModel};
+use hardware::{CancelToken, CopyProgress, Drive, Partition, create_blank_image};
+
+use crate::app::Message;
+use crate::utils::run_with_progress;
+
+/// Default size of a new, blank disk image.
+const DEFAULT_IMAGE_SIZE: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageOperation {
+    /// Create an empty disk image.
+    New,
+    /// Image the selected drive to a file.
+    CreateFrom,
+    /// Write an image file over the selected drive or partition.
+    Restore,
+    /// Attach an image file as a loop device.
+    Attach,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageDialog {
+    pub operation: ImageOperation,
+    pub drive: Option<DriveModel>,
+    /// Set when restoring onto a single partition rather than the whole drive.
+    pub partition: Option<PartitionModel>,
+    pub path: String,
+    pub size: u64,
+    pub read_only: bool,
+    pub progress: Option<CopyProgress>,
+    pub cancel: CancelToken,
+    pub result: Option<Result<String, String>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ImageMessage {
+    PathUpdate(String),
+    SizeUpdate(u64),
+    ReadOnlyUpdate(bool),
+    Start,
+    Progress(CopyProgress),
+    Finished(Result<String, String>),
+    Cancel,
+}
+
+impl Into<Message> for ImageMessage {
+    fn into(self) -> Message {
+        Message::ImageMessage(self)
+    }
+}
+
+impl ImageDialog {
+    pub fn new(operation: ImageOperation, drive: Option<DriveModel>) -> Self {
+        let name = match &drive {
+            Some(d) => d.pretty_name().replace(" ", "_"),
+            None => "disk".into(),
+        };
+
+        let dir = std::env::var("HOME").unwrap_or_else(|_| ".".into());
+
+        let path = match operation {
+            ImageOperation::Restore | ImageOperation::Attach => String::new(),
+            _ => format!("{}/{}.img", dir, name),
+        };
+
+        Self {
+            operation,
+            drive,
+            partition: None,
+            path,
+            size: DEFAULT_IMAGE_SIZE,
+            read_only: true,
+            progress: None,
+            cancel: CancelToken::new(),
+            result: None,
+        }
+    }
+
+    pub fn restore_partition(partition: PartitionModel) -> Self {
+        Self {
+            partition: Some(partition),
+            ..Self::new(ImageOperation::Restore, None)
+        }
+    }
+
+    /// The name of the drive or partition being imaged or restored.
+    pub fn target_name(&self) -> String {
+        match (&self.partition, &self.drive) {
+            (Some(p), _) => p.name(),
+            (None, Some(d)) => d.pretty_name(),
+            (None, None) => String::new(),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self.operation {
+            ImageOperation::New => "New Disk Image".into(),
+            ImageOperation::CreateFrom => match &self.drive {
+                Some(d) => format!("Create Disk Image From {}", d.pretty_name()),
+                None => "Create Disk Image".into(),
+            },
+            ImageOperation::Restore => format!("Restore Disk Image to {}", self.target_name()),
+            ImageOperation::Attach => "Attach Disk Image".into(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.progress.is_some() && self.result.is_none()
+    }
+
+    pub fn update(&mut self, message: ImageMessage) -> Task<Message> {
+        match message {
+            ImageMessage::PathUpdate(path) => self.path = path,
+            ImageMessage::SizeUpdate(size) => self.size = size,
+            ImageMessage::ReadOnlyUpdate(read_only) => self.read_only = read_only,
+            ImageMessage::Progress(progress) => self.progress = Some(progress),
+            ImageMessage::Finished(result) => self.result = Some(result),
+            ImageMessage::Cancel => self.cancel.cancel(),
+            ImageMessage::Start => {
+                if self.is_running() {
+                    return Task::none();
+                }
+
+                self.result = None;
+                self.cancel = CancelToken::new();
+                self.progress = Some(CopyProgress::default());
+
+                let path = self.path.clone();
+
+                match self.operation {
+                    ImageOperation::New => {
+                        let size = self.size;
+                        return Task::perform(
+                            async move {
+                                match create_blank_image(&path, size) {
+                                    Ok(_) => Ok(format!("Created {}", path)),
+                                    Err(e) => Err(e.to_string()),
+                                }
+                            },
+                            |result| Message::ImageMessage(ImageMessage::Finished(result)).into(),
+                        );
+                    }
+                    ImageOperation::CreateFrom => {
+                        let drive = match self.drive.clone() {
+                            Some(d) => d,
+                            None => return Task::none(),
+                        };
+                        let cancel = self.cancel.clone();
+
+                        return run_with_progress(
+                            move |progress| async move {
+                                drive
+                                    .create_image_with_progress(path, progress, cancel)
+                                    .await
+                                    .map(|image| format!("SHA-256: {}", image.sha256))
+                            },
+                            |progress| ImageMessage::Progress(progress).into(),
+                            |result| ImageMessage::Finished(result).into(),
+                        );
+                    }
+                    ImageOperation::Attach => {
+                        let read_only = self.read_only;
+                        return Task::perform(
+                            async move {
+                                match DriveModel::attach_image(&path, read_only).await {
+                                    Ok(block_path) => Ok(format!("Attached as {}", block_path)),
+                                    Err(e) => Err(e.to_string()),
+                                }
+                            },
+                            |result| Message::ImageMessage(ImageMessage::Finished(result)).into(),
+                        );
+                    }
+                    ImageOperation::Restore => {
+                        let drive = self.drive.clone();
+                        let partition = self.partition.clone();
+                        let cancel = self.cancel.clone();
+
+                        return run_with_progress(
+                            move |progress| async move {
+                                let image = match (partition, drive) {
+                                    (Some(p), _) => {
+                                        p.restore_image_with_progress(path, progress, cancel).await
+                                    }
+                                    (None, Some(d)) => {
+                                        d.restore_image_with_progress(path, progress, cancel).await
+                                    }
+                                    (None, None) => return Ok(String::new()),
+                                }?;
+
+                                Ok(format!("Restored and verified, SHA-256: {}", image.sha256))
+                            },
+                            |progress| ImageMessage::Progress(progress).into(),
+                            |result| ImageMessage::Finished(result).into(),
+                        );
+                    }
+                }
+            }
+        }
+
+        Task::none()
+    }
+}
@@ -18,35 +18,50 @@ pub fn menu_view(
         key_binds,
         MENU_ID.clone(),
         Message::Surface,
+        menus(),
+    )]
+}
+
+/// Imaging, SMART, benchmarks and drive settings are only available through UDisks2.
+fn menus() -> Vec<(&'static str, Vec<menu::Item<MenuAction, &'static str>>)> {
+    let mut menus = vec![];
+
+    #[cfg(feature = "dbus")]
+    menus.push((
+        "Image",
         vec![
-            (
-                "Image".into(),
-                vec![
-                    menu::Item::Button("New Disk Image", None, MenuAction::NewDiskImage),
-                    menu::Item::Button("Attach Disk Image", None, MenuAction::AttachDisk),
-                    menu::Item::Button("Create Disk From Drive", None, MenuAction::CreateDiskFrom),
-                    menu::Item::Button("Restore Image to Drive", None, MenuAction::RestoreImageTo),
-                ],
-            ),
-            (
-                "Disk".into(),
-                vec![
-                    menu::Item::Button("Eject", None, MenuAction::Eject),
-                    menu::Item::Button("Power Off", None, MenuAction::PowerOff),
-                    menu::Item::Button("Format Disk", None, MenuAction::Format),
-                    menu::Item::Button("Benchmark Disk", None, MenuAction::Benchmark),
-                    menu::Item::Button("SMART Data & Self-Tests", None, MenuAction::SmartData),
-                    menu::Item::Button("Drive Settings", None, MenuAction::DriveSettings),
-                    menu::Item::Button("Standby Now", None, MenuAction::StandbyNow),
-                    menu::Item::Button("Wake-up From Standby", None, MenuAction::Wakeup),
-                ],
-            ),
-            (
-                "View".into(),
-                vec![menu::Item::Button("about", None, MenuAction::About)],
-            ),
+            menu::Item::Button("New Disk Image", None, MenuAction::NewDiskImage),
+            menu::Item::Button("Attach Disk Image", None, MenuAction::AttachDisk),
+            menu::Item::Button("Create Disk From Drive", None, MenuAction::CreateDiskFrom),
+            menu::Item::Button("Restore Image to Drive", None, MenuAction::RestoreImageTo),
         ],
-    )]
+    ));
+
+    let mut disk = vec![
+        menu::Item::Button("Eject", None, MenuAction::Eject),
+        menu::Item::Button("Power Off", None, MenuAction::PowerOff),
+        menu::Item::Button("Format Disk", None, MenuAction::Format),
+    ];
+
+    #[cfg(feature = "dbus")]
+    disk.extend([
+        menu::Item::Button("Benchmark Disk", None, MenuAction::Benchmark),
+        menu::Item::Button("SMART Data & Self-Tests", None, MenuAction::SmartData),
+        menu::Item::Button("Drive Settings", None, MenuAction::DriveSettings),
+    ]);
+
+    disk.extend([
+        menu::Item::Button("Standby Now", None, MenuAction::StandbyNow),
+        menu::Item::Button("Wake-up From Standby", None, MenuAction::Wakeup),
+    ]);
+
+    menus.push(("Disk", disk));
+    menus.push((
+        "View",
+        vec![menu::Item::Button("about", None, MenuAction::About)],
+    ));
+
+    menus
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -55,14 +70,21 @@ pub enum MenuAction {
     Eject,
     PowerOff,
     Format,
+    #[cfg(feature = "dbus")]
     Benchmark,
+    #[cfg(feature = "dbus")]
     SmartData,
+    #[cfg(feature = "dbus")]
     DriveSettings,
     StandbyNow,
     Wakeup,
+    #[cfg(feature = "dbus")]
     NewDiskImage,
+    #[cfg(feature = "dbus")]
     AttachDisk,
+    #[cfg(feature = "dbus")]
     CreateDiskFrom,
+    #[cfg(feature = "dbus")]
     RestoreImageTo,
 }
 
@@ -75,14 +97,21 @@ impl menu::action::MenuAction for MenuAction {
             MenuAction::Eject => Message::Eject,
             MenuAction::PowerOff => Message::PowerOff,
             MenuAction::Format => Message::Format,
+            #[cfg(feature = "dbus")]
             MenuAction::Benchmark => Message::Benchmark,
+            #[cfg(feature = "dbus")]
             MenuAction::SmartData => Message::SmartData,
+            #[cfg(feature = "dbus")]
             MenuAction::DriveSettings => Message::DriveSettings,
             MenuAction::StandbyNow => Message::StandbyNow,
             MenuAction::Wakeup => Message::Wakeup,
+            #[cfg(feature = "dbus")]
             MenuAction::NewDiskImage => Message::NewDiskImage,
+            #[cfg(feature = "dbus")]
             MenuAction::AttachDisk => Message::AttachDisk,
+            #[cfg(feature = "dbus")]
             MenuAction::CreateDiskFrom => Message::CreateDiskFrom,
+            #[cfg(feature = "dbus")]
             MenuAction::RestoreImageTo => Message::RestoreImageTo,
         }
     }
@@ -1,4 +1,14 @@
 pub(crate) mod about;
+#[cfg(feature = "dbus")]
+pub(crate) mod benchmark;
 pub(crate) mod dialogs;
+#[cfg(feature = "dbus")]
+pub(crate) mod drive_settings;
+pub(crate) mod format_disk;
+#[cfg(feature = "dbus")]
+pub(crate) mod image;
 pub(crate) mod menu;
+pub(crate) mod plan;
+#[cfg(feature = "dbus")]
+pub(crate) mod smart;
 pub(crate) mod volumes;
@@ -0,0 +1,27 @@
+use hardware::ChangePlan;
+
+/// The changes a destructive operation would make, shown in its confirmation dialog. Nothing can
+/// be confirmed until the plan is ready.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PlanState {
+    #[default]
+    Loading,
+    Ready(ChangePlan),
+    /// The backend refused the operation.
+    Failed(String),
+}
+
+impl PlanState {
+    pub fn is_ready(&self) -> bool {
+        matches!(self, PlanState::Ready(_))
+    }
+}
+
+impl From<Result<ChangePlan, String>> for PlanState {
+    fn from(result: Result<ChangePlan, String>) -> Self {
+        match result {
+            Ok(plan) => PlanState::Ready(plan),
+            Err(e) => PlanState::Failed(e),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use cosmic::app::Task;
+use hardware::disks::DriveModel;
+use hardware::{Drive, SelfTestType, SmartReport};
+
+use crate::app::Message;
+
+#[derive(Debug, Clone)]
+pub struct SmartDialog {
+    pub drive: DriveModel,
+    /// `None` until the first report has been read.
+    pub report: Option<Result<SmartReport, String>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SmartMessage {
+    Refresh,
+    Report(Result<SmartReport, String>),
+    StartSelfTest(SelfTestType),
+    AbortSelfTest,
+}
+
+impl Into<Message> for SmartMessage {
+    fn into(self) -> Message {
+        Message::SmartMessage(self)
+    }
+}
+
+impl SmartDialog {
+    pub fn new(drive: DriveModel) -> Self {
+        Self {
+            drive,
+            report: None,
+        }
+    }
+
+    pub fn update(&mut self, message: SmartMessage) -> Task<Message> {
+        let drive = self.drive.clone();
+
+        match message {
+            SmartMessage::Report(report) => self.report = Some(report),
+            SmartMessage::Refresh => return read_report(drive),
+            SmartMessage::StartSelfTest(test) => {
+                return Task::perform(
+                    async move {
+                        match drive.start_self_test(test).await {
+                            Ok(_) => drive.smart_report().await,
+                            Err(e) => Err(e),
+                        }
+                    },
+                    |result| report_message(result),
+                );
+            }
+            SmartMessage::AbortSelfTest => {
+                return Task::perform(
+                    async move {
+                        match drive.abort_self_test().await {
+                            Ok(_) => drive.smart_report().await,
+                            Err(e) => Err(e),
+                        }
+                    },
+                    |result| report_message(result),
+                );
+            }
+        }
+
+        Task::none()
+    }
+}
+
+pub fn read_report(drive: DriveModel) -> Task<Message> {
+    Task::perform(async move { drive.smart_report().await }, |result| {
+        report_message(result)
+    })
+}
+
+fn report_message(result: anyhow::Result<SmartReport>) -> cosmic::Action<Message> {
+    Message::SmartMessage(SmartMessage::Report(result.map_err(|e| e.to_string()))).into()
+}
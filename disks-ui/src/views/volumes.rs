@@ -9,17 +9,27 @@ use cosmic::{
     },
 };
 
-use crate::app::{Message, ShowDialog};
-use hardware::bytes_to_pretty;
-use hardware::disks::{DriveModel, PartitionModel};
-use hardware::{CreatePartitionInfo, Drive, Partition};
+use crate::app::{DriveModel, Message, PartitionModel, ShowDialog};
+#[cfg(feature = "dbus")]
+use crate::views::image::ImageDialog;
+use crate::views::plan::PlanState;
+use hardware::ByteSize;
+use hardware::{
+    Backend, ChangePlan, CreatePartitionInfo, DiskBackend, Drive, FILESYSTEM_TYPES, Partition,
+    PartitionTypeList,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum VolumesControlMessage {
     SegmentSelected(usize),
     Mount,
     Unmount,
+    ConfirmDelete,
+    DeletePlanLoaded(Result<ChangePlan, String>),
     Delete,
+    Lock,
+    Unlock,
+    UnlockPassphraseUpdate(String),
     CreateMessage(CreateMessage),
 }
 
@@ -32,7 +42,10 @@ pub enum CreateMessage {
     PasswordProectedUpdate(bool),
     EraseUpdate(bool),
     PartitionTypeUpdate(usize),
+    FilesystemUpdate(usize),
     Continue,
+    PlanLoaded(Result<ChangePlan, String>),
+    Back,
     Cancel,
     Partition(CreatePartitionInfo),
 }
@@ -55,6 +68,37 @@ impl Into<Message> for VolumesControlMessage {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnlockInfo {
+    pub name: String,
+    pub passphrase: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeleteInfo {
+    pub name: String,
+    pub plan: PlanState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateInfo {
+    pub info: CreatePartitionInfo,
+    /// The partition table of the drive, which decides the partition types on offer.
+    pub table_type: String,
+    /// Set once the user continues, to review the changes before the partition is created.
+    pub plan: Option<PlanState>,
+}
+
+impl CreateInfo {
+    pub fn new(info: CreatePartitionInfo, table_type: String) -> Self {
+        Self {
+            info,
+            table_type,
+            plan: None,
+        }
+    }
+}
+
 pub struct VolumesControl {
     pub selected_segment: usize,
     pub segments: Vec<Segment>,
@@ -109,30 +153,43 @@ impl Segment {
         }
     }
 
-    pub fn get_create_info(&self) -> CreatePartitionInfo {
+    /// The free space left for a new partition, without the space the partition table needs.
+    pub fn get_create_info(&self, drive: &DriveModel) -> CreatePartitionInfo {
+        let usable = drive
+            .geometry()
+            .usable_range(drive.partition_table_type().as_deref().unwrap_or_default());
+        let offset = self.offset.max(usable.start);
+        let size = (self.offset + self.size)
+            .min(usable.end)
+            .saturating_sub(offset);
+
         CreatePartitionInfo {
-            max_size: self.size,
-            offset: self.offset,
-            size: self.size,
+            max_size: size,
+            offset,
+            size,
             ..Default::default()
         }
     }
 
     pub fn new(partition: &PartitionModel) -> Self {
-        let mut name = partition.name.clone();
+        let mut name = partition.label();
         if name.len() < 1 {
             name = "Filesystem".into();
         }
 
-        let mut type_str = partition.id_type.clone().to_uppercase();
-        type_str = format!("{} - {}", type_str, partition.partition_type.clone());
+        let mut type_str = partition.id_type().to_uppercase();
+        type_str = format!("{} - {}", type_str, partition.partition_type());
+
+        if let Some(cleartext) = partition.cleartext() {
+            type_str = format!("{} ({})", type_str, cleartext.id_type().to_uppercase());
+        }
 
         Self {
             label: name,
             name: partition.name(),
             partition_type: type_str,
-            size: partition.size,
-            offset: partition.offset,
+            size: partition.size(),
+            offset: partition.offset(),
             state: false,
             is_free_space: false,
             width: 0,
@@ -141,16 +198,17 @@ impl Segment {
     }
 
     pub fn get_segments(drive: &DriveModel) -> Vec<Segment> {
-        if drive.partitions.len() == 0 {
-            return vec![Segment::free_space(0, drive.size)];
+        let drive_size = drive.size();
+        if drive.partitions().len() == 0 {
+            return vec![Segment::free_space(0, drive_size)];
         }
 
-        let mut ordered_partitions = drive.partitions.clone();
+        let mut ordered_partitions = drive.partitions().to_vec();
 
-        ordered_partitions.sort_by(|a, b| a.offset.cmp(&b.offset));
+        ordered_partitions.sort_by_key(|p| p.offset());
 
         let mut segments = vec![];
-        let mut current_offset = ordered_partitions.first().unwrap().offset; //TODO: HANDLE UNWRAP
+        let mut current_offset = ordered_partitions.first().unwrap().offset(); //TODO: HANDLE UNWRAP
 
         if current_offset > 1048576 {
             //TODO: There seems to be 1024KB at the start of all drives.
@@ -159,30 +217,30 @@ impl Segment {
         }
 
         for p in ordered_partitions {
-            if p.offset > current_offset {
+            if p.offset() > current_offset {
                 //add in a free space segment.
                 segments.push(Segment::free_space(
                     current_offset,
-                    p.offset - current_offset,
+                    p.offset() - current_offset,
                 ));
-                current_offset = p.offset;
+                current_offset = p.offset();
             }
 
             segments.push(Segment::new(&p));
-            current_offset += p.size;
+            current_offset += p.size();
         }
 
         //TODO: Hack to hide weird end portion... find out what this is.
-        if current_offset < drive.size - 5242880 {
+        if current_offset < drive_size - 5242880 {
             segments.push(Segment::free_space(
                 current_offset,
-                drive.size - current_offset,
+                drive_size - current_offset,
             ));
         }
 
         //Figure out Portion value
         segments.iter_mut().for_each(|s| {
-            s.width = (((s.size as f64 / drive.size as f64) * 1000.).log10().ceil() as u16).max(1);
+            s.width = (((s.size as f64 / drive_size as f64) * 1000.).log10().ceil() as u16).max(1);
         });
 
         segments
@@ -193,7 +251,7 @@ impl Segment {
             container(
                 iced_widget::column![
                     caption_heading("Free space").center(),
-                    caption(bytes_to_pretty(&self.size, false)).center()
+                    caption(ByteSize(self.size).pretty()).center()
                 ]
                 .spacing(5)
                 .width(Length::Fill)
@@ -209,7 +267,7 @@ impl Segment {
                     caption_heading(self.name.clone()).center(),
                     caption(self.label.clone()).center(),
                     caption(self.partition_type.clone()).center(),
-                    caption(bytes_to_pretty(&self.size, false)).center()
+                    caption(ByteSize(self.size).pretty()).center()
                 ]
                 .spacing(5)
                 .align_x(Alignment::Center),
@@ -234,6 +292,8 @@ impl VolumesControl {
         }
     }
 
+    /// Actions don't reload the drives when they finish. The device events they cause update the
+    /// affected drive instead.
     pub fn update(
         &mut self,
         message: VolumesControlMessage,
@@ -252,22 +312,19 @@ impl VolumesControl {
                 match segment.clone() {
                     Some(s) => match s.partition {
                         Some(p) => {
+                            //Encrypted partitions are mounted through their unlocked contents.
+                            let p = match p.cleartext().cloned() {
+                                Some(cleartext) => cleartext,
+                                None => p,
+                            };
+
                             return Task::perform(
-                                async move {
-                                    match p.mount().await {
-                                        Ok(_) => match DriveModel::get_drives().await {
-                                            Ok(drives) => Ok(drives),
-                                            Err(e) => Err(e),
-                                        },
-                                        Err(e) => Err(e),
-                                    }
-                                },
-                                |result| match result {
-                                    Ok(drives) => Message::UpdateNav(drives, None).into(),
-                                    Err(e) => {
+                                async move { Backend::connect().await?.mount(&p).await },
+                                |result| {
+                                    if let Err(e) = result {
                                         println!("{e}");
-                                        Message::None.into()
                                     }
+                                    Message::None.into()
                                 },
                             );
                         }
@@ -282,22 +339,19 @@ impl VolumesControl {
                 match segment.clone() {
                     Some(s) => match s.partition {
                         Some(p) => {
+                            //Encrypted partitions are mounted through their unlocked contents.
+                            let p = match p.cleartext().cloned() {
+                                Some(cleartext) => cleartext,
+                                None => p,
+                            };
+
                             return Task::perform(
-                                async move {
-                                    match p.unmount().await {
-                                        Ok(_) => match DriveModel::get_drives().await {
-                                            Ok(drives) => Ok(drives),
-                                            Err(e) => Err(e),
-                                        },
-                                        Err(e) => Err(e),
-                                    }
-                                },
-                                |result| match result {
-                                    Ok(drives) => Message::UpdateNav(drives, None).into(),
-                                    Err(e) => {
+                                async move { Backend::connect().await?.unmount(&p).await },
+                                |result| {
+                                    if let Err(e) = result {
                                         println!("{e}");
-                                        Message::None.into()
                                     }
+                                    Message::None.into()
                                 },
                             );
                         }
@@ -307,26 +361,43 @@ impl VolumesControl {
                 }
                 return Task::none();
             }
+            VolumesControlMessage::ConfirmDelete => {
+                let segment = self.segments.get(self.selected_segment).cloned();
+                if let Some(s) = segment
+                    && let Some(p) = s.partition
+                {
+                    *dialog = Some(ShowDialog::DeletePartition(DeleteInfo {
+                        name: s.name,
+                        plan: PlanState::Loading,
+                    }));
+
+                    return Task::perform(
+                        async move { Backend::connect().await?.plan_delete_partition(&p).await },
+                        |result| {
+                            VolumesControlMessage::DeletePlanLoaded(
+                                result.map_err(|e| e.to_string()),
+                            )
+                            .into()
+                        },
+                    );
+                }
+            }
+            VolumesControlMessage::DeletePlanLoaded(plan) => {
+                if let Some(ShowDialog::DeletePartition(delete)) = dialog.as_mut() {
+                    delete.plan = plan.into();
+                }
+            }
             VolumesControlMessage::Delete => {
                 let segment = self.segments.get(self.selected_segment.clone()).cloned();
                 let task = match segment.clone() {
                     Some(s) => match s.partition {
                         Some(p) => Task::perform(
-                            async move {
-                                match p.delete().await {
-                                    Ok(_) => match DriveModel::get_drives().await {
-                                        Ok(drives) => Ok(drives),
-                                        Err(e) => Err(e),
-                                    },
-                                    Err(e) => Err(e),
-                                }
-                            },
-                            |result| match result {
-                                Ok(drives) => Message::UpdateNav(drives, None).into(),
-                                Err(e) => {
+                            async move { Backend::connect().await?.delete_partition(&p).await },
+                            |result| {
+                                if let Err(e) = result {
                                     println!("{e}");
-                                    Message::None.into()
                                 }
+                                Message::None.into()
                             },
                         ),
                         None => Task::none(),
@@ -336,6 +407,53 @@ impl VolumesControl {
 
                 return Task::done(Message::CloseDialog.into()).chain(task);
             }
+            VolumesControlMessage::Lock => {
+                let segment = self.segments.get(self.selected_segment.clone()).cloned();
+                match segment.clone() {
+                    Some(s) => match s.partition {
+                        Some(p) => {
+                            return Task::perform(async move { p.lock().await }, |result| {
+                                if let Err(e) = result {
+                                    println!("{e}");
+                                }
+                                Message::None.into()
+                            });
+                        }
+                        None => return Task::none(),
+                    },
+                    None => {}
+                }
+                return Task::none();
+            }
+            VolumesControlMessage::UnlockPassphraseUpdate(passphrase) => {
+                if let Some(ShowDialog::UnlockPartition(unlock)) = dialog.as_mut() {
+                    unlock.passphrase = passphrase;
+                }
+            }
+            VolumesControlMessage::Unlock => {
+                let passphrase = match dialog.as_ref() {
+                    Some(ShowDialog::UnlockPartition(unlock)) => unlock.passphrase.clone(),
+                    _ => return Task::none(),
+                };
+
+                let segment = self.segments.get(self.selected_segment.clone()).cloned();
+                let task = match segment.clone() {
+                    Some(s) => match s.partition {
+                        Some(p) => {
+                            Task::perform(async move { p.unlock(passphrase).await }, |result| {
+                                if let Err(e) = result {
+                                    println!("{e}");
+                                }
+                                Message::None.into()
+                            })
+                        }
+                        None => Task::none(),
+                    },
+                    None => Task::none(),
+                };
+
+                return Task::done(Message::CloseDialog.into()).chain(task);
+            }
             VolumesControlMessage::CreateMessage(create_message) => {
                 let d = match dialog.as_mut() {
                     Some(d) => d,
@@ -343,44 +461,74 @@ impl VolumesControl {
                 };
 
                 match d {
-                    ShowDialog::DeletePartition(_) => {}
+                    ShowDialog::DeletePartition(_) | ShowDialog::UnlockPartition(_) => {}
 
                     ShowDialog::AddPartition(create) => match create_message {
-                        CreateMessage::SizeUpdate(size) => create.size = size,
+                        CreateMessage::SizeUpdate(size) => create.info.size = size,
                         CreateMessage::NameUpdate(name) => {
-                            create.name = name;
+                            create.info.name = name;
                         }
-                        CreateMessage::PasswordUpdate(password) => create.password = password,
+                        CreateMessage::PasswordUpdate(password) => create.info.password = password,
                         CreateMessage::ConfirmedPasswordUpdate(confirmed_password) => {
-                            create.confirmed_password = confirmed_password
+                            create.info.confirmed_password = confirmed_password
                         }
                         CreateMessage::PasswordProectedUpdate(protect) => {
-                            create.password_protected = protect
+                            create.info.password_protected = protect
                         }
-                        CreateMessage::EraseUpdate(erase) => create.erase = erase,
+                        CreateMessage::EraseUpdate(erase) => create.info.erase = erase,
                         CreateMessage::PartitionTypeUpdate(p_type) => {
-                            create.selected_partitition_type = p_type
+                            create.info.partition_type =
+                                PartitionTypeList::create(&create.table_type).types[p_type]
+                                    .ty
+                                    .to_owned()
+                        }
+                        CreateMessage::FilesystemUpdate(filesystem) => {
+                            create.info.filesystem = FILESYSTEM_TYPES[filesystem].filesystem;
+                            create.info.partition_type.clear();
+                            if !create.info.filesystem.info().options.encrypt {
+                                create.info.password_protected = false;
+                            }
+                        }
+                        CreateMessage::Continue => {
+                            create.plan = Some(PlanState::Loading);
+
+                            let model = self.model.clone();
+                            let info = create.info.clone();
+                            return Task::perform(
+                                async move {
+                                    Backend::connect()
+                                        .await?
+                                        .plan_create_partition(&model, info)
+                                        .await
+                                },
+                                |result| {
+                                    CreateMessage::PlanLoaded(result.map_err(|e| e.to_string()))
+                                        .into()
+                                },
+                            );
                         }
-                        CreateMessage::Continue => todo!(),
-                        CreateMessage::Cancel => todo!(),
+                        CreateMessage::PlanLoaded(plan) => {
+                            //Ignored if the user went back while it was loading.
+                            if create.plan.is_some() {
+                                create.plan = Some(plan.into());
+                            }
+                        }
+                        CreateMessage::Back => create.plan = None,
+                        CreateMessage::Cancel => return Task::done(Message::CloseDialog.into()),
                         CreateMessage::Partition(create_partition_info) => {
                             let model = self.model.clone();
                             let task = Task::perform(
                                 async move {
-                                    match model.create_partition(create_partition_info).await {
-                                        Ok(_) => match DriveModel::get_drives().await {
-                                            Ok(drives) => Ok(drives),
-                                            Err(e) => Err(e),
-                                        },
-                                        Err(e) => Err(e),
-                                    }
+                                    Backend::connect()
+                                        .await?
+                                        .create_partition(&model, create_partition_info)
+                                        .await
                                 },
-                                |result| match result {
-                                    Ok(drives) => Message::UpdateNav(drives, None).into(),
-                                    Err(e) => {
+                                |result| {
+                                    if let Err(e) = result {
                                         println!("{e}");
-                                        Message::None.into()
                                     }
+                                    Message::None.into()
                                 },
                             );
 
@@ -439,26 +587,77 @@ impl VolumesControl {
         };
         let mut action_bar: Vec<Element<Message>> = vec![];
 
-        action_bar.push(match selected.partition {
+        let primary_action = match &selected.partition {
+            Some(p) if p.is_encrypted() && p.cleartext().is_none() => Some(
+                widget::button::custom(icon::from_name("changes-allow-symbolic")).on_press(
+                    Message::Dialog(ShowDialog::UnlockPartition(UnlockInfo {
+                        name: selected.name.clone(),
+                        passphrase: String::new(),
+                    }))
+                    .into(),
+                ),
+            ),
+            //The backend can't mount, so there is nothing to offer.
+            Some(_) if !Backend::CAN_MOUNT => None,
             Some(p) => {
-                match p.usage //TODO: More solid check than using the output of df to see if mounted.
+                let usage = match p.cleartext() {
+                    Some(cleartext) => cleartext.usage(),
+                    None => p.usage(),
+                };
+
+                match usage //TODO: More solid check than using the output of df to see if mounted.
               {
-                  Some(_) => widget::button::custom(icon::from_name( "media-playback-stop-symbolic")).on_press(VolumesControlMessage::Unmount.into()),
-                  None =>widget::button::custom(icon::from_name( "media-playback-start-symbolic")).on_press(VolumesControlMessage::Mount.into()),
+                  Some(_) => Some(widget::button::custom(icon::from_name( "media-playback-stop-symbolic")).on_press(VolumesControlMessage::Unmount.into())),
+                  None => Some(widget::button::custom(icon::from_name( "media-playback-start-symbolic")).on_press(VolumesControlMessage::Mount.into())),
               }
             }
-            None =>widget::button::custom(icon::from_name( "list-add-symbolic")).on_press(Message::Dialog(ShowDialog::AddPartition(selected.get_create_info())).into()),
-        }.into());
+            None => Some(
+                widget::button::custom(icon::from_name("list-add-symbolic")).on_press(
+                    Message::Dialog(ShowDialog::AddPartition(CreateInfo::new(
+                        selected.get_create_info(&self.model),
+                        self.model.partition_table_type().unwrap_or_default(),
+                    )))
+                    .into(),
+                ),
+            ),
+        };
+
+        if let Some(button) = primary_action {
+            action_bar.push(button.into());
+        }
+
+        if let Some(p) = &selected.partition
+            && p.cleartext().is_some()
+        {
+            action_bar.push(
+                widget::button::custom(icon::from_name("changes-prevent-symbolic"))
+                    .on_press(VolumesControlMessage::Lock.into())
+                    .into(),
+            );
+        }
 
         //TODO Get better icons
         if !selected.is_free_space {
             action_bar.push(widget::button::custom(icon::from_name("edit-find-symbolic")).into());
+
+            #[cfg(feature = "dbus")]
+            if let Some(p) = &selected.partition {
+                action_bar.push(
+                    widget::button::custom(icon::from_name("document-revert-symbolic"))
+                        .on_press(
+                            Message::Dialog(ShowDialog::Image(ImageDialog::restore_partition(
+                                p.clone(),
+                            )))
+                            .into(),
+                        )
+                        .into(),
+                );
+            }
+
             action_bar.push(widget::horizontal_space().into());
             action_bar.push(
                 widget::button::custom(icon::from_name("edit-delete-symbolic"))
-                    .on_press(
-                        Message::Dialog(ShowDialog::DeletePartition(selected.name.clone())).into(),
-                    )
+                    .on_press(VolumesControlMessage::ConfirmDelete.into())
                     .into(),
             );
         }
@@ -0,0 +1,274 @@
+use std::{fs, ops::Range};
+use thiserror::Error;
+
+use crate::ByteSize;
+
+/// Partitions are aligned to 1 MiB unless the drive asks for a larger boundary.
+pub const DEFAULT_ALIGNMENT: u64 = 1024 * 1024;
+
+/// The sectors taken by the GPT header and partition entries at the start of the drive.
+const GPT_PRIMARY_SECTORS: u64 = 34;
+
+/// The sectors taken by the backup GPT partition entries and header at the end of the drive.
+const GPT_BACKUP_SECTORS: u64 = 33;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AlignmentError {
+    #[error("The partition would end past the end of the drive")]
+    PastEnd,
+
+    #[error("The partition would overlap the backup partition table at the end of the drive")]
+    BackupGpt,
+
+    #[error("The partition would overlap the partition at {}", ByteSize(*.0))]
+    Overlaps(u64),
+
+    #[error("The partition is too small to align to {}", ByteSize(*.0))]
+    TooSmall(u64),
+}
+
+/// The sector sizes and I/O hints of a drive, which decide where partitions should start and end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DriveGeometry {
+    pub size: u64,
+    pub logical_sector_size: u64,
+    pub physical_sector_size: u64,
+    /// 0 if the drive doesn't report one.
+    pub optimal_io_size: u64,
+}
+
+/// A partition's place on the drive once aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlignedRange {
+    pub offset: u64,
+    pub size: u64,
+    /// How much of the requested space was left out to align the partition.
+    pub lost: u64,
+}
+
+impl DriveGeometry {
+    /// A drive with 512 byte sectors and no optimal I/O size.
+    pub const fn new(size: u64) -> Self {
+        Self {
+            size,
+            logical_sector_size: 512,
+            physical_sector_size: 512,
+            optimal_io_size: 0,
+        }
+    }
+
+    /// Reads the geometry of the block device `name` (e.g. `sda`) from sysfs. Anything that can't
+    /// be read is left as in [`DriveGeometry::new`].
+    pub fn from_sysfs(name: &str, size: u64) -> Self {
+        let read = |attribute: &str| {
+            fs::read_to_string(format!("/sys/class/block/{name}/queue/{attribute}"))
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+
+        let mut geometry = Self::new(size);
+        if let Some(size) = read("logical_block_size").filter(|s| *s > 0) {
+            geometry.logical_sector_size = size;
+        }
+        if let Some(size) = read("physical_block_size").filter(|s| *s > 0) {
+            geometry.physical_sector_size = size;
+        }
+        if let Some(size) = read("optimal_io_size") {
+            geometry.optimal_io_size = size;
+        }
+
+        geometry
+    }
+
+    /// The boundary partitions start and end on: the optimal I/O size if it is larger than
+    /// [`DEFAULT_ALIGNMENT`] and a whole number of sectors, otherwise 1 MiB.
+    pub fn alignment(&self) -> u64 {
+        let sector = self.logical_sector_size.max(self.physical_sector_size);
+        if self.optimal_io_size > DEFAULT_ALIGNMENT && self.optimal_io_size.is_multiple_of(sector) {
+            return self.optimal_io_size;
+        }
+
+        DEFAULT_ALIGNMENT.next_multiple_of(sector)
+    }
+
+    /// The part of the drive left for partitions by a partition table of `table_type`.
+    pub fn usable_range(&self, table_type: &str) -> Range<u64> {
+        match table_type {
+            "gpt" => {
+                let start = GPT_PRIMARY_SECTORS * self.logical_sector_size;
+                let end = self
+                    .size
+                    .saturating_sub(GPT_BACKUP_SECTORS * self.logical_sector_size);
+                start..end
+            }
+            "dos" => self.logical_sector_size..self.size,
+            _ => 0..self.size,
+        }
+    }
+
+    /// Snaps the start of a new partition up, and its end down, to [`DriveGeometry::alignment`].
+    ///
+    /// A start inside the partition table is moved past it, but a partition that overlaps one in
+    /// `existing`, or the backup GPT, is refused.
+    pub fn align(
+        &self,
+        table_type: &str,
+        offset: u64,
+        size: u64,
+        existing: &[Range<u64>],
+    ) -> Result<AlignedRange, AlignmentError> {
+        let end = offset
+            .checked_add(size)
+            .filter(|end| *end <= self.size)
+            .ok_or(AlignmentError::PastEnd)?;
+
+        let usable = self.usable_range(table_type);
+        if end > usable.end {
+            return Err(AlignmentError::BackupGpt);
+        }
+
+        if let Some(partition) = existing.iter().find(|p| offset < p.end && p.start < end) {
+            return Err(AlignmentError::Overlaps(partition.start));
+        }
+
+        let alignment = self.alignment();
+        let start = offset.max(usable.start).next_multiple_of(alignment);
+        let end = end - end % alignment;
+        if end <= start {
+            return Err(AlignmentError::TooSmall(alignment));
+        }
+
+        Ok(AlignedRange {
+            offset: start,
+            size: end - start,
+            lost: size - (end - start),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+    const GIB: u64 = 1024 * MIB;
+
+    #[test]
+    fn aligns_to_a_larger_optimal_io_size() {
+        let mut geometry = DriveGeometry::new(8 * GIB);
+        geometry.physical_sector_size = 4096;
+
+        geometry.optimal_io_size = 4 * MIB;
+        assert_eq!(geometry.alignment(), 4 * MIB);
+
+        //Not a whole number of physical sectors.
+        geometry.optimal_io_size = 4 * MIB + 512;
+        assert_eq!(geometry.alignment(), MIB);
+
+        geometry.optimal_io_size = 64 * 1024;
+        assert_eq!(geometry.alignment(), MIB);
+    }
+
+    #[test]
+    fn reserves_gpt_sectors_of_the_logical_size() {
+        let mut geometry = DriveGeometry::new(8 * GIB);
+        geometry.logical_sector_size = 4096;
+        geometry.physical_sector_size = 4096;
+
+        assert_eq!(geometry.usable_range("gpt"), 34 * 4096..8 * GIB - 33 * 4096);
+        assert_eq!(geometry.usable_range("dos"), 4096..8 * GIB);
+        assert_eq!(
+            geometry.align("gpt", 0, 10 * MIB, &[]),
+            Ok(AlignedRange {
+                offset: MIB,
+                size: 9 * MIB,
+                lost: MIB,
+            })
+        );
+    }
+
+    #[test]
+    fn moves_a_start_inside_the_primary_gpt_past_it() {
+        let geometry = DriveGeometry::new(8 * GIB);
+
+        assert_eq!(
+            geometry.align("gpt", 17 * 512, 10 * MIB, &[]),
+            Ok(AlignedRange {
+                offset: MIB,
+                size: 9 * MIB,
+                lost: MIB,
+            })
+        );
+    }
+
+    #[test]
+    fn counts_what_alignment_leaves_out() {
+        let geometry = DriveGeometry::new(8 * GIB);
+
+        let range = geometry.align("gpt", MIB + 4096, 10 * MIB, &[]).unwrap();
+        assert_eq!((range.offset, range.size), (2 * MIB, 9 * MIB));
+        assert_eq!(range.lost, 10 * MIB - range.size);
+
+        let range = geometry.align("gpt", MIB, 10 * MIB, &[]).unwrap();
+        assert_eq!((range.offset, range.size, range.lost), (MIB, 10 * MIB, 0));
+    }
+
+    #[test]
+    fn refuses_a_partition_past_the_end() {
+        let geometry = DriveGeometry::new(8 * GIB);
+
+        assert_eq!(
+            geometry.align("gpt", 8 * GIB - MIB, 2 * MIB, &[]),
+            Err(AlignmentError::PastEnd)
+        );
+        assert_eq!(
+            geometry.align("gpt", u64::MAX, 1, &[]),
+            Err(AlignmentError::PastEnd)
+        );
+    }
+
+    #[test]
+    fn refuses_the_backup_gpt() {
+        let geometry = DriveGeometry::new(8 * GIB);
+
+        assert_eq!(
+            geometry.align("gpt", 8 * GIB - 2 * MIB, 2 * MIB, &[]),
+            Err(AlignmentError::BackupGpt)
+        );
+        assert!(
+            geometry
+                .align("dos", 8 * GIB - 2 * MIB, 2 * MIB, &[])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn refuses_overlapping_partitions() {
+        let geometry = DriveGeometry::new(8 * GIB);
+        let existing = [MIB..MIB + GIB, 2 * GIB..3 * GIB];
+
+        assert_eq!(
+            geometry.align("gpt", GIB, GIB, &existing),
+            Err(AlignmentError::Overlaps(MIB))
+        );
+        assert_eq!(
+            geometry.align("gpt", 2 * GIB + MIB, MIB, &existing),
+            Err(AlignmentError::Overlaps(2 * GIB))
+        );
+        assert!(
+            geometry
+                .align("gpt", MIB + GIB, GIB - MIB, &existing)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn refuses_a_partition_smaller_than_the_alignment() {
+        let geometry = DriveGeometry::new(8 * GIB);
+
+        assert_eq!(
+            geometry.align("gpt", MIB + 512, MIB, &[]),
+            Err(AlignmentError::TooSmall(MIB))
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use futures::Stream;
+
+use crate::{ChangePlan, CreatePartitionInfo, DeviceEvent, Drive, EraseMode, Partition};
+
+/// A way of finding and changing the disks on the system. disks-ui only talks to the selected
+/// backend for the operations below, so it works the same whether UDisks2 or disks-rs does the
+/// work underneath.
+///
+/// Every destructive operation has a `plan_*` counterpart that checks it and describes what it
+/// would change, without changing anything, so the user can confirm it first.
+pub trait DiskBackend: Sized + Send + Sync {
+    type Drive: Drive<Partition = Self::Partition> + Clone + Send + Sync + 'static;
+    type Partition: Partition + Clone + Send + Sync + 'static;
+    type EventStream: Stream<Item = DeviceEvent> + Send + Unpin + 'static;
+
+    /// Whether [`DiskBackend::mount`] and [`DiskBackend::unmount`] work. If not, the UI doesn't
+    /// offer them.
+    const CAN_MOUNT: bool = true;
+
+    fn connect() -> impl std::future::Future<Output = Result<Self>> + Send;
+
+    /// Every drive on the system, with its partitions.
+    fn drives(&self) -> impl std::future::Future<Output = Result<Vec<Self::Drive>>> + Send;
+
+    /// Reports devices as they come and go, or change.
+    fn watch(&self) -> impl std::future::Future<Output = Result<Self::EventStream>> + Send;
+
+    /// Brings `drives` up to date with a single event from [`DiskBackend::watch`].
+    fn apply_event(
+        &self,
+        drives: &mut Vec<Self::Drive>,
+        event: DeviceEvent,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn create_partition(
+        &self,
+        drive: &Self::Drive,
+        info: CreatePartitionInfo,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn plan_create_partition(
+        &self,
+        drive: &Self::Drive,
+        info: CreatePartitionInfo,
+    ) -> impl std::future::Future<Output = Result<ChangePlan>> + Send;
+
+    fn delete_partition(
+        &self,
+        partition: &Self::Partition,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn plan_delete_partition(
+        &self,
+        partition: &Self::Partition,
+    ) -> impl std::future::Future<Output = Result<ChangePlan>> + Send;
+
+    fn format_partition(
+        &self,
+        partition: &Self::Partition,
+        name: String,
+        erase: EraseMode,
+        filesystem_type: String,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn plan_format_partition(
+        &self,
+        partition: &Self::Partition,
+        name: String,
+        erase: EraseMode,
+        filesystem_type: String,
+    ) -> impl std::future::Future<Output = Result<ChangePlan>> + Send;
+
+    /// See [`crate::Drive::format_disk`].
+    fn format_disk(
+        &self,
+        drive: &Self::Drive,
+        table_type: String,
+        erase: EraseMode,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn plan_format_disk(
+        &self,
+        drive: &Self::Drive,
+        table_type: String,
+        erase: EraseMode,
+    ) -> impl std::future::Future<Output = Result<ChangePlan>> + Send;
+
+    fn mount(
+        &self,
+        partition: &Self::Partition,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn unmount(
+        &self,
+        partition: &Self::Partition,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+}
@@ -0,0 +1,83 @@
+/// Whether, and where, a benchmark may write to the drive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BenchmarkWrite {
+    /// Only read from the drive.
+    #[default]
+    None,
+    /// Write each sample back over the data just read from it. The drive's contents are kept,
+    /// but nothing may be mounted while the benchmark runs.
+    PreserveData,
+    /// Only write to space not covered by a partition. Mounted partitions are left alone.
+    UnallocatedOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchmarkOptions {
+    /// Number of throughput samples, spread evenly across the drive.
+    pub samples: usize,
+    /// Bytes transferred per throughput sample.
+    pub sample_size: u64,
+    /// Number of random reads used to measure access time.
+    pub access_samples: usize,
+    pub write: BenchmarkWrite,
+}
+
+impl Default for BenchmarkOptions {
+    fn default() -> Self {
+        Self {
+            samples: 100,
+            sample_size: 10 * 1024 * 1024,
+            access_samples: 1000,
+            write: BenchmarkWrite::None,
+        }
+    }
+}
+
+/// The transfer rate measured at one position on the drive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThroughputSample {
+    /// Seconds since the benchmark started.
+    pub time: f64,
+    pub offset: u64,
+    pub bytes_per_second: f64,
+}
+
+/// The time taken for a single small read at a random position on the drive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccessSample {
+    /// Seconds since the benchmark started.
+    pub time: f64,
+    pub offset: u64,
+    pub seconds: f64,
+}
+
+/// The samples collected by a benchmark. While it runs this is reported with the samples
+/// collected so far.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BenchmarkResult {
+    pub size: u64,
+    pub read: Vec<ThroughputSample>,
+    pub write: Vec<ThroughputSample>,
+    pub access: Vec<AccessSample>,
+}
+
+impl BenchmarkResult {
+    pub fn average_read_rate(&self) -> Option<f64> {
+        average(self.read.iter().map(|s| s.bytes_per_second))
+    }
+
+    pub fn average_write_rate(&self) -> Option<f64> {
+        average(self.write.iter().map(|s| s.bytes_per_second))
+    }
+
+    pub fn average_access_time(&self) -> Option<f64> {
+        average(self.access.iter().map(|s| s.seconds))
+    }
+}
+
+fn average(values: impl ExactSizeIterator<Item = f64>) -> Option<f64> {
+    match values.len() {
+        0 => None,
+        len => Some(values.sum::<f64>() / len as f64),
+    }
+}
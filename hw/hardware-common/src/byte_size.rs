@@ -0,0 +1,533 @@
+use num_format::{Locale, ToFormattedString};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr, sync::RwLock};
+use thiserror::Error;
+
+const IEC_UNITS: [&str; 9] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB", "ZiB", "YiB"];
+const SI_UNITS: [&str; 9] = ["B", "kB", "MB", "GB", "TB", "PB", "EB", "ZB", "YB"];
+
+/// The prefixes of both unit systems, in order, for parsing.
+const PREFIXES: &str = "kmgtpezy";
+
+/// Fraction digits past this many are ignored when parsing. Even in EiB, the last one is worth
+/// about a byte.
+const MAX_FRACTION_DIGITS: usize = 18;
+
+/// Whether sizes are counted in powers of 1024 (KiB, MiB, ...) or 1000 (kB, MB, ...).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnitSystem {
+    #[default]
+    Iec,
+    Si,
+}
+
+impl UnitSystem {
+    fn base(self) -> u128 {
+        match self {
+            UnitSystem::Iec => 1024,
+            UnitSystem::Si => 1000,
+        }
+    }
+
+    fn units(self) -> &'static [&'static str; 9] {
+        match self {
+            UnitSystem::Iec => &IEC_UNITS,
+            UnitSystem::Si => &SI_UNITS,
+        }
+    }
+
+    /// The number of bytes in the unit `exponent` steps above bytes, e.g. 1024^3 for GiB.
+    fn multiplier(self, exponent: usize) -> u128 {
+        self.base().pow(exponent as u32)
+    }
+}
+
+/// How sizes are shown to, and read from, the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeFormat {
+    pub units: UnitSystem,
+    /// Supplies the decimal and thousands separators.
+    pub locale: Locale,
+}
+
+static CURRENT_FORMAT: RwLock<Option<SizeFormat>> = RwLock::new(None);
+
+impl SizeFormat {
+    pub const fn new(units: UnitSystem, locale: Locale) -> Self {
+        Self { units, locale }
+    }
+
+    /// The format used by [`ByteSize::pretty`] and [`ByteSize::parse`]. Until one is set, this
+    /// is IEC units in the locale of the environment.
+    pub fn current() -> SizeFormat {
+        if let Some(format) = CURRENT_FORMAT.read().ok().and_then(|f| *f) {
+            return format;
+        }
+
+        let format = SizeFormat::new(UnitSystem::Iec, locale_from_env());
+        format.set_current();
+        format
+    }
+
+    pub fn set_current(self) {
+        if let Ok(mut current) = CURRENT_FORMAT.write() {
+            *current = Some(self);
+        }
+    }
+}
+
+/// Finds the locale for numbers from `LC_ALL`, `LC_NUMERIC` or `LANG`, falling back to English.
+fn locale_from_env() -> Locale {
+    let name = ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.is_empty())
+        .unwrap_or_default();
+
+    //e.g. de_DE.UTF-8 or sr_RS@latin
+    let name = name
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .replace('_', "-");
+    let language = name.split('-').next().unwrap_or_default();
+
+    Locale::from_name(&name)
+        .or_else(|_| Locale::from_name(language))
+        .unwrap_or(Locale::en)
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ByteSizeError {
+    #[error("No size was given")]
+    Empty,
+
+    #[error("'{0}' is not a number")]
+    InvalidNumber(String),
+
+    #[error("'{0}' is not a unit of size")]
+    UnknownUnit(String),
+
+    #[error("A percentage needs a total size to be taken from")]
+    NoTotal,
+
+    #[error("A size can not be more than 100% of the total")]
+    PercentTooLarge,
+
+    #[error("The size is too large")]
+    TooLarge,
+}
+
+/// A number of bytes.
+///
+/// [`ByteSize::pretty`] rounds for display, while [`Display`](fmt::Display) writes the shortest
+/// exact form (e.g. `1.5 GiB`), which [`FromStr`] reads back to the same size.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub const fn new(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    pub const fn bytes(self) -> u64 {
+        self.0
+    }
+
+    /// How many units above bytes the size is shown in, e.g. 3 for GiB.
+    fn exponent(self, units: UnitSystem) -> usize {
+        let mut exponent = 0;
+        while exponent + 1 < units.units().len() && self.0 as u128 >= units.multiplier(exponent + 1)
+        {
+            exponent += 1;
+        }
+
+        exponent
+    }
+
+    /// The size rounded to two decimals in the current [`SizeFormat`], e.g. `1.50 GiB`.
+    pub fn pretty(self) -> String {
+        self.pretty_in(&SizeFormat::current())
+    }
+
+    /// Like [`ByteSize::pretty`], followed by the exact number of bytes.
+    pub fn pretty_with_bytes(self) -> String {
+        let format = SizeFormat::current();
+        format!(
+            "{} ({} bytes)",
+            self.pretty_in(&format),
+            self.0.to_formatted_string(&format.locale)
+        )
+    }
+
+    pub fn pretty_in(self, format: &SizeFormat) -> String {
+        let units = format.units;
+        let mut exponent = self.exponent(units);
+        if exponent == 0 {
+            return format!("{} B", self.0.to_formatted_string(&format.locale));
+        }
+
+        let mut hundredths = div_round(self.0 as u128 * 100, units.multiplier(exponent));
+
+        //Rounding can carry over into the next unit, e.g. 1023.999 KiB.
+        if hundredths >= units.base() * 100 && exponent + 1 < units.units().len() {
+            exponent += 1;
+            hundredths = div_round(self.0 as u128 * 100, units.multiplier(exponent));
+        }
+
+        format!(
+            "{}{}{:02} {}",
+            ((hundredths / 100) as u64).to_formatted_string(&format.locale),
+            format.locale.decimal(),
+            hundredths % 100,
+            units.units()[exponent]
+        )
+    }
+
+    /// The size of one unit of [`ByteSize::pretty`], which makes a sensible step for a spinner.
+    pub fn step(self) -> u64 {
+        let units = SizeFormat::current().units;
+        units.multiplier(self.exponent(units)) as u64
+    }
+
+    /// Reads a size in the current [`SizeFormat`], e.g. `1.5GiB`, `500M`, `2 TB` or `25%`.
+    ///
+    /// Percentages are taken of `total`. Units without a `B` (`500M`) are in the current unit
+    /// system, and a number alone is in bytes. The result is rounded to the nearest byte.
+    pub fn parse(input: &str, total: Option<ByteSize>) -> Result<ByteSize, ByteSizeError> {
+        Self::parse_in(input, &SizeFormat::current(), total)
+    }
+
+    pub fn parse_in(
+        input: &str,
+        format: &SizeFormat,
+        total: Option<ByteSize>,
+    ) -> Result<ByteSize, ByteSizeError> {
+        let separator = match format.locale.separator() {
+            s if s == format.locale.decimal() => "",
+            s => s,
+        };
+
+        parse(
+            input,
+            format.locale.decimal(),
+            separator,
+            format.units,
+            total,
+        )
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut shortest = format!("{} B", self.0);
+
+        for units in [UnitSystem::Iec, UnitSystem::Si] {
+            for exponent in 1..units.units().len() {
+                let multiplier = units.multiplier(exponent);
+                if (self.0 as u128) < multiplier {
+                    break;
+                }
+
+                let thousandths = self.0 as u128 * 1000;
+                if !thousandths.is_multiple_of(multiplier) {
+                    continue;
+                }
+
+                let thousandths = thousandths / multiplier;
+                let mut value = (thousandths / 1000).to_string();
+                if !thousandths.is_multiple_of(1000) {
+                    let fraction = format!("{:03}", thousandths % 1000);
+                    value = format!("{value}.{}", fraction.trim_end_matches('0'));
+                }
+
+                let candidate = format!("{value} {}", units.units()[exponent]);
+                if candidate.len() < shortest.len() {
+                    shortest = candidate;
+                }
+            }
+        }
+
+        f.write_str(&shortest)
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = ByteSizeError;
+
+    /// Reads the form written by [`Display`](fmt::Display), independent of locale.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s, ".", "", UnitSystem::Iec, None)
+    }
+}
+
+fn div_round(numerator: u128, denominator: u128) -> u128 {
+    (numerator + denominator / 2) / denominator
+}
+
+fn parse(
+    input: &str,
+    decimal: &str,
+    separator: &str,
+    default_units: UnitSystem,
+    total: Option<ByteSize>,
+) -> Result<ByteSize, ByteSizeError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(ByteSizeError::Empty);
+    }
+
+    let split = input
+        .find(|c: char| c.is_alphabetic() || c == '%')
+        .unwrap_or(input.len());
+    let (number, unit) = (input[..split].trim(), input[split..].trim());
+
+    let (mantissa, scale) = parse_decimal(number, decimal, separator)
+        .ok_or_else(|| ByteSizeError::InvalidNumber(number.to_owned()))?;
+
+    let bytes = match unit {
+        "%" => {
+            let total = total.ok_or(ByteSizeError::NoTotal)?;
+            if mantissa > 100 * scale {
+                return Err(ByteSizeError::PercentTooLarge);
+            }
+
+            let scaled = mantissa
+                .checked_mul(total.0 as u128)
+                .ok_or(ByteSizeError::TooLarge)?;
+            div_round(scaled, 100 * scale)
+        }
+        _ => {
+            let multiplier = unit_multiplier(unit, default_units)
+                .ok_or_else(|| ByteSizeError::UnknownUnit(unit.to_owned()))?;
+
+            let scaled = mantissa
+                .checked_mul(multiplier)
+                .ok_or(ByteSizeError::TooLarge)?;
+            div_round(scaled, scale)
+        }
+    };
+
+    u64::try_from(bytes)
+        .map(ByteSize)
+        .map_err(|_| ByteSizeError::TooLarge)
+}
+
+/// Reads a decimal number exactly, as its digits without the decimal point and the power of ten
+/// to divide them by.
+fn parse_decimal(number: &str, decimal: &str, separator: &str) -> Option<(u128, u128)> {
+    let mut number = number.to_owned();
+    if !separator.is_empty() {
+        number = number.replace(separator, "");
+    }
+
+    let (whole, fraction) = match number.split_once(decimal) {
+        Some((whole, fraction)) => (whole.to_owned(), fraction.to_owned()),
+        None => (number, String::new()),
+    };
+
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let fraction: String = fraction.chars().take(MAX_FRACTION_DIGITS).collect();
+    let scale = 10_u128.pow(fraction.len() as u32);
+
+    let whole: u128 = match whole.is_empty() {
+        true => 0,
+        false => whole.parse().ok()?,
+    };
+    let fraction: u128 = match fraction.is_empty() {
+        true => 0,
+        false => fraction.parse().ok()?,
+    };
+
+    whole
+        .checked_mul(scale)?
+        .checked_add(fraction)
+        .map(|m| (m, scale))
+}
+
+/// The number of bytes in a unit such as `GiB`, `GB`, `G` or `B`, ignoring case.
+fn unit_multiplier(unit: &str, default_units: UnitSystem) -> Option<u128> {
+    let unit = unit.to_ascii_lowercase();
+    if matches!(unit.as_str(), "" | "b" | "byte" | "bytes") {
+        return Some(1);
+    }
+
+    let mut chars = unit.chars();
+    let exponent = PREFIXES.find(chars.next()?)? + 1;
+
+    let units = match chars.as_str() {
+        "" => default_units,
+        "ib" => UnitSystem::Iec,
+        "b" => UnitSystem::Si,
+        _ => return None,
+    };
+
+    Some(units.multiplier(exponent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IEC: SizeFormat = SizeFormat::new(UnitSystem::Iec, Locale::en);
+    const SI: SizeFormat = SizeFormat::new(UnitSystem::Si, Locale::en);
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn parses_units() {
+        assert_eq!(
+            ByteSize::parse_in("1.5GiB", &IEC, None),
+            Ok(ByteSize(GIB * 3 / 2))
+        );
+        assert_eq!(
+            ByteSize::parse_in("1.5GiB", &SI, None),
+            Ok(ByteSize(GIB * 3 / 2))
+        );
+        assert_eq!(
+            ByteSize::parse_in("2 TB", &IEC, None),
+            Ok(ByteSize(2_000_000_000_000))
+        );
+        assert_eq!(ByteSize::parse_in("4096", &IEC, None), Ok(ByteSize(4096)));
+    }
+
+    #[test]
+    fn bare_prefixes_follow_the_unit_system() {
+        assert_eq!(
+            ByteSize::parse_in("500M", &IEC, None),
+            Ok(ByteSize(500 * 1024 * 1024))
+        );
+        assert_eq!(
+            ByteSize::parse_in("500M", &SI, None),
+            Ok(ByteSize(500_000_000))
+        );
+    }
+
+    #[test]
+    fn parses_percentages_of_the_total() {
+        let total = Some(ByteSize(1000));
+
+        assert_eq!(ByteSize::parse_in("25%", &IEC, total), Ok(ByteSize(250)));
+        assert_eq!(ByteSize::parse_in("12.5 %", &IEC, total), Ok(ByteSize(125)));
+        assert_eq!(
+            ByteSize::parse_in("25%", &IEC, None),
+            Err(ByteSizeError::NoTotal)
+        );
+        assert_eq!(
+            ByteSize::parse_in("101%", &IEC, total),
+            Err(ByteSizeError::PercentTooLarge)
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_units() {
+        assert_eq!(
+            ByteSize::parse_in("5 GQ", &IEC, None),
+            Err(ByteSizeError::UnknownUnit("GQ".into()))
+        );
+        assert_eq!(
+            ByteSize::parse_in("5 xB", &IEC, None),
+            Err(ByteSizeError::UnknownUnit("xB".into()))
+        );
+        assert_eq!(
+            "5 bits".parse::<ByteSize>(),
+            Err(ByteSizeError::UnknownUnit("bits".into()))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_numbers() {
+        assert_eq!(
+            ByteSize::parse_in("", &IEC, None),
+            Err(ByteSizeError::Empty)
+        );
+        assert_eq!(
+            ByteSize::parse_in("1.2.3 GiB", &IEC, None),
+            Err(ByteSizeError::InvalidNumber("1.2.3".into()))
+        );
+        assert_eq!(
+            ByteSize::parse_in("20 EiB", &IEC, None),
+            Err(ByteSizeError::TooLarge)
+        );
+    }
+
+    #[test]
+    fn formats_iec_and_si() {
+        assert_eq!(ByteSize(GIB * 3 / 2).pretty_in(&IEC), "1.50 GiB");
+        assert_eq!(ByteSize(GIB * 3 / 2).pretty_in(&SI), "1.61 GB");
+        assert_eq!(ByteSize(1_000_000_000).pretty_in(&IEC), "953.67 MiB");
+        assert_eq!(ByteSize(1_000_000_000).pretty_in(&SI), "1.00 GB");
+        assert_eq!(ByteSize(1023).pretty_in(&IEC), "1,023 B");
+    }
+
+    #[test]
+    fn rounding_carries_into_the_next_unit() {
+        assert_eq!(ByteSize(1024 * 1024 - 1).pretty_in(&IEC), "1.00 MiB");
+    }
+
+    #[test]
+    fn display_writes_the_shortest_exact_form() {
+        assert_eq!(ByteSize(GIB * 3 / 2).to_string(), "1.5 GiB");
+        assert_eq!(ByteSize(2_000_000_000_000).to_string(), "2 TB");
+        assert_eq!(ByteSize(1023).to_string(), "1023 B");
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for bytes in [
+            0,
+            1,
+            1023,
+            1536,
+            4096,
+            1_000_000,
+            123_456_789,
+            GIB * 3 / 2,
+            2_000_000_000_000,
+            u64::MAX,
+        ] {
+            let size = ByteSize(bytes);
+            assert_eq!(size.to_string().parse(), Ok(size), "{size}");
+        }
+    }
+
+    #[test]
+    fn pretty_round_trips_to_two_decimals() {
+        for format in [IEC, SI] {
+            for bytes in [1536, 5_000_000, GIB * 3 / 2, 2_000_000_000_000] {
+                let size = ByteSize(bytes);
+                let parsed = ByteSize::parse_in(&size.pretty_in(&format), &format, None).unwrap();
+                let exponent = size.exponent(format.units);
+
+                //Two decimals are within half a hundredth of the unit shown.
+                assert!(
+                    parsed.0.abs_diff(bytes) as u128 <= format.units.multiplier(exponent) / 200,
+                    "{} parsed as {parsed:?}",
+                    size.pretty_in(&format)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::fmt::{self, Display};
+
+/// What a destructive operation would do, worked out without writing anything. Each change is
+/// a sentence for the user, in the order it would be carried out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangePlan {
+    pub changes: Vec<String>,
+}
+
+impl ChangePlan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, change: impl Into<String>) {
+        self.changes.push(change.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Display for ChangePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, change) in self.changes.iter().enumerate() {
+            writeln!(f, "{}. {}", i + 1, change)?;
+        }
+
+        Ok(())
+    }
+}
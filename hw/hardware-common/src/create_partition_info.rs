@@ -1,3 +1,5 @@
+use crate::{FilesystemType, PartitionTypeInfo};
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct CreatePartitionInfo {
     pub name: String,
@@ -5,10 +7,39 @@ pub struct CreatePartitionInfo {
     pub max_size: u64,
     pub offset: u64,
     pub erase: bool,
-    pub selected_type: String,
-    pub selected_partitition_type: usize,
+    /// The partition type, e.g. a GPT GUID or an MBR code such as `0x83`. Empty to use the one
+    /// `filesystem` implies.
+    pub partition_type: String,
+    pub filesystem: FilesystemType,
     pub password_protected: bool,
     pub password: String,
     pub confirmed_password: String,
     pub can_continue: bool,
 }
+
+impl CreatePartitionInfo {
+    /// The partition type to create on a `table_type` partition table. Empty if neither it nor
+    /// the filesystem has one, leaving the choice to the backend. An MBR type code given for a
+    /// GPT table is swapped for its GPT equivalent.
+    pub fn partition_type_for(&self, table_type: &str) -> &str {
+        if self.partition_type.is_empty() {
+            return self
+                .filesystem
+                .info()
+                .partition_type(table_type)
+                .unwrap_or_default();
+        }
+
+        match table_type {
+            "gpt" => PartitionTypeInfo::dos_to_gpt(&self.partition_type)
+                .map_or(self.partition_type.as_str(), |p| p.ty),
+            _ => &self.partition_type,
+        }
+    }
+
+    /// Whether the partition will be encrypted, either around its filesystem or as an empty
+    /// LUKS container.
+    pub fn is_encrypted(&self) -> bool {
+        self.password_protected || self.filesystem == FilesystemType::Luks
+    }
+}
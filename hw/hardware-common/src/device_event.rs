@@ -0,0 +1,17 @@
+/// A change to the devices on the system, as reported by a [`crate::DiskBackend`]. Paths are
+/// whatever the backend uses to identify its drives and partitions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceEvent {
+    /// A block device or drive appeared.
+    Added(String),
+    /// A block device or drive went away.
+    Removed(String),
+    /// An interface of the object at `path` was added, removed, or had its properties changed.
+    Changed { path: String, interface: String },
+    /// The filesystem of the block device at the path was mounted, or gained a mount point.
+    Mounted(String),
+    /// The filesystem of the block device at the path lost its last mount point.
+    Unmounted(String),
+    /// Media was inserted into or removed from the drive at the path.
+    MediaChanged(String),
+}
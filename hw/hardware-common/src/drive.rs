@@ -1,19 +1,109 @@
 use anyhow::Result;
 
-use crate::CreatePartitionInfo;
+use crate::{
+    BenchmarkOptions, BenchmarkResult, ChangePlan, CreatePartitionInfo, DiskImage, DriveGeometry,
+    DriveSettings, EraseMode, SelfTestType, SmartReport,
+};
 
 pub trait Drive {
+    type Partition: crate::Partition;
+
     fn pretty_name(&self) -> String;
 
+    /// Tells the drive apart from the others, and keeps doing so when the drives are reloaded.
+    fn block_path(&self) -> String;
+
+    fn size(&self) -> u64;
+
+    fn model(&self) -> String;
+
+    fn serial(&self) -> String;
+
+    fn removable(&self) -> bool;
+
+    /// "gpt" or "dos", or `None` if the drive has no partition table.
+    fn partition_table_type(&self) -> Option<String>;
+
+    fn geometry(&self) -> DriveGeometry;
+
+    /// The image file behind a loop device. `None` for real drives.
+    fn backing_file(&self) -> Option<String>;
+
+    fn partitions(&self) -> &[Self::Partition];
+
     fn eject(&self) -> impl std::future::Future<Output = Result<()>> + Send;
 
     fn power_off(&self) -> impl std::future::Future<Output = Result<()>> + Send;
 
+    /// Puts the drive into standby, spinning it down.
+    fn standby(&self) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    /// Wakes the drive up from standby.
+    fn wakeup(&self) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn drive_settings(&self) -> impl std::future::Future<Output = Result<DriveSettings>> + Send;
+
+    /// Persists `settings`, replacing any that were previously set.
+    fn set_drive_settings(
+        &self,
+        settings: DriveSettings,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
     fn create_partition(
         &self,
         info: CreatePartitionInfo,
     ) -> impl std::future::Future<Output = Result<()>> + Send;
 
+    /// The changes [`Drive::create_partition`] would make, checked but not written.
+    fn plan_create_partition(
+        &self,
+        info: CreatePartitionInfo,
+    ) -> impl std::future::Future<Output = Result<ChangePlan>> + Send;
+
+    /// Replaces everything on the drive with a new, empty partition table. `table_type` is
+    /// "gpt", "dos", or "" to leave the drive without a partition table.
+    fn format_disk(
+        &self,
+        table_type: String,
+        erase: EraseMode,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    /// The changes [`Drive::format_disk`] would make, checked but not written.
+    fn plan_format_disk(
+        &self,
+        table_type: String,
+        erase: EraseMode,
+    ) -> impl std::future::Future<Output = Result<ChangePlan>> + Send;
+
+    /// Creates a *.img of the whole drive.
+    fn create_image(
+        &self,
+        output_path: String,
+    ) -> impl std::future::Future<Output = Result<DiskImage>> + Send;
+
+    /// Writes the image at `input_path` over the whole drive, then verifies it by reading it
+    /// back. Any mounted filesystems are unmounted first.
+    fn restore_image(
+        &self,
+        input_path: String,
+    ) -> impl std::future::Future<Output = Result<DiskImage>> + Send;
+
+    /// Reads the drive's SMART data.
+    fn smart_report(&self) -> impl std::future::Future<Output = Result<SmartReport>> + Send;
+
+    fn start_self_test(
+        &self,
+        test: SelfTestType,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn abort_self_test(&self) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    /// Measures the drive's read (and optionally write) throughput and access time.
+    fn benchmark(
+        &self,
+        options: BenchmarkOptions,
+    ) -> impl std::future::Future<Output = Result<BenchmarkResult>> + Send;
+
     //async fn get_drive_paths(connection: &Connection) -> Result<Vec<DriveBlockPair>>;
 
     //async fn get_drives() -> Result<Vec<DriveModel>>;
@@ -0,0 +1,104 @@
+/// Persistent ATA drive settings, applied by the backend whenever the drive is connected. `None`
+/// leaves a setting at the drive's own default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DriveSettings {
+    /// Time before the drive spins down, in the ATA encoding. See [`standby_timeout_seconds`].
+    pub standby_timeout: Option<u8>,
+    /// Advanced Power Management level, from 1 (most power saving) to 254 (best performance).
+    /// 255 disables APM.
+    pub apm_level: Option<u8>,
+    /// Automatic Acoustic Management level, from 128 (quietest) to 254 (fastest).
+    pub aam_level: Option<u8>,
+    pub write_cache: Option<bool>,
+}
+
+/// Which of the [`DriveSettings`] a drive supports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DriveSettingsSupport {
+    pub standby: bool,
+    pub apm: bool,
+    pub aam: bool,
+    pub write_cache: bool,
+    /// The AAM level the manufacturer recommends.
+    pub aam_vendor_recommended: Option<u8>,
+}
+
+pub const AAM_LEVEL_MIN: u8 = 128;
+pub const AAM_LEVEL_MAX: u8 = 254;
+pub const APM_LEVEL_MIN: u8 = 1;
+pub const APM_DISABLED: u8 = 255;
+
+/// Converts an ATA standby timer value to seconds. 0 disables the timer, returning `None`.
+pub fn standby_timeout_seconds(value: u8) -> Option<u64> {
+    match value {
+        0 => None,
+        1..=240 => Some(value as u64 * 5),
+        241..=251 => Some((value as u64 - 240) * 30 * 60),
+        252 => Some(21 * 60),
+        //253 is vendor defined and 254 is reserved. Treat both as the longest fixed timeout.
+        253 | 254 => Some(5 * 60 * 60 + 30 * 60),
+        255 => Some(21 * 60 + 15),
+    }
+}
+
+/// Converts a timeout in seconds to the nearest ATA standby timer value that isn't shorter.
+/// 0 disables the timer.
+pub fn standby_timeout_from_seconds(seconds: u64) -> u8 {
+    match seconds {
+        0 => 0,
+        1..=1200 => seconds.div_ceil(5) as u8,
+        _ => (240 + seconds.div_ceil(30 * 60)).min(251) as u8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_disables_the_timer() {
+        assert_eq!(standby_timeout_seconds(0), None);
+        assert_eq!(standby_timeout_from_seconds(0), 0);
+    }
+
+    #[test]
+    fn round_trips_five_second_units() {
+        assert_eq!(standby_timeout_seconds(1), Some(5));
+        assert_eq!(standby_timeout_seconds(240), Some(20 * 60));
+
+        for value in 1..=240 {
+            let seconds = standby_timeout_seconds(value).unwrap();
+            assert_eq!(standby_timeout_from_seconds(seconds), value);
+        }
+    }
+
+    #[test]
+    fn round_trips_thirty_minute_units() {
+        assert_eq!(standby_timeout_seconds(241), Some(30 * 60));
+        assert_eq!(standby_timeout_seconds(251), Some(5 * 60 * 60 + 30 * 60));
+
+        for value in 241..=251 {
+            let seconds = standby_timeout_seconds(value).unwrap();
+            assert_eq!(standby_timeout_from_seconds(seconds), value);
+        }
+    }
+
+    #[test]
+    fn special_values_map_to_a_timeout_that_is_not_shorter() {
+        assert_eq!(standby_timeout_seconds(252), Some(21 * 60));
+        assert_eq!(standby_timeout_seconds(253), Some(5 * 60 * 60 + 30 * 60));
+        assert_eq!(standby_timeout_seconds(255), Some(21 * 60 + 15));
+
+        assert_eq!(standby_timeout_from_seconds(21 * 60), 241);
+        assert_eq!(standby_timeout_from_seconds(5 * 60 * 60 + 30 * 60), 251);
+        assert_eq!(standby_timeout_from_seconds(21 * 60 + 15), 241);
+    }
+
+    #[test]
+    fn rounds_up_between_units() {
+        assert_eq!(standby_timeout_from_seconds(1), 1);
+        assert_eq!(standby_timeout_from_seconds(6), 2);
+        assert_eq!(standby_timeout_from_seconds(20 * 60 + 1), 241);
+        assert_eq!(standby_timeout_from_seconds(24 * 60 * 60), 251);
+    }
+}
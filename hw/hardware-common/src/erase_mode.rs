@@ -0,0 +1,48 @@
+/// How a device should be erased before it is formatted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EraseMode {
+    /// Only wipe known filesystem signatures.
+    #[default]
+    None,
+    /// Overwrite the whole device with zeroes.
+    Zero,
+    /// Use the drive's ATA secure erase command.
+    AtaSecureErase,
+    /// Use the drive's enhanced ATA secure erase command.
+    AtaSecureEraseEnhanced,
+}
+
+impl EraseMode {
+    /// The value of the UDisks2 `erase` format option, if one should be passed.
+    pub fn udisks_option(&self) -> Option<&'static str> {
+        match self {
+            EraseMode::None => None,
+            EraseMode::Zero => Some("zero"),
+            EraseMode::AtaSecureErase => Some("ata-secure-erase"),
+            EraseMode::AtaSecureEraseEnhanced => Some("ata-secure-erase-enhanced"),
+        }
+    }
+}
+
+impl EraseMode {
+    /// How the erase is described in a [`crate::ChangePlan`], if anything is erased.
+    pub fn describe(&self, target: &str) -> Option<String> {
+        match self {
+            EraseMode::None => None,
+            EraseMode::Zero => Some(format!("Overwrite {target} with zeroes")),
+            EraseMode::AtaSecureErase => Some(format!("Securely erase {target}")),
+            EraseMode::AtaSecureEraseEnhanced => Some(format!(
+                "Securely erase {target}, including reallocated sectors"
+            )),
+        }
+    }
+}
+
+impl From<bool> for EraseMode {
+    fn from(erase: bool) -> Self {
+        match erase {
+            true => EraseMode::Zero,
+            false => EraseMode::None,
+        }
+    }
+}
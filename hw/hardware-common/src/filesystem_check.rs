@@ -0,0 +1,20 @@
+/// The outcome of checking or repairing a filesystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilesystemCheck {
+    /// No errors were found.
+    Clean,
+    /// Errors were found, and have not been repaired.
+    ErrorsFound,
+    /// Errors were found, and have been repaired.
+    Repaired,
+    /// The filesystem can not be checked or repaired. Contains the reason reported by the backend,
+    /// typically the name of a missing tool.
+    Unsupported(String),
+}
+
+impl FilesystemCheck {
+    /// Whether the filesystem is in a consistent state, and safe to mount.
+    pub fn is_safe_to_mount(&self) -> bool {
+        matches!(self, FilesystemCheck::Clean | FilesystemCheck::Repaired)
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt;
+
+use thiserror::Error;
+
+/// How the length of a filesystem label is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelUnit {
+    Bytes,
+    Characters,
+}
+
+impl fmt::Display for LabelUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LabelUnit::Bytes => write!(f, "bytes"),
+            LabelUnit::Characters => write!(f, "characters"),
+        }
+    }
+}
+
+/// Restrictions a filesystem places on its label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LabelLimit {
+    pub max_length: usize,
+    pub unit: LabelUnit,
+    /// Whether the label must be uppercase (e.g. FAT).
+    pub uppercase: bool,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LabelError {
+    #[error("{filesystem} labels can be at most {max_length} {unit}")]
+    TooLong {
+        filesystem: String,
+        max_length: usize,
+        unit: LabelUnit,
+    },
+
+    #[error("{0} labels must be uppercase")]
+    NotUppercase(String),
+
+    #[error("{0} labels can not contain '{1}'")]
+    InvalidCharacter(String, char),
+}
+
+/// Characters FAT does not allow in a volume label.
+const FAT_INVALID_CHARS: &[char] = &[
+    '"', '*', '+', ',', '.', '/', ':', ';', '<', '=', '>', '?', '[', '\\', ']', '|',
+];
+
+impl LabelLimit {
+    const fn new(max_length: usize, unit: LabelUnit, uppercase: bool) -> Self {
+        Self {
+            max_length,
+            unit,
+            uppercase,
+        }
+    }
+
+    /// Returns the label restrictions for a filesystem, as reported by `IdType` (e.g. `ext4`).
+    /// `None` means the filesystem is unknown, and the label is passed through unchecked.
+    pub fn for_filesystem(filesystem_type: &str) -> Option<LabelLimit> {
+        match filesystem_type {
+            "vfat" => Some(Self::new(11, LabelUnit::Bytes, true)),
+            "exfat" => Some(Self::new(11, LabelUnit::Characters, false)),
+            "ext2" | "ext3" | "ext4" => Some(Self::new(16, LabelUnit::Bytes, false)),
+            "ntfs" => Some(Self::new(128, LabelUnit::Characters, false)),
+            "btrfs" => Some(Self::new(255, LabelUnit::Bytes, false)),
+            "xfs" => Some(Self::new(12, LabelUnit::Bytes, false)),
+            "f2fs" => Some(Self::new(512, LabelUnit::Characters, false)),
+            "swap" => Some(Self::new(16, LabelUnit::Bytes, false)),
+            _ => None,
+        }
+    }
+}
+
+/// Checks `label` against the restrictions of `filesystem_type`, so that bad labels can be
+/// rejected before they are sent to the backend.
+pub fn validate_label(filesystem_type: &str, label: &str) -> Result<(), LabelError> {
+    let limit = match LabelLimit::for_filesystem(filesystem_type) {
+        Some(l) => l,
+        None => return Ok(()),
+    };
+
+    let length = match limit.unit {
+        LabelUnit::Bytes => label.len(),
+        LabelUnit::Characters => label.encode_utf16().count(),
+    };
+
+    if length > limit.max_length {
+        return Err(LabelError::TooLong {
+            filesystem: filesystem_type.to_owned(),
+            max_length: limit.max_length,
+            unit: limit.unit,
+        });
+    }
+
+    if limit.uppercase && label.chars().any(|c| c.is_lowercase()) {
+        return Err(LabelError::NotUppercase(filesystem_type.to_owned()));
+    }
+
+    if filesystem_type == "vfat"
+        && let Some(c) = label.chars().find(|c| FAT_INVALID_CHARS.contains(c))
+    {
+        return Err(LabelError::InvalidCharacter(filesystem_type.to_owned(), c));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vfat_limits_length_to_11() {
+        assert_eq!(validate_label("vfat", "ABCDEFGHIJK"), Ok(()));
+        assert_eq!(
+            validate_label("vfat", "ABCDEFGHIJKL"),
+            Err(LabelError::TooLong {
+                filesystem: "vfat".to_owned(),
+                max_length: 11,
+                unit: LabelUnit::Bytes,
+            })
+        );
+    }
+
+    #[test]
+    fn vfat_requires_uppercase() {
+        assert_eq!(
+            validate_label("vfat", "Data"),
+            Err(LabelError::NotUppercase("vfat".to_owned()))
+        );
+    }
+
+    #[test]
+    fn vfat_rejects_invalid_characters() {
+        assert_eq!(
+            validate_label("vfat", "A:B"),
+            Err(LabelError::InvalidCharacter("vfat".to_owned(), ':'))
+        );
+        assert_eq!(
+            validate_label("vfat", "A.B"),
+            Err(LabelError::InvalidCharacter("vfat".to_owned(), '.'))
+        );
+        assert_eq!(validate_label("vfat", "A-B_C 1"), Ok(()));
+    }
+
+    #[test]
+    fn ext4_counts_bytes_not_characters() {
+        // 8 characters, but 16 bytes in UTF-8.
+        assert_eq!(validate_label("ext4", "éééééééé"), Ok(()));
+        // 9 characters, 18 bytes.
+        assert!(matches!(
+            validate_label("ext4", "ééééééééé"),
+            Err(LabelError::TooLong { max_length: 16, .. })
+        ));
+    }
+
+    #[test]
+    fn xfs_limits_length_to_12() {
+        assert_eq!(validate_label("xfs", "abcdefghijkl"), Ok(()));
+        assert!(matches!(
+            validate_label("xfs", "abcdefghijklm"),
+            Err(LabelError::TooLong { max_length: 12, .. })
+        ));
+    }
+
+    #[test]
+    fn ntfs_counts_utf16_units() {
+        assert_eq!(validate_label("ntfs", &"é".repeat(128)), Ok(()));
+        // Characters outside the BMP take two UTF-16 units each.
+        assert_eq!(validate_label("ntfs", &"😀".repeat(64)), Ok(()));
+        assert!(matches!(
+            validate_label("ntfs", &"😀".repeat(65)),
+            Err(LabelError::TooLong {
+                max_length: 128,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn btrfs_limits_length_to_255() {
+        assert_eq!(validate_label("btrfs", &"a".repeat(255)), Ok(()));
+        assert!(matches!(
+            validate_label("btrfs", &"a".repeat(256)),
+            Err(LabelError::TooLong {
+                max_length: 255,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn unknown_filesystems_are_not_checked() {
+        assert_eq!(validate_label("zfs", &"a".repeat(1000)), Ok(()));
+    }
+
+    #[test]
+    fn too_long_message_uses_readable_unit() {
+        let err = validate_label("xfs", "abcdefghijklm").unwrap_err();
+        assert_eq!(err.to_string(), "xfs labels can be at most 12 bytes");
+    }
+}
@@ -0,0 +1,208 @@
+use std::sync::LazyLock;
+
+/// The GPT type for Linux filesystems, also used for LUKS containers.
+const GPT_LINUX_FILESYSTEM: &str = "0fc63daf-8483-4772-8e79-3d69d8477de4";
+const GPT_LINUX_SWAP: &str = "0657fd6d-a4ab-43c4-84e5-0933c84b4f4f";
+/// The GPT type Windows uses for FAT, exFAT and NTFS.
+const GPT_BASIC_DATA: &str = "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7";
+
+/// What a new partition can be formatted with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FilesystemType {
+    #[default]
+    Ext4,
+    Xfs,
+    Btrfs,
+    Vfat,
+    Exfat,
+    Ntfs,
+    F2fs,
+    Swap,
+    /// An encrypted container with nothing in it yet.
+    Luks,
+    /// Leave the partition unformatted.
+    None,
+}
+
+/// The options a filesystem supports when it is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilesystemOptions {
+    /// Whether it can be given a label.
+    pub label: bool,
+    /// Whether it can be created inside a LUKS container.
+    pub encrypt: bool,
+    /// Whether the user creating it can be made its owner, as it has Unix permissions.
+    pub take_ownership: bool,
+}
+
+/// What is known about a [`FilesystemType`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilesystemInfo {
+    pub filesystem: FilesystemType,
+    pub name: &'static str,
+    /// The type blkid reports, and UDisks2 shows as `IdType` (e.g. `ext4`).
+    pub id_type: &'static str,
+    /// The type to pass to UDisks2 when formatting. LUKS containers are made by formatting as
+    /// `empty` with a passphrase.
+    pub format_type: &'static str,
+    /// The partition type the filesystem implies on a GPT partition table.
+    pub gpt_type: Option<&'static str>,
+    /// The partition type the filesystem implies on an MBR partition table.
+    pub dos_type: Option<&'static str>,
+    pub options: FilesystemOptions,
+}
+
+impl FilesystemOptions {
+    const fn new(label: bool, encrypt: bool, take_ownership: bool) -> Self {
+        Self {
+            label,
+            encrypt,
+            take_ownership,
+        }
+    }
+}
+
+impl FilesystemInfo {
+    const fn new(
+        filesystem: FilesystemType,
+        name: &'static str,
+        id_type: &'static str,
+        format_type: &'static str,
+        gpt_type: Option<&'static str>,
+        dos_type: Option<&'static str>,
+        options: FilesystemOptions,
+    ) -> Self {
+        Self {
+            filesystem,
+            name,
+            id_type,
+            format_type,
+            gpt_type,
+            dos_type,
+            options,
+        }
+    }
+
+    /// The partition type the filesystem implies on a `table_type` partition table. `None` if it
+    /// implies none, and the default of the table should be used.
+    pub fn partition_type(&self, table_type: &str) -> Option<&'static str> {
+        match table_type {
+            "gpt" => self.gpt_type,
+            "dos" => self.dos_type,
+            _ => None,
+        }
+    }
+}
+
+impl FilesystemType {
+    pub fn info(&self) -> &'static FilesystemInfo {
+        FILESYSTEM_TYPES
+            .iter()
+            .find(|f| f.filesystem == *self)
+            .expect("every filesystem type is in FILESYSTEM_TYPES")
+    }
+
+    /// Finds a filesystem by the type blkid reports, e.g. `ext4` or `crypto_LUKS`.
+    pub fn from_id_type(id_type: &str) -> Option<FilesystemType> {
+        FILESYSTEM_TYPES
+            .iter()
+            .find(|f| f.id_type == id_type)
+            .map(|f| f.filesystem)
+    }
+}
+
+pub static FILESYSTEM_NAMES: LazyLock<Vec<String>> =
+    LazyLock::new(|| FILESYSTEM_TYPES.iter().map(|f| f.name.to_owned()).collect());
+
+pub static FILESYSTEM_TYPES: [FilesystemInfo; 10] = [
+    FilesystemInfo::new(
+        FilesystemType::Ext4,
+        "Ext4 (Linux)",
+        "ext4",
+        "ext4",
+        Some(GPT_LINUX_FILESYSTEM),
+        Some("0x83"),
+        FilesystemOptions::new(true, true, true),
+    ),
+    FilesystemInfo::new(
+        FilesystemType::Xfs,
+        "XFS (Linux)",
+        "xfs",
+        "xfs",
+        Some(GPT_LINUX_FILESYSTEM),
+        Some("0x83"),
+        FilesystemOptions::new(true, true, true),
+    ),
+    FilesystemInfo::new(
+        FilesystemType::Btrfs,
+        "Btrfs (Linux)",
+        "btrfs",
+        "btrfs",
+        Some(GPT_LINUX_FILESYSTEM),
+        Some("0x83"),
+        FilesystemOptions::new(true, true, true),
+    ),
+    FilesystemInfo::new(
+        FilesystemType::Vfat,
+        "FAT (All systems)",
+        "vfat",
+        "vfat",
+        Some(GPT_BASIC_DATA),
+        Some("0x0c"),
+        FilesystemOptions::new(true, true, false),
+    ),
+    FilesystemInfo::new(
+        FilesystemType::Exfat,
+        "exFAT (All systems)",
+        "exfat",
+        "exfat",
+        Some(GPT_BASIC_DATA),
+        Some("0x07"),
+        FilesystemOptions::new(true, true, false),
+    ),
+    FilesystemInfo::new(
+        FilesystemType::Ntfs,
+        "NTFS (Windows)",
+        "ntfs",
+        "ntfs",
+        Some(GPT_BASIC_DATA),
+        Some("0x07"),
+        FilesystemOptions::new(true, true, false),
+    ),
+    FilesystemInfo::new(
+        FilesystemType::F2fs,
+        "F2FS (Flash storage)",
+        "f2fs",
+        "f2fs",
+        Some(GPT_LINUX_FILESYSTEM),
+        Some("0x83"),
+        FilesystemOptions::new(true, true, true),
+    ),
+    FilesystemInfo::new(
+        FilesystemType::Swap,
+        "Linux Swap",
+        "swap",
+        "swap",
+        Some(GPT_LINUX_SWAP),
+        Some("0x82"),
+        FilesystemOptions::new(true, false, false),
+    ),
+    FilesystemInfo::new(
+        FilesystemType::Luks,
+        "Encrypted (LUKS)",
+        "crypto_LUKS",
+        "empty",
+        Some(GPT_LINUX_FILESYSTEM),
+        Some("0x83"),
+        FilesystemOptions::new(false, false, false),
+    ),
+    FilesystemInfo::new(
+        FilesystemType::None,
+        "No Filesystem",
+        "",
+        "empty",
+        None,
+        None,
+        FilesystemOptions::new(false, false, false),
+    ),
+];
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use std::{
+    fs::OpenOptions,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// Progress of a long running copy, such as creating or restoring a disk image.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CopyProgress {
+    pub bytes_done: u64,
+    pub total_bytes: u64,
+}
+
+impl CopyProgress {
+    /// Progress as a value between 0 and 1.
+    pub fn fraction(&self) -> f32 {
+        match self.total_bytes {
+            0 => 0.,
+            total => self.bytes_done as f32 / total as f32,
+        }
+    }
+}
+
+/// Used to cancel a long running operation from another task.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A disk image written to a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskImage {
+    pub path: String,
+    pub size: u64,
+    /// Hex encoded sha256 of the image contents.
+    pub sha256: String,
+}
+
+/// Compression applied to an image file, determined by its extension.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageCompression {
+    #[default]
+    None,
+    Xz,
+    Zstd,
+}
+
+impl ImageCompression {
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("xz") => ImageCompression::Xz,
+            Some(e) if e.eq_ignore_ascii_case("zst") => ImageCompression::Zstd,
+            _ => ImageCompression::None,
+        }
+    }
+}
+
+/// Creates an empty disk image of `size` bytes. The file is sparse, so it takes up no space until
+/// it is written to.
+pub fn create_blank_image(path: impl AsRef<Path>, size: u64) -> Result<()> {
+    let file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.set_len(size)?;
+    Ok(())
+}
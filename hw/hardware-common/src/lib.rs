@@ -1,13 +1,39 @@
+mod alignment;
+mod backend;
+mod benchmark;
+mod byte_size;
+mod change_plan;
 mod create_partition_info;
+mod device_event;
 mod drive;
-mod format;
+mod drive_settings;
+mod erase_mode;
+mod filesystem_check;
+mod filesystem_label;
+mod filesystem_type;
+mod image;
+mod mount_options;
 mod partition;
 mod partition_type;
+mod smart;
 mod usage;
 
+pub use alignment::*;
+pub use backend::*;
+pub use benchmark::*;
+pub use byte_size::*;
+pub use change_plan::*;
 pub use create_partition_info::*;
+pub use device_event::*;
 pub use drive::*;
-pub use format::*;
+pub use drive_settings::*;
+pub use erase_mode::*;
+pub use filesystem_check::*;
+pub use filesystem_label::*;
+pub use filesystem_type::*;
+pub use image::*;
+pub use mount_options::*;
 pub use partition::*;
 pub use partition_type::*;
+pub use smart::*;
 pub use usage::*;
@@ -0,0 +1,212 @@
+/// The persistent mount configuration of a filesystem, as stored in `/etc/fstab`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MountOptions {
+    pub mount_at_startup: bool,
+    pub show_in_ui: bool,
+    pub require_auth: bool,
+    pub display_name: Option<String>,
+    pub icon_name: Option<String>,
+    pub symbolic_icon_name: Option<String>,
+    /// Any remaining mount options, comma separated.
+    pub options: String,
+    pub mount_point: String,
+    /// How the device is identified, e.g. `UUID=...`, `LABEL=...` or `/dev/sda1`.
+    pub identify_as: String,
+    pub filesystem_type: String,
+}
+
+const NO_AUTO: &str = "noauto";
+const SHOW: &str = "x-gvfs-show";
+const REQUIRE_AUTH: &str = "x-udisks-auth";
+const NAME: &str = "x-gvfs-name=";
+const ICON: &str = "x-gvfs-icon=";
+const SYMBOLIC_ICON: &str = "x-gvfs-symbolic-icon=";
+
+impl MountOptions {
+    /// Builds the fstab options column, folding the flags and names into their `x-gvfs-*` and
+    /// `x-udisks-*` options.
+    pub fn to_options_string(&self) -> String {
+        let mut options: Vec<String> = self
+            .options
+            .split(',')
+            .map(|o| o.trim())
+            .filter(|o| !o.is_empty())
+            .map(|o| o.to_owned())
+            .collect();
+
+        if !self.mount_at_startup {
+            options.push(NO_AUTO.to_owned());
+        }
+
+        if self.show_in_ui {
+            options.push(SHOW.to_owned());
+        }
+
+        if self.require_auth {
+            options.push(REQUIRE_AUTH.to_owned());
+        }
+
+        if let Some(name) = &self.display_name {
+            options.push(format!("{}{}", NAME, escape(name)));
+        }
+
+        if let Some(icon) = &self.icon_name {
+            options.push(format!("{}{}", ICON, escape(icon)));
+        }
+
+        if let Some(icon) = &self.symbolic_icon_name {
+            options.push(format!("{}{}", SYMBOLIC_ICON, escape(icon)));
+        }
+
+        match options.is_empty() {
+            true => "defaults".to_owned(),
+            false => options.join(","),
+        }
+    }
+
+    /// Reads the columns of an fstab entry, splitting the options column back into fields.
+    pub fn from_fstab(
+        identify_as: &str,
+        mount_point: &str,
+        filesystem_type: &str,
+        options: &str,
+    ) -> Self {
+        let mut result = MountOptions {
+            mount_at_startup: true,
+            identify_as: identify_as.to_owned(),
+            mount_point: mount_point.to_owned(),
+            filesystem_type: filesystem_type.to_owned(),
+            ..Default::default()
+        };
+
+        let mut remaining = vec![];
+
+        for option in options.split(',').map(|o| o.trim()) {
+            match option {
+                "" | "defaults" => {}
+                NO_AUTO => result.mount_at_startup = false,
+                SHOW => result.show_in_ui = true,
+                REQUIRE_AUTH => result.require_auth = true,
+                _ => {
+                    if let Some(name) = option.strip_prefix(NAME) {
+                        result.display_name = Some(unescape(name));
+                    } else if let Some(icon) = option.strip_prefix(SYMBOLIC_ICON) {
+                        result.symbolic_icon_name = Some(unescape(icon));
+                    } else if let Some(icon) = option.strip_prefix(ICON) {
+                        result.icon_name = Some(unescape(icon));
+                    } else {
+                        remaining.push(option);
+                    }
+                }
+            }
+        }
+
+        result.options = remaining.join(",");
+        result
+    }
+}
+
+/// Escapes the characters fstab can not hold in a column or an option value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\134")
+        .replace(' ', "\\040")
+        .replace('\t', "\\011")
+        .replace('\n', "\\012")
+        .replace(',', "\\054")
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\040", " ")
+        .replace("\\011", "\t")
+        .replace("\\012", "\n")
+        .replace("\\054", ",")
+        .replace("\\134", "\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(options: &MountOptions) -> MountOptions {
+        MountOptions::from_fstab(
+            &options.identify_as,
+            &options.mount_point,
+            &options.filesystem_type,
+            &options.to_options_string(),
+        )
+    }
+
+    #[test]
+    fn defaults_round_trip() {
+        let options = MountOptions {
+            mount_at_startup: true,
+            identify_as: "UUID=1234".to_owned(),
+            mount_point: "/mnt/data".to_owned(),
+            filesystem_type: "ext4".to_owned(),
+            ..Default::default()
+        };
+
+        assert_eq!(options.to_options_string(), "defaults");
+        assert_eq!(round_trip(&options), options);
+    }
+
+    #[test]
+    fn flags_and_names_round_trip() {
+        let options = MountOptions {
+            mount_at_startup: false,
+            show_in_ui: true,
+            require_auth: true,
+            display_name: Some("Data".to_owned()),
+            icon_name: Some("drive-harddisk".to_owned()),
+            symbolic_icon_name: Some("drive-harddisk-symbolic".to_owned()),
+            options: "nosuid,nodev".to_owned(),
+            mount_point: "/mnt/data".to_owned(),
+            identify_as: "LABEL=Data".to_owned(),
+            filesystem_type: "btrfs".to_owned(),
+        };
+
+        assert_eq!(
+            options.to_options_string(),
+            "nosuid,nodev,noauto,x-gvfs-show,x-udisks-auth,x-gvfs-name=Data,\
+             x-gvfs-icon=drive-harddisk,x-gvfs-symbolic-icon=drive-harddisk-symbolic"
+        );
+        assert_eq!(round_trip(&options), options);
+    }
+
+    #[test]
+    fn escaped_characters_round_trip() {
+        let options = MountOptions {
+            mount_at_startup: true,
+            display_name: Some("My Disk, backup\\old\tcopy".to_owned()),
+            identify_as: "/dev/sda1".to_owned(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options.to_options_string(),
+            "x-gvfs-name=My\\040Disk\\054\\040backup\\134old\\011copy"
+        );
+        assert_eq!(round_trip(&options), options);
+    }
+
+    #[test]
+    fn escaped_backslash_is_not_unescaped_twice() {
+        let options = MountOptions {
+            mount_at_startup: true,
+            display_name: Some("a\\040b".to_owned()),
+            ..Default::default()
+        };
+
+        assert_eq!(round_trip(&options), options);
+    }
+
+    #[test]
+    fn unknown_options_are_kept() {
+        let options = MountOptions::from_fstab("/dev/sda1", "/", "ext4", "defaults,ro,x-foo=bar");
+
+        assert!(options.mount_at_startup);
+        assert_eq!(options.options, "ro,x-foo=bar");
+    }
+}
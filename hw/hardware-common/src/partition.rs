@@ -1,8 +1,40 @@
 use anyhow::Result;
 
+use crate::{ChangePlan, DiskImage, EraseMode, FilesystemCheck, MountOptions, Usage};
+
 pub trait Partition {
     fn name(&self) -> String;
 
+    /// The name given to the partition in the partition table, which may be empty.
+    fn label(&self) -> String;
+
+    fn number(&self) -> u32;
+
+    /// Where the partition starts on the drive, in bytes.
+    fn offset(&self) -> u64;
+
+    fn size(&self) -> u64;
+
+    /// What the partition holds, e.g. "ext4" or "crypto_LUKS". Empty if unknown.
+    fn id_type(&self) -> String;
+
+    /// The partition type, as shown to the user.
+    fn partition_type(&self) -> String;
+
+    fn uuid(&self) -> String;
+
+    /// The device node, e.g. /dev/sda1, if it could be found.
+    fn device_path(&self) -> Option<String>;
+
+    /// Where the partition is mounted, and how full it is. `None` if it isn't mounted.
+    fn usage(&self) -> Option<&Usage>;
+
+    fn is_encrypted(&self) -> bool;
+
+    /// The unlocked contents of an encrypted partition. `None` if the partition isn't
+    /// encrypted, or is locked.
+    fn cleartext(&self) -> Option<&Self>;
+
     fn connect(&mut self) -> impl std::future::Future<Output = Result<()>> + Send;
 
     fn mount(&self) -> impl std::future::Future<Output = Result<()>> + Send;
@@ -11,14 +43,24 @@ pub trait Partition {
 
     fn delete(&self) -> impl std::future::Future<Output = Result<()>> + Send;
 
+    /// The changes [`Partition::delete`] would make, checked but not written.
+    fn plan_delete(&self) -> impl std::future::Future<Output = Result<ChangePlan>> + Send;
+
     fn format(
         &self,
         name: String,
-        erase: bool,
-        partition_type: String,
+        erase: EraseMode,
+        filesystem_type: String,
     ) -> impl std::future::Future<Output = Result<()>> + Send;
 
-    //TODO: implement
+    /// The changes [`Partition::format`] would make, checked but not written.
+    fn plan_format(
+        &self,
+        name: String,
+        erase: EraseMode,
+        filesystem_type: String,
+    ) -> impl std::future::Future<Output = Result<ChangePlan>> + Send;
+
     fn edit_partition(
         &self,
         partition_type: String,
@@ -26,23 +68,35 @@ pub trait Partition {
         flags: u64,
     ) -> impl std::future::Future<Output = Result<()>> + Send;
 
-    //TODO: implement
     fn edit_filesystem_label(
         &self,
         label: String,
     ) -> impl std::future::Future<Output = Result<()>> + Send;
 
-    //TODO: implement
-    fn change_passphrase(&self) -> impl std::future::Future<Output = Result<()>> + Send;
+    fn unlock(&self, passphrase: String) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn lock(&self) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn change_passphrase(
+        &self,
+        passphrase: String,
+        new_passphrase: String,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
 
-    //TODO: implement
     fn resize(&self, new_size_bytes: u64) -> impl std::future::Future<Output = Result<()>> + Send;
 
-    //TODO: implement
-    fn check_filesystem(&self) -> impl std::future::Future<Output = Result<()>> + Send;
+    /// The changes [`Partition::resize`] would make, checked but not written.
+    fn plan_resize(
+        &self,
+        new_size_bytes: u64,
+    ) -> impl std::future::Future<Output = Result<ChangePlan>> + Send;
 
-    //TODO: implement
-    fn repair_filesystem(&self) -> impl std::future::Future<Output = Result<()>> + Send;
+    fn check_filesystem(&self)
+    -> impl std::future::Future<Output = Result<FilesystemCheck>> + Send;
+
+    fn repair_filesystem(
+        &self,
+    ) -> impl std::future::Future<Output = Result<FilesystemCheck>> + Send;
 
     //TODO: implement
     fn take_ownership(
@@ -50,10 +104,14 @@ pub trait Partition {
         recursive: bool,
     ) -> impl std::future::Future<Output = Result<()>> + Send;
 
-    //TODO: implement. See how edit mount options -> User session defaults works in gnome-disks.
+    /// Removes any persistent mount options, reverting to the user session defaults.
     fn default_mount_options(&self) -> impl std::future::Future<Output = Result<()>> + Send;
 
-    //TODO: implement. Look at gnome-disks -> partition -> edit mount options. Likely make all params optional.
+    /// Returns the persistent mount options, or `None` if the user session defaults are in use.
+    fn mount_options(
+        &self,
+    ) -> impl std::future::Future<Output = Result<Option<MountOptions>>> + Send;
+
     fn edit_mount_options(
         &self,
         mount_at_startup: bool,
@@ -71,9 +129,16 @@ pub trait Partition {
     //TODO: implement
     fn edit_encrytion_options(&self) -> impl std::future::Future<Output = Result<()>> + Send;
 
-    //TODO: implement. creates a *.img of self.
+    /// Creates a *.img of self.
     fn create_image(
         &self,
         output_path: String,
-    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    ) -> impl std::future::Future<Output = Result<DiskImage>> + Send;
+
+    /// Writes the image at `input_path` over the contents of self, then verifies it by reading
+    /// it back. Any mounted filesystems are unmounted first.
+    fn restore_image(
+        &self,
+        input_path: String,
+    ) -> impl std::future::Future<Output = Result<DiskImage>> + Send;
 }
@@ -24,6 +24,64 @@ pub enum PartitionTypeInfoFlags {
     System = (1 << 4),
 }
 
+/// The operating system a partition type belongs to, used to group types when picking one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PartitionTypeGroup {
+    Linux,
+    Windows,
+    Apple,
+    /// Types of other systems, and those not tied to any (e.g. EFI System).
+    Other,
+}
+
+impl PartitionTypeGroup {
+    pub const ALL: [PartitionTypeGroup; 4] = [
+        PartitionTypeGroup::Linux,
+        PartitionTypeGroup::Windows,
+        PartitionTypeGroup::Apple,
+        PartitionTypeGroup::Other,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PartitionTypeGroup::Linux => "Linux",
+            PartitionTypeGroup::Windows => "Windows",
+            PartitionTypeGroup::Apple => "Apple",
+            PartitionTypeGroup::Other => "Other",
+        }
+    }
+
+    fn from_subtype(table_subtype: &str) -> Self {
+        match table_subtype {
+            "linux" => PartitionTypeGroup::Linux,
+            "microsoft" => PartitionTypeGroup::Windows,
+            "apple" => PartitionTypeGroup::Apple,
+            _ => PartitionTypeGroup::Other,
+        }
+    }
+}
+
+/// MBR type codes and the GPT types that hold the same contents.
+const DOS_TO_GPT: [(&str, &str); 17] = [
+    ("0x01", "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7"),
+    ("0x04", "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7"),
+    ("0x06", "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7"),
+    ("0x07", "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7"),
+    ("0x0b", "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7"),
+    ("0x0c", "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7"),
+    ("0x0e", "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7"),
+    ("0x27", "de94bba4-06d1-4d40-a16a-bfd50179d6ac"),
+    ("0x82", "0657fd6d-a4ab-43c4-84e5-0933c84b4f4f"),
+    ("0x83", "0fc63daf-8483-4772-8e79-3d69d8477de4"),
+    ("0x8e", "e6d6d379-f507-44c2-a23c-238f2a3df928"),
+    ("0xa5", "516e7cb4-6ecf-11d6-8ff8-00022d09712b"),
+    ("0xa8", "55465300-0000-11aa-aa11-00306543ecac"),
+    ("0xaf", "48465300-0000-11aa-aa11-00306543ecac"),
+    ("0xbf", "6a85cf4d-1dd2-11b2-99a6-080020736631"),
+    ("0xef", "c12a7328-f81f-11d2-ba4b-00a0c93ec93b"),
+    ("0xfd", "a19d880f-05fc-4d3b-a006-743f0f84911e"),
+];
+
 /// Detailed information about a partition type.
 ///
 /// `table_subtype` is used to break the set of partition types for
@@ -67,6 +125,67 @@ impl PartitionTypeInfo {
             None => None,
         }
     }
+
+    /// Finds `type_id` among the partition types known for `table_type` (e.g. `gpt` or `dos`).
+    pub fn find_for_table(table_type: &str, type_id: &str) -> Option<PartitionTypeInfo> {
+        PARTITION_TYPES
+            .iter()
+            .find(|p| p.table_type == table_type && p.ty.eq_ignore_ascii_case(type_id))
+            .cloned()
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn group(&self) -> PartitionTypeGroup {
+        PartitionTypeGroup::from_subtype(self.table_subtype)
+    }
+
+    /// The known partition types of `table_type` (e.g. `gpt` or `dos`).
+    pub fn for_table(table_type: &str) -> impl Iterator<Item = &'static PartitionTypeInfo> {
+        PARTITION_TYPES
+            .iter()
+            .filter(move |p| p.table_type == table_type)
+    }
+
+    /// The known partition types of `table_type`, split into their groups. Groups without any
+    /// types are left out.
+    pub fn grouped_for_table(
+        table_type: &str,
+    ) -> Vec<(PartitionTypeGroup, Vec<&'static PartitionTypeInfo>)> {
+        PartitionTypeGroup::ALL
+            .into_iter()
+            .map(|group| {
+                let types = Self::for_table(table_type)
+                    .filter(|p| p.group() == group)
+                    .collect();
+                (group, types)
+            })
+            .filter(|(_, types): &(_, Vec<_>)| !types.is_empty())
+            .collect()
+    }
+
+    /// The GPT type for the contents of an MBR partition of type `code` (e.g. `0x83`), for
+    /// moving a partition from an MBR table to a GPT one.
+    pub fn dos_to_gpt(code: &str) -> Option<&'static PartitionTypeInfo> {
+        let code = code.trim().to_ascii_lowercase();
+        let code = match code.strip_prefix("0x") {
+            Some(hex) => u8::from_str_radix(hex, 16).ok()?,
+            None => u8::from_str_radix(&code, 16).ok()?,
+        };
+
+        DOS_TO_GPT
+            .iter()
+            .find(|(dos, _)| u8::from_str_radix(&dos[2..], 16).ok() == Some(code))
+            .and_then(|(_, gpt)| PARTITION_TYPES.iter().find(|p| p.ty == *gpt))
+    }
+
+    /// Whether this type may only be used when creating a partition, and not when changing
+    /// the type of an existing one.
+    pub fn is_create_only(&self) -> bool {
+        matches!(self.flags, PartitionTypeInfoFlags::CreateOnly)
+    }
 }
 
 pub static PARTITION_NAMES: LazyLock<Vec<String>> = LazyLock::new(|| {
@@ -83,6 +202,84 @@ pub static COMMON_PARTITION_NAMES: LazyLock<Vec<String>> = LazyLock::new(|| {
         .collect()
 });
 
+/// The partition types offered for one partition table type, in the order of their
+/// [`PartitionTypeGroup`]s, with a name for each to show in a dropdown.
+#[derive(Debug, Clone, Default)]
+pub struct PartitionTypeList {
+    pub types: Vec<&'static PartitionTypeInfo>,
+    pub names: Vec<String>,
+}
+
+/// The partition table types there are [`PartitionTypeList`]s for.
+const LIST_TABLE_TYPES: [&str; 3] = ["gpt", "dos", "apm"];
+
+static COMMON_LISTS: LazyLock<Vec<PartitionTypeList>> = LazyLock::new(|| {
+    LIST_TABLE_TYPES
+        .iter()
+        .map(|table_type| {
+            PartitionTypeList::new(
+                COMMON_PARTITION_TYPES
+                    .iter()
+                    .filter(|p| p.table_type == *table_type),
+            )
+        })
+        .collect()
+});
+
+static EDIT_LISTS: LazyLock<Vec<PartitionTypeList>> = LazyLock::new(|| {
+    LIST_TABLE_TYPES
+        .iter()
+        .map(|table_type| {
+            PartitionTypeList::new(
+                PartitionTypeInfo::for_table(table_type).filter(|p| !p.is_create_only()),
+            )
+        })
+        .collect()
+});
+
+static EMPTY_LIST: PartitionTypeList = PartitionTypeList {
+    types: vec![],
+    names: vec![],
+};
+
+impl PartitionTypeList {
+    fn new(types: impl Iterator<Item = &'static PartitionTypeInfo>) -> Self {
+        let mut types: Vec<_> = types.collect();
+        types.sort_by_key(|p| p.group());
+
+        let names = types
+            .iter()
+            .map(|p| format!("{}: {} - {}", p.group().name(), p.name, p.ty))
+            .collect();
+
+        Self { types, names }
+    }
+
+    fn find(lists: &'static [PartitionTypeList], table_type: &str) -> &'static PartitionTypeList {
+        LIST_TABLE_TYPES
+            .iter()
+            .position(|t| *t == table_type)
+            .map_or(&EMPTY_LIST, |i| &lists[i])
+    }
+
+    /// The common types to pick from when creating a partition on a `table_type` table.
+    pub fn create(table_type: &str) -> &'static PartitionTypeList {
+        Self::find(&COMMON_LISTS, table_type)
+    }
+
+    /// Every type an existing partition on a `table_type` table can be changed to.
+    pub fn edit(table_type: &str) -> &'static PartitionTypeList {
+        Self::find(&EDIT_LISTS, table_type)
+    }
+
+    /// Where `type_id` is in the list, ignoring case.
+    pub fn position(&self, type_id: &str) -> Option<usize> {
+        self.types
+            .iter()
+            .position(|p| p.ty.eq_ignore_ascii_case(type_id))
+    }
+}
+
 pub static COMMON_PARTITION_TYPES: [PartitionTypeInfo; 20] = [
     // System/Boot partitions
     PartitionTypeInfo::new(
@@ -1848,3 +2045,94 @@ pub static PARTITION_TYPES: [PartitionTypeInfo; 228] = [
         PartitionTypeInfoFlags::None,
     ),
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_mbr_types_to_gpt() {
+        let cases = [
+            ("0x83", "0fc63daf-8483-4772-8e79-3d69d8477de4"),
+            ("0x82", "0657fd6d-a4ab-43c4-84e5-0933c84b4f4f"),
+            ("0x07", "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7"),
+            ("0xef", "c12a7328-f81f-11d2-ba4b-00a0c93ec93b"),
+            ("0x8e", "e6d6d379-f507-44c2-a23c-238f2a3df928"),
+            ("0xfd", "a19d880f-05fc-4d3b-a006-743f0f84911e"),
+        ];
+
+        for (dos, gpt) in cases {
+            let info = PartitionTypeInfo::dos_to_gpt(dos).unwrap();
+            assert_eq!((info.table_type, info.ty), ("gpt", gpt), "{dos}");
+        }
+    }
+
+    #[test]
+    fn reads_mbr_types_however_they_are_written() {
+        for code in ["83", "0X83", " 0x83 "] {
+            let info = PartitionTypeInfo::dos_to_gpt(code).unwrap();
+            assert_eq!(info.ty, "0fc63daf-8483-4772-8e79-3d69d8477de4", "{code}");
+        }
+    }
+
+    #[test]
+    fn unmapped_mbr_types_have_no_gpt_type() {
+        //Extended partitions only exist in MBR tables.
+        assert!(PartitionTypeInfo::dos_to_gpt("0x05").is_none());
+        assert!(PartitionTypeInfo::dos_to_gpt("not a type").is_none());
+    }
+
+    #[test]
+    fn lists_only_the_types_of_a_table() {
+        for table_type in ["dos", "gpt"] {
+            let types: Vec<_> = PartitionTypeInfo::for_table(table_type).collect();
+
+            assert!(!types.is_empty());
+            assert!(types.iter().all(|p| p.table_type == table_type));
+        }
+
+        assert_eq!(PartitionTypeInfo::for_table("sun").count(), 0);
+    }
+
+    #[test]
+    fn groups_every_type_of_a_table_once() {
+        for table_type in ["dos", "gpt"] {
+            let grouped = PartitionTypeInfo::grouped_for_table(table_type);
+            let groups: Vec<_> = grouped.iter().map(|(group, _)| *group).collect();
+
+            //In the order they are listed, and only those with types.
+            assert!(groups.is_sorted());
+            assert!(grouped.iter().all(|(_, types)| !types.is_empty()));
+            assert!(
+                grouped
+                    .iter()
+                    .all(|(group, types)| types.iter().all(|p| p.group() == *group))
+            );
+
+            let count: usize = grouped.iter().map(|(_, types)| types.len()).sum();
+            assert_eq!(count, PartitionTypeInfo::for_table(table_type).count());
+        }
+    }
+
+    #[test]
+    fn groups_types_by_system() {
+        let group = |ty| {
+            PartitionTypeInfo::find_for_table("gpt", ty)
+                .unwrap()
+                .group()
+        };
+
+        assert_eq!(
+            group("0fc63daf-8483-4772-8e79-3d69d8477de4"),
+            PartitionTypeGroup::Linux
+        );
+        assert_eq!(
+            group("ebd0a0a2-b9e5-4433-87c0-68b6b72699c7"),
+            PartitionTypeGroup::Windows
+        );
+        assert_eq!(
+            group("c12a7328-f81f-11d2-ba4b-00a0c93ec93b"),
+            PartitionTypeGroup::Other
+        );
+    }
+}
@@ -0,0 +1,147 @@
+/// The protocol a drive reports its SMART data through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmartProtocol {
+    Ata,
+    Nvme,
+}
+
+/// The overall health of a drive, as judged from its SMART data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmartAssessment {
+    /// No problems were reported.
+    Healthy,
+    /// Some attributes are, or have been, past their threshold, or there are bad sectors. The
+    /// drive still works, but should be watched.
+    Warning,
+    /// The drive predicts its own failure. Data should be backed up immediately.
+    Failing,
+}
+
+/// The kind of self-test to run on a drive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTestType {
+    /// A quick test of the drive's electronics and a sample of the media, taking a few minutes.
+    Short,
+    /// A full scan of the media, which can take hours.
+    Extended,
+}
+
+impl SelfTestType {
+    /// The value of the UDisks2 `type` argument to `SmartSelftestStart`.
+    pub fn udisks_option(&self) -> &'static str {
+        match self {
+            SelfTestType::Short => "short",
+            SelfTestType::Extended => "extended",
+        }
+    }
+}
+
+/// The state of the most recent self-test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelfTestStatus {
+    /// No self-test has been run.
+    NotRun,
+    /// A self-test is running, with the given percentage remaining.
+    InProgress(i32),
+    /// The last self-test completed without errors.
+    Passed,
+    /// The last self-test was aborted or interrupted.
+    Aborted,
+    /// The last self-test failed. Contains the failure reported by the drive.
+    Failed(String),
+}
+
+impl SelfTestStatus {
+    /// Parses the `SmartSelftestStatus` property, shared by the ATA and NVMe interfaces.
+    pub fn from_udisks(status: &str, percent_remaining: i32) -> Self {
+        match status {
+            "" => SelfTestStatus::NotRun,
+            "inprogress" => SelfTestStatus::InProgress(percent_remaining),
+            "success" => SelfTestStatus::Passed,
+            "aborted" | "interrupted" => SelfTestStatus::Aborted,
+            failure => SelfTestStatus::Failed(failure.to_owned()),
+        }
+    }
+}
+
+/// A single SMART attribute. Normalized values only exist for ATA drives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmartAttribute {
+    /// The ATA attribute ID.
+    pub id: Option<u8>,
+    pub name: String,
+    /// The current normalized value.
+    pub value: Option<i32>,
+    /// The lowest normalized value recorded.
+    pub worst: Option<i32>,
+    /// The normalized value at or below which the attribute is failing.
+    pub threshold: Option<i32>,
+    /// The raw value, formatted with its unit.
+    pub pretty: String,
+    /// The attribute is currently past its threshold.
+    pub failing: bool,
+    /// The attribute has been past its threshold at some point.
+    pub failed_in_past: bool,
+}
+
+/// The SMART data of a drive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmartReport {
+    pub protocol: SmartProtocol,
+    pub assessment: SmartAssessment,
+    pub temperature_celsius: Option<f64>,
+    pub power_on_hours: Option<u64>,
+    /// Reallocated (ATA) sectors. NVMe drives don't report these.
+    pub reallocated_sectors: Option<u64>,
+    pub self_test: SelfTestStatus,
+    /// When the data was last read from the drive, in seconds since the Unix epoch.
+    pub updated: u64,
+    pub attributes: Vec<SmartAttribute>,
+}
+
+/// Converts a temperature reported in kelvin, where 0 means unknown.
+pub fn kelvin_to_celsius(kelvin: f64) -> Option<f64> {
+    match kelvin > 0. {
+        true => Some(kelvin - 273.15),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_self_test_status() {
+        let cases = [
+            ("", 0, SelfTestStatus::NotRun),
+            ("inprogress", 40, SelfTestStatus::InProgress(40)),
+            ("success", 0, SelfTestStatus::Passed),
+            ("aborted", 0, SelfTestStatus::Aborted),
+            ("interrupted", 0, SelfTestStatus::Aborted),
+            ("fatal", 0, SelfTestStatus::Failed("fatal".into())),
+            ("error_read", 0, SelfTestStatus::Failed("error_read".into())),
+            (
+                "known_seg_fail",
+                0,
+                SelfTestStatus::Failed("known_seg_fail".into()),
+            ),
+        ];
+
+        for (status, percent_remaining, expected) in cases {
+            assert_eq!(
+                SelfTestStatus::from_udisks(status, percent_remaining),
+                expected,
+                "{status:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_statuses_are_failures() {
+        assert_eq!(
+            SelfTestStatus::from_udisks("something_new", 10),
+            SelfTestStatus::Failed("something_new".into())
+        );
+    }
+}
@@ -1,38 +1,300 @@
 use anyhow::Result;
 use serde::Deserialize;
-use std::process::Command;
+use std::{
+    ffi::CString,
+    fmt, fs,
+    os::unix::fs::{FileTypeExt, MetadataExt},
+    str::FromStr,
+};
 
+/// A device's major and minor numbers, e.g. `8:1` for /dev/sda1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+pub struct DeviceNumber {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl DeviceNumber {
+    /// Splits a Linux `dev_t`, as found in `st_rdev` or the UDisks2 `DeviceNumber` property.
+    pub fn from_dev_t(dev: u64) -> Self {
+        Self {
+            major: (((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0x0000_0fff)) as u32,
+            minor: (((dev >> 12) & 0xffff_ff00) | (dev & 0x0000_00ff)) as u32,
+        }
+    }
+}
+
+impl fmt::Display for DeviceNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for DeviceNumber {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (major, minor) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("Invalid device number: {s}"))?;
+
+        Ok(Self {
+            major: major.parse()?,
+            minor: minor.parse()?,
+        })
+    }
+}
+
+/// A mounted filesystem and how full it is.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Usage {
+    /// What was mounted, usually a device node such as /dev/sda1.
     pub filesystem: String,
+    /// The block device behind the filesystem. Partitions are matched to their usage by this.
+    pub device: DeviceNumber,
+    pub fs_type: String,
+    /// The options `mount_point` is mounted with, e.g. `rw` and `relatime`.
+    pub options: Vec<String>,
     pub blocks: u64,
     pub used: u64,
     pub available: u64,
     pub percent: u32,
+    /// Where the whole filesystem is mounted, or its first mount point if only parts of it are.
     pub mount_point: String,
+    /// Every place the filesystem is mounted, including bind mounts and btrfs subvolumes.
+    pub mount_points: Vec<String>,
+}
+
+/// One line of /proc/self/mountinfo.
+struct MountInfo {
+    device: DeviceNumber,
+    /// The directory within the filesystem that is mounted, e.g. a btrfs subvolume.
+    root: String,
+    mount_point: String,
+    options: Vec<String>,
+    fs_type: String,
+    source: String,
+}
+
+/// Undoes the octal escapes mountinfo uses for spaces, tabs, newlines and backslashes.
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes.get(i + 1..i + 4).filter(|_| bytes[i] == b'\\');
+        match escape.and_then(|e| u8::from_str_radix(std::str::from_utf8(e).ok()?, 8).ok()) {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+impl MountInfo {
+    fn parse(line: &str) -> Option<Self> {
+        //The optional fields before the separator vary in number, so the line is split there.
+        let (mount, filesystem) = line.split_once(" - ")?;
+        let mount: Vec<&str> = mount.split(' ').collect();
+        let filesystem: Vec<&str> = filesystem.split(' ').collect();
+
+        if mount.len() < 6 || filesystem.len() < 2 {
+            return None;
+        }
+
+        let source = unescape(filesystem[1]);
+
+        //btrfs and some others report an anonymous device number here, so the number of the
+        //device node that was mounted is used when there is one.
+        let device = Some(&source)
+            .filter(|s| s.starts_with('/'))
+            .and_then(|s| fs::metadata(s).ok())
+            .filter(|m| m.file_type().is_block_device())
+            .map(|m| DeviceNumber::from_dev_t(m.rdev()))
+            .or_else(|| mount[2].parse().ok())?;
+
+        Some(Self {
+            device,
+            root: unescape(mount[3]),
+            mount_point: unescape(mount[4]),
+            options: mount[5].split(',').map(str::to_owned).collect(),
+            fs_type: unescape(filesystem[0]),
+            source,
+        })
+    }
+}
+
+/// Reads the size, used and available space of the filesystem mounted at `mount_point`.
+fn statvfs(mount_point: &str) -> Option<(u64, u64, u64)> {
+    let path = CString::new(mount_point).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+
+    let fragment = stat.f_frsize;
+    let blocks = stat.f_blocks * fragment;
+    let free = stat.f_bfree * fragment;
+    let available = stat.f_bavail * fragment;
+
+    Some((blocks, blocks - free, available))
 }
 
+/// Lists mounted filesystems from /proc/self/mountinfo, one per device, with their usage from
+/// statvfs.
 pub fn get_usage_data() -> Result<Vec<Usage>> {
-    let output = Command::new("df").arg("--block-size=1").output()?;
-
-    let text = String::from_utf8(output.stdout)?;
-    let lines: Vec<&str> = text.lines().collect();
-
-    let mut usages = vec![];
-    for ln in 1..lines.len() {
-        let values: Vec<&str> = lines[ln].split_whitespace().collect();
-
-        if values.len() == 6 {
-            usages.push(Usage {
-                filesystem: values[0].to_string(),
-                blocks: values[1].parse()?,
-                used: values[2].parse()?,
-                available: values[3].parse()?,
-                percent: values[4].trim_end_matches('%').parse()?,
-                mount_point: values[5].to_string(),
-            });
+    let mountinfo = fs::read_to_string("/proc/self/mountinfo")?;
+
+    Ok(parse_mountinfo(&mountinfo)
+        .into_iter()
+        .map(|mut usage| {
+            if let Some((blocks, used, available)) = statvfs(&usage.mount_point) {
+                usage.blocks = blocks;
+                usage.used = used;
+                usage.available = available;
+
+                //Rounded up, as df does.
+                usage.percent = match used + available {
+                    0 => 0,
+                    total => used.saturating_mul(100).div_ceil(total) as u32,
+                };
+            }
+
+            usage
+        })
+        .collect())
+}
+
+/// Groups the mounts in `mountinfo` by device, without their sizes.
+fn parse_mountinfo(mountinfo: &str) -> Vec<Usage> {
+    let mut usages: Vec<(Usage, bool)> = vec![];
+    for mount in mountinfo.lines().filter_map(MountInfo::parse) {
+        let whole = mount.root == "/";
+
+        match usages.iter_mut().find(|(u, _)| u.device == mount.device) {
+            Some((usage, has_whole)) => {
+                usage.mount_points.push(mount.mount_point.clone());
+                if whole && !*has_whole {
+                    usage.mount_point = mount.mount_point;
+                    usage.options = mount.options;
+                    *has_whole = true;
+                }
+            }
+            None => usages.push((
+                Usage {
+                    filesystem: mount.source,
+                    device: mount.device,
+                    fs_type: mount.fs_type,
+                    options: mount.options,
+                    blocks: 0,
+                    used: 0,
+                    available: 0,
+                    percent: 0,
+                    mount_point: mount.mount_point.clone(),
+                    mount_points: vec![mount.mount_point],
+                },
+                whole,
+            )),
         }
     }
 
-    Ok(usages)
+    usages.into_iter().map(|(usage, _)| usage).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(number: &str) -> DeviceNumber {
+        number.parse().unwrap()
+    }
+
+    #[test]
+    fn unescapes_mount_points() {
+        let usages = parse_mountinfo(
+            r"95 29 8:33 / /run/media/user/My\040Disk rw,nosuid,nodev shared:50 - vfat /dev/sdc1 rw",
+        );
+
+        assert_eq!(usages.len(), 1);
+        assert_eq!(usages[0].mount_point, "/run/media/user/My Disk");
+        assert_eq!(usages[0].mount_points, ["/run/media/user/My Disk"]);
+        assert_eq!(usages[0].options, ["rw", "nosuid", "nodev"]);
+        assert_eq!(usages[0].fs_type, "vfat");
+    }
+
+    #[test]
+    fn unescapes_tabs_newlines_and_backslashes() {
+        assert_eq!(unescape(r"a\011b\012c\134d"), "a\tb\nc\\d");
+        assert_eq!(unescape(r"trailing\04"), r"trailing\04");
+    }
+
+    #[test]
+    fn groups_bind_mounts_by_device() {
+        let usages = parse_mountinfo(concat!(
+            "40 29 8:17 /exports /srv/nfs rw,relatime shared:20 - ext4 /dev/sdb1 rw\n",
+            "41 29 8:17 / /data rw,relatime shared:21 - ext4 /dev/sdb1 rw\n",
+            "42 29 8:17 /exports /home/user/exports ro,relatime shared:22 - ext4 /dev/sdb1 rw\n",
+        ));
+
+        assert_eq!(usages.len(), 1);
+        assert_eq!(usages[0].device, device("8:17"));
+        assert_eq!(usages[0].mount_point, "/data");
+        assert_eq!(
+            usages[0].mount_points,
+            ["/srv/nfs", "/data", "/home/user/exports"]
+        );
+        assert_eq!(usages[0].options, ["rw", "relatime"]);
+    }
+
+    #[test]
+    fn groups_btrfs_subvolumes() {
+        let usages = parse_mountinfo(concat!(
+            "30 1 0:45 /@ / rw,relatime shared:1 - btrfs /dev/disk/by-missing/root rw,subvol=/@\n",
+            "31 30 0:45 /@home /home rw,relatime shared:2 - btrfs /dev/disk/by-missing/root rw,subvol=/@home\n",
+        ));
+
+        //Without the whole filesystem mounted, the first subvolume stands in for it.
+        assert_eq!(usages.len(), 1);
+        assert_eq!(usages[0].device, device("0:45"));
+        assert_eq!(usages[0].mount_point, "/");
+        assert_eq!(usages[0].mount_points, ["/", "/home"]);
+    }
+
+    #[test]
+    fn keeps_sda1_and_sda11_apart() {
+        let usages = parse_mountinfo(concat!(
+            "50 29 8:11 / /srv rw,relatime shared:30 - ext4 /dev/sda11 rw\n",
+            "51 29 8:1 / /boot rw,relatime shared:31 - ext4 /dev/sda1 rw\n",
+        ));
+
+        assert_eq!(usages.len(), 2);
+
+        let sda1 = usages.iter().find(|u| u.device == device("8:1")).unwrap();
+        assert_eq!(sda1.mount_point, "/boot");
+
+        let sda11 = usages.iter().find(|u| u.device == device("8:11")).unwrap();
+        assert_eq!(sda11.mount_point, "/srv");
+    }
+
+    #[test]
+    fn splits_dev_t() {
+        assert_eq!(DeviceNumber::from_dev_t(0x0801), device("8:1"));
+        assert_eq!(DeviceNumber::from_dev_t(0x080b), device("8:11"));
+        assert_eq!(DeviceNumber::from_dev_t(0x1001_0300), device("259:65536"));
+    }
+
+    #[test]
+    fn skips_malformed_lines() {
+        assert!(parse_mountinfo("not a mountinfo line\n").is_empty());
+        assert!("8".parse::<DeviceNumber>().is_err());
+    }
 }
@@ -0,0 +1,100 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::{Context, Result, bail};
+use zbus::Connection;
+
+/// Lets anyone own any name and talk to anyone, which is all a test needs.
+const CONFIG: &str = r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>session</type>
+  <listen>unix:path={socket}</listen>
+  <auth>EXTERNAL</auth>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>
+"#;
+
+/// Tells apart the buses started by one test binary, as tests run in parallel.
+static BUS_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// A dbus-daemon of its own, so tests neither need nor disturb the system bus. The daemon is
+/// stopped when this is dropped.
+pub struct PrivateBus {
+    daemon: Child,
+    dir: PathBuf,
+    address: String,
+}
+
+impl PrivateBus {
+    pub fn start() -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!(
+            "hardware-dbus-mock-{}-{}",
+            std::process::id(),
+            BUS_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir)?;
+
+        let config = dir.join("bus.conf");
+        let socket = dir.join("bus");
+        fs::write(
+            &config,
+            CONFIG.replace("{socket}", &socket.to_string_lossy()),
+        )?;
+
+        let mut daemon = Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .args(["--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Could not start dbus-daemon")?;
+
+        //The address is printed once the bus accepts connections.
+        let mut address = String::new();
+        if let Some(stdout) = daemon.stdout.take() {
+            BufReader::new(stdout).read_line(&mut address)?;
+        }
+
+        let address = address.trim().to_owned();
+        if address.is_empty() {
+            let _ = daemon.kill();
+            let _ = fs::remove_dir_all(&dir);
+            bail!("dbus-daemon exited without printing its address");
+        }
+
+        Ok(Self {
+            daemon,
+            dir,
+            address,
+        })
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Opens a new connection to the bus.
+    pub async fn connect(&self) -> Result<Connection> {
+        Ok(zbus::connection::Builder::address(self.address())?
+            .build()
+            .await?)
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
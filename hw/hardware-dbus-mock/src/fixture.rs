@@ -0,0 +1,113 @@
+pub const UDISKS2_SERVICE: &str = "org.freedesktop.UDisks2";
+pub const UDISKS2_PATH: &str = "/org/freedesktop/UDisks2";
+pub const MANAGER_PATH: &str = "/org/freedesktop/UDisks2/Manager";
+pub const BLOCK_DEVICES_PATH: &str = "/org/freedesktop/UDisks2/block_devices/";
+pub const DRIVES_PATH: &str = "/org/freedesktop/UDisks2/drives/";
+
+/// Everything served by [`MockUDisks2`](crate::MockUDisks2) when it starts.
+#[derive(Debug, Clone, Default)]
+pub struct Fixture {
+    pub drives: Vec<DriveFixture>,
+}
+
+/// A drive, and the partitions on it.
+#[derive(Debug, Clone, Default)]
+pub struct DriveFixture {
+    /// Name of the drive's block device, such as "sda". The D-Bus objects are named after it.
+    pub name: String,
+    pub model: String,
+    pub vendor: String,
+    pub serial: String,
+    pub revision: String,
+    pub size: u64,
+    pub removable: bool,
+    pub ejectable: bool,
+    pub can_power_off: bool,
+    /// "gpt" or "dos". `None` leaves the drive without a partition table.
+    pub table_type: Option<String>,
+    pub partitions: Vec<PartitionFixture>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PartitionFixture {
+    pub number: u32,
+    pub name: String,
+    pub offset: u64,
+    pub size: u64,
+    /// A GUID for GPT, or a code such as "0x83" for DOS.
+    pub partition_type: String,
+    pub uuid: String,
+    /// What the partition contains, such as "ext4" or "crypto_LUKS". Empty if unknown.
+    pub id_type: String,
+    pub label: String,
+    pub mount_points: Vec<String>,
+}
+
+impl DriveFixture {
+    /// A fixed drive with a GPT partition table and no partitions.
+    pub fn new(name: &str, size: u64) -> Self {
+        Self {
+            name: name.to_owned(),
+            model: "Mock Drive".into(),
+            vendor: "hardware-dbus-mock".into(),
+            serial: name.to_owned(),
+            revision: "1.0".into(),
+            size,
+            table_type: Some("gpt".into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_partition(mut self, partition: PartitionFixture) -> Self {
+        self.partitions.push(partition);
+        self
+    }
+
+    pub fn drive_path(&self) -> String {
+        format!("{DRIVES_PATH}{}", self.name)
+    }
+
+    pub fn block_path(&self) -> String {
+        format!("{BLOCK_DEVICES_PATH}{}", self.name)
+    }
+
+    pub fn partition_path(&self, number: u32) -> String {
+        partition_path(&self.block_path(), number)
+    }
+}
+
+impl PartitionFixture {
+    pub fn new(number: u32, offset: u64, size: u64) -> Self {
+        Self {
+            number,
+            offset,
+            size,
+            partition_type: "0fc63daf-8483-4772-8e79-3d69d8477de4".into(),
+            uuid: format!("00000000-0000-0000-0000-{number:012}"),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_filesystem(mut self, id_type: &str) -> Self {
+        self.id_type = id_type.to_owned();
+        self
+    }
+
+    /// Whether the contents can be mounted, giving the partition a Filesystem interface.
+    pub(crate) fn has_filesystem(&self) -> bool {
+        has_filesystem(&self.id_type)
+    }
+}
+
+/// Names a partition's block device after the drive's, the way the kernel does: "sda1", but
+/// "nvme0n1p1".
+pub(crate) fn partition_path(block_path: &str, number: u32) -> String {
+    match block_path.ends_with(|c: char| c.is_ascii_digit()) {
+        true => format!("{block_path}p{number}"),
+        false => format!("{block_path}{number}"),
+    }
+}
+
+pub(crate) fn has_filesystem(id_type: &str) -> bool {
+    !matches!(id_type, "" | "crypto_LUKS" | "swap" | "empty")
+}
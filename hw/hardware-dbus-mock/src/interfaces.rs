@@ -0,0 +1,497 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use zbus::{
+    fdo, interface,
+    object_server::SignalEmitter,
+    zvariant::{OwnedObjectPath, OwnedValue},
+};
+
+use crate::{
+    fixture::{DriveFixture, PartitionFixture, has_filesystem},
+    service::Service,
+};
+
+type Options = HashMap<String, OwnedValue>;
+
+/// D-Bus wants paths as NUL terminated byte strings.
+fn to_bytes(value: &str) -> Vec<u8> {
+    let mut bytes = value.as_bytes().to_vec();
+    bytes.push(0);
+    bytes
+}
+
+fn device_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// The major number Linux sets aside for local use, so mock devices never match a real mount.
+const LOCAL_MAJOR: u64 = 240;
+
+pub(crate) struct MockManager {
+    pub(crate) service: Service,
+}
+
+#[interface(name = "org.freedesktop.UDisks2.Manager")]
+impl MockManager {
+    async fn get_block_devices(&self, _options: Options) -> Vec<OwnedObjectPath> {
+        self.service.block_devices()
+    }
+
+    #[zbus(property)]
+    async fn version(&self) -> String {
+        "2.10.1".into()
+    }
+}
+
+pub(crate) struct MockDrive {
+    pub(crate) service: Service,
+    pub(crate) fixture: DriveFixture,
+    pub(crate) media_available: bool,
+}
+
+#[interface(name = "org.freedesktop.UDisks2.Drive")]
+impl MockDrive {
+    async fn eject(
+        &mut self,
+        _options: Options,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        if !self.fixture.ejectable {
+            return Err(fdo::Error::NotSupported(
+                "The drive is not ejectable".into(),
+            ));
+        }
+
+        self.media_available = false;
+        self.media_available_changed(&emitter).await?;
+        Ok(())
+    }
+
+    /// A drive that has been powered off goes away, as if it had been unplugged.
+    async fn power_off(&self, _options: Options) -> fdo::Result<()> {
+        if !self.fixture.can_power_off {
+            return Err(fdo::Error::NotSupported(
+                "The drive can't be powered off".into(),
+            ));
+        }
+
+        self.service.remove_drive(&self.fixture.name).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    async fn id(&self) -> String {
+        format!("{}-{}", self.fixture.model, self.fixture.serial)
+    }
+
+    #[zbus(property)]
+    async fn model(&self) -> String {
+        self.fixture.model.clone()
+    }
+
+    #[zbus(property)]
+    async fn vendor(&self) -> String {
+        self.fixture.vendor.clone()
+    }
+
+    #[zbus(property)]
+    async fn serial(&self) -> String {
+        self.fixture.serial.clone()
+    }
+
+    #[zbus(property)]
+    async fn revision(&self) -> String {
+        self.fixture.revision.clone()
+    }
+
+    #[zbus(property)]
+    async fn size(&self) -> u64 {
+        self.fixture.size
+    }
+
+    #[zbus(property)]
+    async fn removable(&self) -> bool {
+        self.fixture.removable
+    }
+
+    #[zbus(property)]
+    async fn ejectable(&self) -> bool {
+        self.fixture.ejectable
+    }
+
+    #[zbus(property)]
+    async fn can_power_off(&self) -> bool {
+        self.fixture.can_power_off
+    }
+
+    #[zbus(property)]
+    async fn media_available(&self) -> bool {
+        self.media_available
+    }
+
+    #[zbus(property)]
+    async fn media_change_detected(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    async fn media_removable(&self) -> bool {
+        self.fixture.removable
+    }
+
+    #[zbus(property)]
+    async fn optical(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    async fn optical_blank(&self) -> bool {
+        false
+    }
+}
+
+pub(crate) struct MockBlock {
+    pub(crate) service: Service,
+    pub(crate) path: String,
+    pub(crate) drive: OwnedObjectPath,
+    pub(crate) size: u64,
+    pub(crate) id_type: String,
+    pub(crate) id_uuid: String,
+    pub(crate) id_label: String,
+    /// Partitions are formatted with a filesystem, whole drives with a partition table.
+    pub(crate) is_partition: bool,
+}
+
+#[interface(name = "org.freedesktop.UDisks2.Block")]
+impl MockBlock {
+    async fn format(
+        &mut self,
+        type_: String,
+        _options: Options,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        if !self.is_partition {
+            let table_type = match type_.as_str() {
+                "gpt" | "dos" => Some(type_.as_str()),
+                "empty" => None,
+                _ => {
+                    return Err(fdo::Error::NotSupported(format!(
+                        "Formatting a whole drive as {type_} is not supported"
+                    )));
+                }
+            };
+
+            self.service
+                .replace_table(&self.path, &self.drive, self.size, table_type)
+                .await?;
+            return Ok(());
+        }
+
+        let id_type = match type_.as_str() {
+            "empty" => String::new(),
+            _ => type_,
+        };
+
+        self.service
+            .set_filesystem(&self.path, self.size, has_filesystem(&id_type))
+            .await?;
+
+        self.id_type = id_type;
+        self.id_type_changed(&emitter).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    async fn size(&self) -> u64 {
+        self.size
+    }
+
+    #[zbus(property)]
+    async fn drive(&self) -> OwnedObjectPath {
+        self.drive.clone()
+    }
+
+    #[zbus(property)]
+    async fn device(&self) -> Vec<u8> {
+        to_bytes(&format!("/dev/{}", device_name(&self.path)))
+    }
+
+    #[zbus(property)]
+    async fn preferred_device(&self) -> Vec<u8> {
+        to_bytes(&format!("/dev/{}", device_name(&self.path)))
+    }
+
+    #[zbus(property)]
+    async fn device_number(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.path.hash(&mut hasher);
+        (LOCAL_MAJOR << 8) | (hasher.finish() & 0xff)
+    }
+
+    #[zbus(property)]
+    async fn id_type(&self) -> String {
+        self.id_type.clone()
+    }
+
+    #[zbus(property)]
+    async fn id_usage(&self) -> String {
+        match has_filesystem(&self.id_type) {
+            true => "filesystem".into(),
+            false => String::new(),
+        }
+    }
+
+    #[zbus(property, name = "IdUUID")]
+    async fn id_uuid(&self) -> String {
+        self.id_uuid.clone()
+    }
+
+    #[zbus(property)]
+    async fn id_label(&self) -> String {
+        self.id_label.clone()
+    }
+
+    #[zbus(property)]
+    async fn crypto_backing_device(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from("/").unwrap()
+    }
+
+    #[zbus(property)]
+    async fn read_only(&self) -> bool {
+        false
+    }
+}
+
+pub(crate) struct MockPartition {
+    pub(crate) service: Service,
+    pub(crate) path: String,
+    pub(crate) table: OwnedObjectPath,
+    pub(crate) fixture: PartitionFixture,
+}
+
+#[interface(name = "org.freedesktop.UDisks2.Partition")]
+impl MockPartition {
+    async fn delete(&self, _options: Options) -> fdo::Result<()> {
+        self.service
+            .remove_partition(self.table.as_str(), &self.path)
+            .await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    async fn number(&self) -> u32 {
+        self.fixture.number
+    }
+
+    #[zbus(property)]
+    async fn name(&self) -> String {
+        self.fixture.name.clone()
+    }
+
+    #[zbus(property)]
+    async fn offset(&self) -> u64 {
+        self.fixture.offset
+    }
+
+    #[zbus(property)]
+    async fn size(&self) -> u64 {
+        self.fixture.size
+    }
+
+    #[zbus(property, name = "Type")]
+    async fn type_(&self) -> String {
+        self.fixture.partition_type.clone()
+    }
+
+    #[zbus(property, name = "UUID")]
+    async fn uuid(&self) -> String {
+        self.fixture.uuid.clone()
+    }
+
+    #[zbus(property)]
+    async fn flags(&self) -> u64 {
+        0
+    }
+
+    #[zbus(property)]
+    async fn table(&self) -> OwnedObjectPath {
+        self.table.clone()
+    }
+
+    #[zbus(property)]
+    async fn is_container(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    async fn is_contained(&self) -> bool {
+        false
+    }
+}
+
+pub(crate) struct MockPartitionTable {
+    pub(crate) service: Service,
+    pub(crate) path: String,
+    pub(crate) drive: OwnedObjectPath,
+    pub(crate) size: u64,
+    pub(crate) table_type: String,
+    pub(crate) partitions: Vec<OwnedObjectPath>,
+}
+
+impl MockPartitionTable {
+    async fn create(
+        &mut self,
+        partition: PartitionFixture,
+        emitter: &SignalEmitter<'_>,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let existing = self.service.partitions(&self.partitions).await;
+
+        let end = partition.offset.saturating_add(partition.size);
+        if partition.size == 0 || end > self.size {
+            return Err(fdo::Error::InvalidArgs(
+                "The partition doesn't fit on the drive".into(),
+            ));
+        }
+
+        if existing
+            .iter()
+            .any(|p| partition.offset < p.offset + p.size && p.offset < end)
+        {
+            return Err(fdo::Error::InvalidArgs(
+                "The partition overlaps another partition".into(),
+            ));
+        }
+
+        //Take the lowest free number, like a real partition table.
+        let number = (1..)
+            .find(|n| !existing.iter().any(|p| p.number == *n))
+            .unwrap_or(1);
+
+        let partition = PartitionFixture {
+            uuid: format!("00000000-0000-0000-0000-{number:012}"),
+            number,
+            ..partition
+        };
+
+        let path = self
+            .service
+            .add_partition(&self.path, &self.drive, &partition)
+            .await?;
+
+        self.partitions.push(path.clone());
+        self.partitions_changed(emitter).await?;
+
+        Ok(path)
+    }
+}
+
+#[interface(name = "org.freedesktop.UDisks2.PartitionTable")]
+impl MockPartitionTable {
+    async fn create_partition(
+        &mut self,
+        offset: u64,
+        size: u64,
+        type_: String,
+        name: String,
+        _options: Options,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let partition = PartitionFixture {
+            name,
+            offset,
+            size,
+            partition_type: type_,
+            ..Default::default()
+        };
+
+        self.create(partition, &emitter).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn create_partition_and_format(
+        &mut self,
+        offset: u64,
+        size: u64,
+        type_: String,
+        name: String,
+        _options: Options,
+        format_type: String,
+        _format_options: Options,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let partition = PartitionFixture {
+            name,
+            offset,
+            size,
+            partition_type: type_,
+            id_type: format_type,
+            ..Default::default()
+        };
+
+        self.create(partition, &emitter).await
+    }
+
+    #[zbus(property, name = "Type")]
+    async fn type_(&self) -> String {
+        self.table_type.clone()
+    }
+
+    #[zbus(property)]
+    async fn partitions(&self) -> Vec<OwnedObjectPath> {
+        self.partitions.clone()
+    }
+}
+
+pub(crate) struct MockFilesystem {
+    pub(crate) path: String,
+    pub(crate) size: u64,
+    pub(crate) mount_points: Vec<String>,
+}
+
+#[interface(name = "org.freedesktop.UDisks2.Filesystem")]
+impl MockFilesystem {
+    async fn mount(
+        &mut self,
+        _options: Options,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<String> {
+        if let Some(mount_point) = self.mount_points.first() {
+            return Err(fdo::Error::Failed(format!(
+                "Already mounted at {mount_point}"
+            )));
+        }
+
+        let mount_point = format!("/run/media/mock/{}", device_name(&self.path));
+        self.mount_points.push(mount_point.clone());
+        self.mount_points_changed(&emitter).await?;
+
+        Ok(mount_point)
+    }
+
+    async fn unmount(
+        &mut self,
+        _options: Options,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        if self.mount_points.is_empty() {
+            return Err(fdo::Error::Failed("Not mounted".into()));
+        }
+
+        self.mount_points.clear();
+        self.mount_points_changed(&emitter).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    async fn mount_points(&self) -> Vec<Vec<u8>> {
+        self.mount_points.iter().map(|m| to_bytes(m)).collect()
+    }
+
+    #[zbus(property)]
+    async fn size(&self) -> u64 {
+        self.size
+    }
+}
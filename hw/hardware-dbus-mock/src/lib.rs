@@ -0,0 +1,13 @@
+//! A fake UDisks2 service, so hardware-dbus can be tested without a real system bus or real
+//! drives. [`MockUDisks2`] starts a private bus, serves the drives described by a [`Fixture`] on
+//! it, and hands out a connection that can be passed to
+//! `DriveModel::get_drives_for_connection` and `DiskManager::new_for_connection`.
+
+mod bus;
+mod fixture;
+mod interfaces;
+mod service;
+
+pub use bus::PrivateBus;
+pub use fixture::*;
+pub use service::MockUDisks2;
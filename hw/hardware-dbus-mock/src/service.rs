@@ -0,0 +1,364 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use zbus::{Connection, ObjectServer, fdo::ObjectManager, zvariant::OwnedObjectPath};
+
+use crate::{
+    bus::PrivateBus,
+    fixture::{
+        BLOCK_DEVICES_PATH, DRIVES_PATH, DriveFixture, Fixture, MANAGER_PATH, PartitionFixture,
+        UDISKS2_PATH, UDISKS2_SERVICE, partition_path,
+    },
+    interfaces::{
+        MockBlock, MockDrive, MockFilesystem, MockManager, MockPartition, MockPartitionTable,
+    },
+};
+
+/// Adds and removes the objects of the fake service. Shared by the interfaces, for the methods
+/// that change more than their own object, and by [`MockUDisks2`].
+///
+/// UDisks2 announces an object once, with all of its interfaces, while the object server adds
+/// them one at a time. The Block interface is always added last and removed first, so that a
+/// device is complete whenever it is seen as a block device.
+#[derive(Clone)]
+pub(crate) struct Service {
+    connection: Connection,
+    block_devices: Arc<Mutex<Vec<String>>>,
+}
+
+impl Service {
+    fn server(&self) -> &ObjectServer {
+        self.connection.object_server()
+    }
+
+    pub(crate) fn block_devices(&self) -> Vec<OwnedObjectPath> {
+        self.block_devices
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|p| OwnedObjectPath::try_from(p.as_str()).ok())
+            .collect()
+    }
+
+    async fn add_block(&self, block: MockBlock) -> zbus::Result<()> {
+        let path = block.path.clone();
+        self.server().at(path.as_str(), block).await?;
+        self.block_devices.lock().unwrap().push(path);
+        Ok(())
+    }
+
+    async fn remove_block(&self, path: &str) -> zbus::Result<()> {
+        self.block_devices.lock().unwrap().retain(|p| p != path);
+        self.server().remove::<MockBlock, _>(path).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn add_drive(&self, drive: &DriveFixture) -> zbus::Result<()> {
+        let drive_path = OwnedObjectPath::try_from(drive.drive_path())?;
+        let block_path = drive.block_path();
+
+        self.server()
+            .at(
+                drive_path.as_str(),
+                MockDrive {
+                    service: self.clone(),
+                    fixture: drive.clone(),
+                    media_available: true,
+                },
+            )
+            .await?;
+
+        if let Some(table_type) = &drive.table_type {
+            let mut partitions = vec![];
+            for partition in &drive.partitions {
+                partitions.push(
+                    self.add_partition(&block_path, &drive_path, partition)
+                        .await?,
+                );
+            }
+
+            self.server()
+                .at(
+                    block_path.as_str(),
+                    MockPartitionTable {
+                        service: self.clone(),
+                        path: block_path.clone(),
+                        drive: drive_path.clone(),
+                        size: drive.size,
+                        table_type: table_type.clone(),
+                        partitions,
+                    },
+                )
+                .await?;
+        }
+
+        self.add_block(MockBlock {
+            service: self.clone(),
+            path: block_path,
+            drive: drive_path,
+            size: drive.size,
+            id_type: String::new(),
+            id_uuid: String::new(),
+            id_label: String::new(),
+            is_partition: false,
+        })
+        .await
+    }
+
+    pub(crate) async fn remove_drive(&self, name: &str) -> zbus::Result<()> {
+        let block_path = format!("{BLOCK_DEVICES_PATH}{name}");
+
+        self.remove_block(&block_path).await?;
+        self.remove_table(&block_path).await?;
+        self.server()
+            .remove::<MockDrive, _>(format!("{DRIVES_PATH}{name}").as_str())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Removes the partition table of the drive at `block_path`, and every partition in it.
+    async fn remove_table(&self, block_path: &str) -> zbus::Result<()> {
+        let Ok(table) = self
+            .server()
+            .interface::<_, MockPartitionTable>(block_path)
+            .await
+        else {
+            return Ok(());
+        };
+
+        let partitions = table.get().await.partitions.clone();
+        for partition in partitions {
+            self.remove_partition_objects(partition.as_str()).await?;
+        }
+
+        self.server()
+            .remove::<MockPartitionTable, _>(block_path)
+            .await?;
+        Ok(())
+    }
+
+    /// Replaces the partition table of the drive at `block_path` with an empty one, or with none.
+    pub(crate) async fn replace_table(
+        &self,
+        block_path: &str,
+        drive_path: &OwnedObjectPath,
+        size: u64,
+        table_type: Option<&str>,
+    ) -> zbus::Result<()> {
+        self.remove_table(block_path).await?;
+
+        if let Some(table_type) = table_type {
+            self.server()
+                .at(
+                    block_path,
+                    MockPartitionTable {
+                        service: self.clone(),
+                        path: block_path.to_owned(),
+                        drive: drive_path.clone(),
+                        size,
+                        table_type: table_type.to_owned(),
+                        partitions: vec![],
+                    },
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds the objects of a partition. The caller lists it in the partition table.
+    pub(crate) async fn add_partition(
+        &self,
+        block_path: &str,
+        drive_path: &OwnedObjectPath,
+        partition: &PartitionFixture,
+    ) -> zbus::Result<OwnedObjectPath> {
+        let path = partition_path(block_path, partition.number);
+
+        self.server()
+            .at(
+                path.as_str(),
+                MockPartition {
+                    service: self.clone(),
+                    path: path.clone(),
+                    table: OwnedObjectPath::try_from(block_path)?,
+                    fixture: partition.clone(),
+                },
+            )
+            .await?;
+
+        if partition.has_filesystem() {
+            self.server()
+                .at(
+                    path.as_str(),
+                    MockFilesystem {
+                        path: path.clone(),
+                        size: partition.size,
+                        mount_points: partition.mount_points.clone(),
+                    },
+                )
+                .await?;
+        }
+
+        self.add_block(MockBlock {
+            service: self.clone(),
+            path: path.clone(),
+            drive: drive_path.clone(),
+            size: partition.size,
+            id_type: partition.id_type.clone(),
+            id_uuid: partition.uuid.clone(),
+            id_label: partition.label.clone(),
+            is_partition: true,
+        })
+        .await?;
+
+        Ok(OwnedObjectPath::try_from(path)?)
+    }
+
+    async fn remove_partition_objects(&self, path: &str) -> zbus::Result<()> {
+        self.remove_block(path).await?;
+        let _ = self.server().remove::<MockFilesystem, _>(path).await;
+        self.server().remove::<MockPartition, _>(path).await?;
+        Ok(())
+    }
+
+    /// Removes a partition, and drops it from the partition table at `table_path`.
+    pub(crate) async fn remove_partition(&self, table_path: &str, path: &str) -> zbus::Result<()> {
+        self.remove_partition_objects(path).await?;
+
+        let table = self
+            .server()
+            .interface::<_, MockPartitionTable>(table_path)
+            .await?;
+        let mut table_iface = table.get_mut().await;
+        table_iface.partitions.retain(|p| p.as_str() != path);
+        table_iface
+            .partitions_changed(table.signal_emitter())
+            .await?;
+
+        Ok(())
+    }
+
+    /// The partitions at `paths`, as they are now.
+    pub(crate) async fn partitions(&self, paths: &[OwnedObjectPath]) -> Vec<PartitionFixture> {
+        let mut partitions = vec![];
+
+        for path in paths {
+            if let Ok(partition) = self
+                .server()
+                .interface::<_, MockPartition>(path.as_str())
+                .await
+            {
+                partitions.push(partition.get().await.fixture.clone());
+            }
+        }
+
+        partitions
+    }
+
+    /// Gives the partition at `path` a Filesystem interface, or takes it away.
+    pub(crate) async fn set_filesystem(
+        &self,
+        path: &str,
+        size: u64,
+        has_filesystem: bool,
+    ) -> zbus::Result<()> {
+        let _ = self.server().remove::<MockFilesystem, _>(path).await;
+
+        if has_filesystem {
+            self.server()
+                .at(
+                    path,
+                    MockFilesystem {
+                        path: path.to_owned(),
+                        size,
+                        mount_points: vec![],
+                    },
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A fake UDisks2 service, serving the drives of a [`Fixture`] on a [`PrivateBus`]. Drives can
+/// be plugged in and out while it runs, and the D-Bus methods used by hardware-dbus to change
+/// drives change the fake ones the same way.
+pub struct MockUDisks2 {
+    connection: Connection,
+    service: Service,
+    _bus: PrivateBus,
+}
+
+impl MockUDisks2 {
+    pub async fn start(fixture: Fixture) -> Result<Self> {
+        let bus = PrivateBus::start()?;
+        let server = bus.connect().await?;
+
+        let service = Service {
+            connection: server.clone(),
+            block_devices: Arc::new(Mutex::new(vec![])),
+        };
+
+        server
+            .object_server()
+            .at(UDISKS2_PATH, ObjectManager)
+            .await?;
+        server
+            .object_server()
+            .at(
+                MANAGER_PATH,
+                MockManager {
+                    service: service.clone(),
+                },
+            )
+            .await?;
+
+        for drive in &fixture.drives {
+            service.add_drive(drive).await?;
+        }
+
+        server.request_name(UDISKS2_SERVICE).await?;
+
+        Ok(Self {
+            connection: bus.connect().await?,
+            service,
+            _bus: bus,
+        })
+    }
+
+    /// A connection to the private bus, for hardware-dbus to use in place of the system bus.
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Plugs in a drive.
+    pub async fn add_drive(&self, drive: &DriveFixture) -> Result<()> {
+        Ok(self.service.add_drive(drive).await?)
+    }
+
+    /// Unplugs the drive named `name`.
+    pub async fn remove_drive(&self, name: &str) -> Result<()> {
+        Ok(self.service.remove_drive(name).await?)
+    }
+
+    /// Mounts or unmounts the filesystem at `path` behind hardware-dbus's back, as an automounter
+    /// would.
+    pub async fn set_mount_points(&self, path: &str, mount_points: Vec<String>) -> Result<()> {
+        let filesystem = self
+            .service
+            .server()
+            .interface::<_, MockFilesystem>(path)
+            .await?;
+
+        let mut filesystem_iface = filesystem.get_mut().await;
+        filesystem_iface.mount_points = mount_points;
+        filesystem_iface
+            .mount_points_changed(filesystem.signal_emitter())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+#![allow(dead_code)] //Not every test uses every helper.
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use futures::StreamExt;
+use hardware_common::{CreatePartitionInfo, DeviceEvent, Drive};
+use hardware_dbus::disks::{DeviceEventStream, DiskManager, DriveModel, PartitionModel};
+use hardware_dbus_mock::{DriveFixture, Fixture, MockUDisks2, PartitionFixture};
+
+pub const GIB: u64 = 1024 * 1024 * 1024;
+pub const MIB: u64 = 1024 * 1024;
+
+/// A fixed drive with two partitions, and a removable one without a partition table.
+pub fn fixture() -> Fixture {
+    Fixture {
+        drives: vec![
+            DriveFixture::new("mocka", 8 * GIB)
+                .with_partition(PartitionFixture::new(1, MIB, GIB).with_filesystem("vfat"))
+                .with_partition(
+                    PartitionFixture::new(2, GIB + MIB, 4 * GIB).with_filesystem("ext4"),
+                ),
+            DriveFixture {
+                removable: true,
+                ejectable: true,
+                table_type: None,
+                ..DriveFixture::new("mockb", 2 * GIB)
+            },
+        ],
+    }
+}
+
+pub fn find<'a>(drives: &'a [DriveModel], drive: &DriveFixture) -> Option<&'a DriveModel> {
+    drives.iter().find(|d| d.block_path == drive.block_path())
+}
+
+/// Applies events to `drives` until `done` holds, failing if none arrive for a while.
+pub async fn apply_until(
+    manager: &DiskManager,
+    stream: &mut DeviceEventStream,
+    drives: &mut Vec<DriveModel>,
+    done: impl Fn(&[DriveModel]) -> bool,
+) -> Result<()> {
+    while !done(drives) {
+        let event = next_event(stream).await?;
+        manager.apply_change(drives, event).await?;
+    }
+
+    Ok(())
+}
+
+/// The mock service started with [`fixture`], and its drives, kept up to date from its events.
+pub struct Harness {
+    pub fixture: Fixture,
+    pub mock: MockUDisks2,
+    pub manager: DiskManager,
+    pub stream: DeviceEventStream,
+    pub drives: Vec<DriveModel>,
+}
+
+impl Harness {
+    pub async fn start() -> Result<Self> {
+        let fixture = fixture();
+        let mock = MockUDisks2::start(fixture.clone()).await?;
+        let manager = DiskManager::new_for_connection(mock.connection().clone());
+        let stream = manager.device_event_stream().await?;
+        let drives = DriveModel::get_drives_for_connection(mock.connection()).await?;
+
+        Ok(Self {
+            fixture,
+            mock,
+            manager,
+            stream,
+            drives,
+        })
+    }
+
+    /// The partitioned drive of the fixture.
+    pub fn drive(&self) -> &DriveModel {
+        find(&self.drives, &self.fixture.drives[0]).unwrap()
+    }
+
+    /// Applies events until `done` holds for [`Harness::drive`].
+    pub async fn apply_until(&mut self, done: impl Fn(&DriveModel) -> bool) -> Result<()> {
+        let fixture = &self.fixture.drives[0];
+        apply_until(&self.manager, &mut self.stream, &mut self.drives, |d| {
+            find(d, fixture).is_some_and(&done)
+        })
+        .await
+    }
+
+    /// Creates a partition on [`Harness::drive`], and returns it once it shows up.
+    pub async fn create_partition(&mut self, info: CreatePartitionInfo) -> Result<PartitionModel> {
+        let existing: Vec<u32> = self.drive().partitions.iter().map(|p| p.number).collect();
+        self.drive().create_partition(info).await?;
+
+        self.apply_until(|d| d.partitions.len() > existing.len())
+            .await?;
+
+        Ok(self
+            .drive()
+            .partitions
+            .iter()
+            .find(|p| !existing.contains(&p.number))
+            .unwrap()
+            .clone())
+    }
+}
+
+pub async fn next_event(stream: &mut DeviceEventStream) -> Result<DeviceEvent> {
+    tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await?
+        .ok_or_else(|| anyhow!("The event stream ended"))
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use anyhow::Result;
+use common::{GIB, MIB, find, fixture};
+use hardware_dbus::disks::DriveModel;
+use hardware_dbus_mock::{Fixture, MockUDisks2};
+
+#[tokio::test]
+async fn lists_every_drive() -> Result<()> {
+    let fixture = fixture();
+    let mock = MockUDisks2::start(fixture.clone()).await?;
+
+    let drives = DriveModel::get_drives_for_connection(mock.connection()).await?;
+
+    assert_eq!(drives.len(), 2);
+    for drive in &fixture.drives {
+        let model = find(&drives, drive).expect("drive is listed");
+        assert_eq!(model.path, drive.drive_path());
+        assert_eq!(model.size, drive.size);
+        assert_eq!(model.model, drive.model);
+        assert_eq!(model.serial, drive.serial);
+        assert_eq!(model.removable, drive.removable);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fixed_drives_come_first() -> Result<()> {
+    let mock = MockUDisks2::start(fixture()).await?;
+
+    let drives = DriveModel::get_drives_for_connection(mock.connection()).await?;
+    let removable: Vec<bool> = drives.iter().map(|d| d.removable).collect();
+
+    assert_eq!(removable, vec![false, true]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn lists_partitions_of_each_drive() -> Result<()> {
+    let fixture = fixture();
+    let mock = MockUDisks2::start(fixture.clone()).await?;
+
+    let drives = DriveModel::get_drives_for_connection(mock.connection()).await?;
+
+    let partitioned = find(&drives, &fixture.drives[0]).unwrap();
+    assert_eq!(partitioned.partition_table_type.as_deref(), Some("gpt"));
+
+    let mut partitions = partitioned.partitions.clone();
+    partitions.sort_by_key(|p| p.number);
+    let summary: Vec<(u32, u64, u64, &str)> = partitions
+        .iter()
+        .map(|p| (p.number, p.offset, p.size, p.id_type.as_str()))
+        .collect();
+
+    assert_eq!(
+        summary,
+        vec![(1, MIB, GIB, "vfat"), (2, GIB + MIB, 4 * GIB, "ext4")]
+    );
+    assert!(
+        partitions
+            .iter()
+            .all(|p| p.path.as_str() == fixture.drives[0].partition_path(p.number))
+    );
+
+    let unpartitioned = find(&drives, &fixture.drives[1]).unwrap();
+    assert_eq!(unpartitioned.partition_table_type, None);
+    assert!(unpartitioned.partitions.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn lists_nothing_without_drives() -> Result<()> {
+    let mock = MockUDisks2::start(Fixture::default()).await?;
+
+    let drives = DriveModel::get_drives_for_connection(mock.connection()).await?;
+
+    assert!(drives.is_empty());
+    Ok(())
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use anyhow::Result;
+use common::{GIB, MIB, apply_until, find, fixture, next_event};
+use hardware_common::DeviceEvent;
+use hardware_dbus::disks::{DiskManager, DriveModel};
+use hardware_dbus_mock::{DriveFixture, MockUDisks2, PartitionFixture};
+
+#[tokio::test]
+async fn plugging_in_a_drive_adds_it() -> Result<()> {
+    let mock = MockUDisks2::start(fixture()).await?;
+    let manager = DiskManager::new_for_connection(mock.connection().clone());
+    let mut stream = manager.device_event_stream().await?;
+    let mut drives = DriveModel::get_drives_for_connection(mock.connection()).await?;
+
+    let plugged = DriveFixture {
+        removable: true,
+        ..DriveFixture::new("mockc", 16 * GIB)
+            .with_partition(PartitionFixture::new(1, MIB, 15 * GIB).with_filesystem("exfat"))
+    };
+    mock.add_drive(&plugged).await?;
+
+    apply_until(&manager, &mut stream, &mut drives, |d| {
+        find(d, &plugged).is_some_and(|d| d.partitions.len() == 1)
+    })
+    .await?;
+
+    assert_eq!(drives.len(), 3);
+    assert_eq!(
+        find(&drives, &plugged).unwrap().partitions[0].id_type,
+        "exfat"
+    );
+
+    //The incremental update ends up where a full reload would.
+    let reloaded = DriveModel::get_drives_for_connection(mock.connection()).await?;
+    let summary = |drives: &[DriveModel]| -> Vec<(String, usize)> {
+        drives
+            .iter()
+            .map(|d| (d.block_path.clone(), d.partitions.len()))
+            .collect()
+    };
+    assert_eq!(summary(&drives), summary(&reloaded));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn unplugging_a_drive_removes_it() -> Result<()> {
+    let fixture = fixture();
+    let mock = MockUDisks2::start(fixture.clone()).await?;
+    let manager = DiskManager::new_for_connection(mock.connection().clone());
+    let mut stream = manager.device_event_stream().await?;
+    let mut drives = DriveModel::get_drives_for_connection(mock.connection()).await?;
+
+    mock.remove_drive(&fixture.drives[0].name).await?;
+
+    apply_until(&manager, &mut stream, &mut drives, |d| {
+        find(d, &fixture.drives[0]).is_none()
+    })
+    .await?;
+
+    assert_eq!(drives.len(), 1);
+    assert!(find(&drives, &fixture.drives[1]).is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reports_mounts_made_elsewhere() -> Result<()> {
+    let fixture = fixture();
+    let mock = MockUDisks2::start(fixture.clone()).await?;
+    let manager = DiskManager::new_for_connection(mock.connection().clone());
+    let mut stream = manager.device_event_stream().await?;
+
+    let path = fixture.drives[0].partition_path(2);
+
+    mock.set_mount_points(&path, vec!["/run/media/mock/data".into()])
+        .await?;
+    assert_eq!(
+        next_event(&mut stream).await?,
+        DeviceEvent::Mounted(path.clone())
+    );
+
+    mock.set_mount_points(&path, vec![]).await?;
+    assert_eq!(next_event(&mut stream).await?, DeviceEvent::Unmounted(path));
+
+    Ok(())
+}
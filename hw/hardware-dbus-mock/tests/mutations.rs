@@ -0,0 +1,224 @@
+mod common;
+
+use anyhow::Result;
+use common::{GIB, Harness, MIB, find, next_event};
+use hardware_common::{
+    AlignmentError, CreatePartitionInfo, DeviceEvent, DiskBackend, Drive, EraseMode,
+    FilesystemType, Partition,
+};
+use hardware_dbus::disks::{DbusBackend, DiskError};
+
+#[tokio::test]
+async fn creates_a_partition() -> Result<()> {
+    let mut harness = Harness::start().await?;
+
+    let created = harness
+        .create_partition(CreatePartitionInfo {
+            name: "new".into(),
+            offset: 5 * GIB + MIB,
+            size: GIB,
+            ..Default::default()
+        })
+        .await?;
+
+    assert_eq!(created.number, 3);
+    assert_eq!(created.name, "new");
+    assert_eq!((created.offset, created.size), (5 * GIB + MIB, GIB));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn formats_with_the_implied_partition_type() -> Result<()> {
+    let mut harness = Harness::start().await?;
+
+    let created = harness
+        .create_partition(CreatePartitionInfo {
+            offset: 5 * GIB + 2 * MIB,
+            size: GIB,
+            filesystem: FilesystemType::Vfat,
+            ..Default::default()
+        })
+        .await?;
+
+    assert_eq!(created.id_type, "vfat");
+    assert_eq!(created.partition_type, "Basic Data");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn maps_mbr_types_on_gpt() -> Result<()> {
+    let mut harness = Harness::start().await?;
+
+    let created = harness
+        .create_partition(CreatePartitionInfo {
+            offset: 5 * GIB + 2 * MIB,
+            size: GIB,
+            partition_type: "0x82".into(),
+            filesystem: FilesystemType::Swap,
+            ..Default::default()
+        })
+        .await?;
+
+    assert_eq!(created.partition_type, "Linux Swap");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn refuses_swap_with_a_password() -> Result<()> {
+    let harness = Harness::start().await?;
+
+    let result = harness
+        .drive()
+        .create_partition(CreatePartitionInfo {
+            offset: 5 * GIB + 2 * MIB,
+            size: GIB,
+            filesystem: FilesystemType::Swap,
+            password_protected: true,
+            password: "secret".into(),
+            confirmed_password: "secret".into(),
+            ..Default::default()
+        })
+        .await;
+
+    let err = result.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<DiskError>(),
+        Some(DiskError::EncryptionNotSupported(name)) if name == FilesystemType::Swap.info().name
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn refuses_overlapping_partitions() -> Result<()> {
+    let harness = Harness::start().await?;
+
+    let result = harness
+        .drive()
+        .create_partition(CreatePartitionInfo {
+            offset: 2 * GIB,
+            size: GIB,
+            ..Default::default()
+        })
+        .await;
+
+    assert_eq!(
+        result.unwrap_err().downcast_ref::<AlignmentError>(),
+        Some(&AlignmentError::Overlaps(GIB + MIB))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn aligns_new_partitions() -> Result<()> {
+    let mut harness = Harness::start().await?;
+    let info = CreatePartitionInfo {
+        offset: 5 * GIB + MIB + 4096,
+        size: GIB,
+        ..Default::default()
+    };
+
+    let plan = harness.drive().plan_create_partition(info.clone()).await?;
+    assert!(plan.changes.iter().any(|c| c.contains("unused")));
+
+    let created = harness.create_partition(info).await?;
+    assert_eq!(
+        (created.offset, created.size),
+        (5 * GIB + 2 * MIB, GIB - MIB)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn refuses_the_backup_gpt() -> Result<()> {
+    let harness = Harness::start().await?;
+
+    let result = harness
+        .drive()
+        .create_partition(CreatePartitionInfo {
+            offset: 7 * GIB,
+            size: GIB,
+            ..Default::default()
+        })
+        .await;
+
+    assert_eq!(
+        result.unwrap_err().downcast_ref::<AlignmentError>(),
+        Some(&AlignmentError::BackupGpt)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn deletes_a_partition() -> Result<()> {
+    let mut harness = Harness::start().await?;
+
+    let partition = harness.drive().partitions.iter().find(|p| p.number == 1);
+    partition.unwrap().delete().await?;
+
+    harness.apply_until(|d| d.partitions.len() == 1).await?;
+
+    assert_eq!(harness.drive().partitions[0].number, 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn mounts_and_unmounts() -> Result<()> {
+    let mut harness = Harness::start().await?;
+
+    let partition = harness.drive().partitions.iter().find(|p| p.number == 2);
+    let partition = partition.unwrap().clone();
+    let path = partition.path.to_string();
+
+    partition.mount().await?;
+    assert_eq!(
+        next_event(&mut harness.stream).await?,
+        DeviceEvent::Mounted(path.clone())
+    );
+
+    partition.unmount().await?;
+    assert_eq!(
+        next_event(&mut harness.stream).await?,
+        DeviceEvent::Unmounted(path)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn formatting_a_drive_removes_its_partitions() -> Result<()> {
+    let mut harness = Harness::start().await?;
+
+    harness
+        .drive()
+        .format_disk("".into(), EraseMode::None)
+        .await?;
+
+    harness
+        .apply_until(|d| d.partition_table_type.is_none())
+        .await?;
+
+    assert!(harness.drive().partitions.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn backend_deletes_and_watches() -> Result<()> {
+    let harness = Harness::start().await?;
+    let backend = DbusBackend::new_for_connection(harness.mock.connection().clone());
+    let mut stream = backend.watch().await?;
+    let mut drives = backend.drives().await?;
+
+    let partition = harness.drive().partitions[0].clone();
+    backend.delete_partition(&partition).await?;
+
+    while find(&drives, &harness.fixture.drives[0]).is_some_and(|d| d.partitions.len() != 1) {
+        let event = next_event(&mut stream).await?;
+        backend.apply_event(&mut drives, event).await?;
+    }
+
+    Ok(())
+}
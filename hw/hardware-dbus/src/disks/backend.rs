@@ -0,0 +1,111 @@
+use anyhow::Result;
+use hardware_common::{
+    ChangePlan, CreatePartitionInfo, DeviceEvent, DiskBackend, Drive, EraseMode, Partition,
+};
+use zbus::Connection;
+
+use super::{DeviceEventStream, DiskManager, DriveModel, PartitionModel};
+
+/// The UDisks2 backend.
+pub struct DbusBackend {
+    manager: DiskManager,
+}
+
+impl DbusBackend {
+    /// Uses the UDisks2 service on `connection`, rather than the one on the system bus.
+    pub fn new_for_connection(connection: Connection) -> Self {
+        Self {
+            manager: DiskManager::new_for_connection(connection),
+        }
+    }
+}
+
+impl DiskBackend for DbusBackend {
+    type Drive = DriveModel;
+    type Partition = PartitionModel;
+    type EventStream = DeviceEventStream;
+
+    async fn connect() -> Result<Self> {
+        Ok(Self {
+            manager: DiskManager::new().await?,
+        })
+    }
+
+    async fn drives(&self) -> Result<Vec<DriveModel>> {
+        DriveModel::get_drives_for_connection(self.manager.connection()).await
+    }
+
+    async fn watch(&self) -> Result<DeviceEventStream> {
+        self.manager.device_event_stream().await
+    }
+
+    async fn apply_event(&self, drives: &mut Vec<DriveModel>, event: DeviceEvent) -> Result<()> {
+        self.manager.apply_change(drives, event).await
+    }
+
+    async fn create_partition(&self, drive: &DriveModel, info: CreatePartitionInfo) -> Result<()> {
+        drive.create_partition(info).await
+    }
+
+    async fn plan_create_partition(
+        &self,
+        drive: &DriveModel,
+        info: CreatePartitionInfo,
+    ) -> Result<ChangePlan> {
+        drive.plan_create_partition(info).await
+    }
+
+    async fn delete_partition(&self, partition: &PartitionModel) -> Result<()> {
+        partition.delete().await
+    }
+
+    async fn plan_delete_partition(&self, partition: &PartitionModel) -> Result<ChangePlan> {
+        partition.plan_delete().await
+    }
+
+    async fn format_partition(
+        &self,
+        partition: &PartitionModel,
+        name: String,
+        erase: EraseMode,
+        filesystem_type: String,
+    ) -> Result<()> {
+        partition.format(name, erase, filesystem_type).await
+    }
+
+    async fn plan_format_partition(
+        &self,
+        partition: &PartitionModel,
+        name: String,
+        erase: EraseMode,
+        filesystem_type: String,
+    ) -> Result<ChangePlan> {
+        partition.plan_format(name, erase, filesystem_type).await
+    }
+
+    async fn format_disk(
+        &self,
+        drive: &DriveModel,
+        table_type: String,
+        erase: EraseMode,
+    ) -> Result<()> {
+        drive.format_disk(table_type, erase).await
+    }
+
+    async fn plan_format_disk(
+        &self,
+        drive: &DriveModel,
+        table_type: String,
+        erase: EraseMode,
+    ) -> Result<ChangePlan> {
+        drive.plan_format_disk(table_type, erase).await
+    }
+
+    async fn mount(&self, partition: &PartitionModel) -> Result<()> {
+        partition.mount().await
+    }
+
+    async fn unmount(&self, partition: &PartitionModel) -> Result<()> {
+        partition.unmount().await
+    }
+}
@@ -0,0 +1,408 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    ops::Range,
+    os::unix::fs::FileExt,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use hardware_common::{
+    AccessSample, BenchmarkOptions, BenchmarkResult, BenchmarkWrite, CancelToken, ThroughputSample,
+};
+use udisks2::block::BlockProxy;
+use zbus::{
+    Connection,
+    zvariant::{OwnedObjectPath, Value},
+};
+
+use super::DiskError;
+
+/// Offsets, lengths and buffers all have to be aligned for O_DIRECT. 4 KiB covers every logical
+/// sector size in use.
+const ALIGNMENT: u64 = 4096;
+
+/// Bytes read by each access time probe.
+const ACCESS_READ_SIZE: u64 = ALIGNMENT;
+
+/// Space at either end of the drive that is never written to, as it holds the partition table.
+const RESERVED_SIZE: u64 = 1024 * 1024;
+
+/// Benchmarks the block device at `block_path`. Reads are spread across the whole device. If
+/// `options.write` allows it, writes are spread across `writable`, and always put back the data
+/// that was read from the same place, so the contents of the device are kept.
+pub(crate) async fn benchmark(
+    connection: &Connection,
+    block_path: &OwnedObjectPath,
+    writable: Vec<Range<u64>>,
+    options: BenchmarkOptions,
+    progress: impl Fn(BenchmarkResult) + Send + 'static,
+    cancel: CancelToken,
+) -> Result<BenchmarkResult> {
+    let proxy = BlockProxy::builder(connection)
+        .path(block_path)?
+        .build()
+        .await?;
+
+    let size = proxy.size().await?;
+    let write = options.write != BenchmarkWrite::None;
+
+    //Caching would measure memory rather than the drive.
+    let mut open_options: HashMap<&str, Value<'_>> = HashMap::new();
+    open_options.insert(
+        "flags",
+        Value::from(libc::O_DIRECT | libc::O_SYNC | libc::O_CLOEXEC),
+    );
+
+    let fd = proxy
+        .open_device(if write { "rw" } else { "r" }, open_options)
+        .await
+        .map_err(DiskError::from)?;
+    let device = File::from(std::os::fd::OwnedFd::from(fd));
+
+    tokio::task::spawn_blocking(move || {
+        run_benchmark(device, size, writable, options, &progress, &cancel)
+    })
+    .await?
+}
+
+fn run_benchmark(
+    device: File,
+    size: u64,
+    writable: Vec<Range<u64>>,
+    options: BenchmarkOptions,
+    progress: &impl Fn(BenchmarkResult),
+    cancel: &CancelToken,
+) -> Result<BenchmarkResult> {
+    let sample_size = align_down(options.sample_size.min(size));
+    if sample_size == 0 {
+        return Err(DiskError::TooSmallToBenchmark(size).into());
+    }
+
+    let mut result = BenchmarkResult {
+        size,
+        ..Default::default()
+    };
+    let mut buffer = AlignedBuffer::new(sample_size as usize);
+    let started = Instant::now();
+
+    let read_offsets = spread(
+        std::slice::from_ref(&(0..size)),
+        options.samples,
+        sample_size,
+    );
+    let write_offsets = match options.write {
+        BenchmarkWrite::None => vec![],
+        BenchmarkWrite::PreserveData => read_offsets.clone(),
+        BenchmarkWrite::UnallocatedOnly => spread(&writable, options.samples, sample_size),
+    };
+
+    if options.write == BenchmarkWrite::UnallocatedOnly && write_offsets.is_empty() {
+        return Err(DiskError::NoUnallocatedSpace.into());
+    }
+
+    for offset in read_offsets {
+        if cancel.is_cancelled() {
+            return Err(DiskError::Cancelled.into());
+        }
+
+        let timer = Instant::now();
+        device.read_exact_at(buffer.as_mut_slice(), offset)?;
+
+        result.read.push(ThroughputSample {
+            time: started.elapsed().as_secs_f64(),
+            offset,
+            bytes_per_second: sample_size as f64 / timer.elapsed().as_secs_f64(),
+        });
+        progress(result.clone());
+    }
+
+    for offset in write_offsets {
+        if cancel.is_cancelled() {
+            return Err(DiskError::Cancelled.into());
+        }
+
+        //Write back exactly what is there, so that nothing is lost.
+        device.read_exact_at(buffer.as_mut_slice(), offset)?;
+
+        let timer = Instant::now();
+        device.write_all_at(buffer.as_mut_slice(), offset)?;
+
+        result.write.push(ThroughputSample {
+            time: started.elapsed().as_secs_f64(),
+            offset,
+            bytes_per_second: sample_size as f64 / timer.elapsed().as_secs_f64(),
+        });
+        progress(result.clone());
+    }
+
+    let mut buffer = AlignedBuffer::new(ACCESS_READ_SIZE as usize);
+    let mut random = Random::new();
+    let positions = (size - ACCESS_READ_SIZE.min(size)) / ALIGNMENT + 1;
+
+    for _ in 0..options.access_samples {
+        if cancel.is_cancelled() {
+            return Err(DiskError::Cancelled.into());
+        }
+
+        let offset = (random.next() % positions) * ALIGNMENT;
+
+        let timer = Instant::now();
+        device.read_exact_at(buffer.as_mut_slice(), offset)?;
+
+        result.access.push(AccessSample {
+            time: started.elapsed().as_secs_f64(),
+            offset,
+            seconds: timer.elapsed().as_secs_f64(),
+        });
+        progress(result.clone());
+    }
+
+    Ok(result)
+}
+
+/// The parts of a device of `size` bytes that aren't covered by any of `used`, minus the space
+/// reserved for partition tables.
+pub(crate) fn unallocated_regions(size: u64, mut used: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    used.sort_by_key(|r| r.start);
+
+    let mut free = vec![];
+    let mut position = RESERVED_SIZE;
+    let end = size.saturating_sub(RESERVED_SIZE);
+
+    for range in used {
+        if range.start > position {
+            free.push(position..range.start.min(end));
+        }
+        position = position.max(range.end);
+    }
+
+    if end > position {
+        free.push(position..end);
+    }
+
+    free.retain(|r| r.end > r.start);
+    free
+}
+
+/// Picks `count` aligned offsets, spread evenly across `regions`, at which `sample_size` bytes can
+/// be transferred without leaving the region.
+fn spread(regions: &[Range<u64>], count: usize, sample_size: u64) -> Vec<u64> {
+    //The positions within each region at which a sample may start.
+    let regions: Vec<Range<u64>> = regions
+        .iter()
+        .filter(|r| r.end.saturating_sub(r.start) >= sample_size)
+        .map(|r| align_up(r.start)..align_down(r.end - sample_size))
+        .filter(|r| r.end >= r.start)
+        .collect();
+
+    let total: u64 = regions.iter().map(|r| r.end - r.start).sum();
+    if regions.is_empty() || count == 0 {
+        return vec![];
+    }
+
+    (0..count)
+        .map(|i| {
+            let mut position = match count {
+                1 => 0,
+                _ => (total as u128 * i as u128 / (count - 1) as u128) as u64,
+            };
+
+            for region in &regions {
+                let len = region.end - region.start;
+                if position <= len {
+                    return align_down(region.start + position);
+                }
+                position -= len;
+            }
+
+            regions.last().unwrap().end
+        })
+        .collect()
+}
+
+fn align_down(value: u64) -> u64 {
+    value / ALIGNMENT * ALIGNMENT
+}
+
+fn align_up(value: u64) -> u64 {
+    value.div_ceil(ALIGNMENT) * ALIGNMENT
+}
+
+/// O_DIRECT needs buffers that are aligned in memory, which a Vec doesn't guarantee, so this
+/// over-allocates and uses an aligned window.
+struct AlignedBuffer {
+    data: Vec<u8>,
+    start: usize,
+    len: usize,
+}
+
+impl AlignedBuffer {
+    fn new(len: usize) -> Self {
+        let data = vec![0u8; len + ALIGNMENT as usize];
+        let start = data.as_ptr().align_offset(ALIGNMENT as usize);
+
+        Self { data, start, len }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.data[self.start..self.start + self.len]
+    }
+}
+
+/// A xorshift generator. Access time probes only need to be scattered, not unpredictable.
+struct Random(u64);
+
+impl Random {
+    fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)] //Single regions are what is being tested.
+mod tests {
+    use std::{fs::OpenOptions, io::Write};
+
+    use super::*;
+
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * 1024;
+
+    #[test]
+    fn spreads_a_single_sample_to_the_start() {
+        assert_eq!(spread(&[MIB + 1..4 * MIB], 1, 64 * KIB), [MIB + ALIGNMENT]);
+    }
+
+    #[test]
+    fn skips_regions_smaller_than_a_sample() {
+        assert!(spread(&[MIB..MIB + 32 * KIB], 4, 64 * KIB).is_empty());
+        assert_eq!(
+            spread(&[0..32 * KIB, MIB..2 * MIB], 2, 64 * KIB),
+            [MIB, 2 * MIB - 64 * KIB]
+        );
+    }
+
+    #[test]
+    fn last_sample_ends_at_the_region_end() {
+        let offsets = spread(&[0..MIB], 4, 64 * KIB);
+
+        assert_eq!(offsets.len(), 4);
+        assert_eq!(*offsets.last().unwrap() + 64 * KIB, MIB);
+        assert!(offsets.windows(2).all(|w| w[0] < w[1]));
+        assert!(offsets.iter().all(|o| o % ALIGNMENT == 0));
+    }
+
+    #[test]
+    fn samples_stay_inside_their_regions() {
+        let regions = [MIB..3 * MIB, 10 * MIB..10 * MIB + 200 * KIB];
+
+        for offset in spread(&regions, 25, 64 * KIB) {
+            assert!(
+                regions
+                    .iter()
+                    .any(|r| offset >= r.start && offset + 64 * KIB <= r.end),
+                "{offset}"
+            );
+        }
+    }
+
+    #[test]
+    fn finds_gaps_around_partitions() {
+        let used = vec![40 * MIB..50 * MIB, 10 * MIB..20 * MIB];
+
+        assert_eq!(
+            unallocated_regions(100 * MIB, used.clone()),
+            [MIB..10 * MIB, 20 * MIB..40 * MIB, 50 * MIB..99 * MIB]
+        );
+
+        for free in unallocated_regions(100 * MIB, used.clone()) {
+            assert!(
+                used.iter()
+                    .all(|u| free.end <= u.start || free.start >= u.end)
+            );
+        }
+    }
+
+    #[test]
+    fn leaves_the_partition_tables_alone() {
+        assert_eq!(
+            unallocated_regions(100 * MIB, vec![MIB..99 * MIB]),
+            Vec::<Range<u64>>::new()
+        );
+        assert_eq!(unallocated_regions(100 * MIB, vec![]), [MIB..99 * MIB]);
+        assert_eq!(
+            unallocated_regions(100 * MIB, vec![0..2 * MIB, 98 * MIB..100 * MIB]),
+            [2 * MIB..98 * MIB]
+        );
+    }
+
+    #[test]
+    fn benchmarks_an_image_file() -> Result<()> {
+        let size = 8 * MIB;
+        let sample_size = 256 * KIB;
+        let path = std::env::temp_dir().join(format!("disks-benchmark-{}.img", std::process::id()));
+
+        let contents: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+        File::create(&path)?.write_all(&contents)?;
+
+        let image = OpenOptions::new().read(true).write(true).open(&path)?;
+        let writable = unallocated_regions(size, vec![2 * MIB..4 * MIB]);
+        let options = BenchmarkOptions {
+            samples: 8,
+            sample_size,
+            access_samples: 16,
+            write: BenchmarkWrite::UnallocatedOnly,
+        };
+
+        let result = run_benchmark(
+            image,
+            size,
+            writable.clone(),
+            options,
+            &|_| {},
+            &CancelToken::new(),
+        );
+        let written = std::fs::read(&path);
+        let _ = std::fs::remove_file(&path);
+        let result = result?;
+
+        assert_eq!(result.size, size);
+        assert_eq!(result.read.len(), 8);
+        assert_eq!(result.access.len(), 16);
+        assert!(result.read.iter().all(|s| s.offset + sample_size <= size));
+        assert!(
+            result
+                .access
+                .iter()
+                .all(|s| s.offset + ACCESS_READ_SIZE <= size)
+        );
+
+        assert_eq!(result.write.len(), 8);
+        for sample in &result.write {
+            assert!(
+                writable
+                    .iter()
+                    .any(|r| sample.offset >= r.start && sample.offset + sample_size <= r.end),
+                "{}",
+                sample.offset
+            );
+        }
+
+        //Writes put back what was there.
+        assert!(written? == contents);
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use hardware_common::MountOptions;
+use zbus::zvariant::{OwnedValue, Value};
+
+/// A `(type, details)` item of the UDisks2 Block.Configuration property.
+pub(crate) type ConfigurationItem = (String, HashMap<String, OwnedValue>);
+
+/// Converts a string into the NUL terminated byte string UDisks uses for configuration values.
+pub(crate) fn to_bytestring(value: &str) -> Value<'static> {
+    let mut bytes = value.as_bytes().to_vec();
+    bytes.push(0);
+    Value::from(bytes)
+}
+
+/// Reads a NUL terminated byte string configuration value, defaulting to an empty string.
+pub(crate) fn from_bytestring(value: Option<&OwnedValue>) -> String {
+    match value.and_then(|v| Vec::<u8>::try_from(v.clone()).ok()) {
+        Some(bytes) => String::from_utf8_lossy(&bytes)
+            .trim_end_matches('\0')
+            .to_string(),
+        None => String::new(),
+    }
+}
+
+/// Borrows a configuration item in the form the Block configuration methods take.
+pub(crate) fn as_item(item: &ConfigurationItem) -> (&str, HashMap<&str, Value<'_>>) {
+    let details = item
+        .1
+        .iter()
+        .filter_map(|(k, v)| Value::try_from(v).ok().map(|v| (k.as_str(), v)))
+        .collect();
+
+    (item.0.as_str(), details)
+}
+
+pub(crate) fn find_item<'a>(
+    configuration: &'a [ConfigurationItem],
+    item_type: &str,
+) -> Option<&'a ConfigurationItem> {
+    configuration.iter().find(|(t, _)| t == item_type)
+}
+
+pub(crate) fn fstab_item(
+    options: &MountOptions,
+) -> (&'static str, HashMap<&'static str, Value<'static>>) {
+    let mut details = HashMap::new();
+    details.insert("fsname", to_bytestring(&options.identify_as));
+    details.insert("dir", to_bytestring(&options.mount_point));
+    details.insert("type", to_bytestring(&options.filesystem_type));
+    details.insert("opts", to_bytestring(&options.to_options_string()));
+    details.insert("freq", Value::from(0i32));
+    details.insert("passno", Value::from(0i32));
+
+    ("fstab", details)
+}
+
+pub(crate) fn parse_fstab(details: &HashMap<String, OwnedValue>) -> MountOptions {
+    MountOptions::from_fstab(
+        &from_bytestring(details.get("fsname")),
+        &from_bytestring(details.get("dir")),
+        &from_bytestring(details.get("type")),
+        &from_bytestring(details.get("opts")),
+    )
+}
+
+/// A crypttab entry that asks for the passphrase at boot, unless `unlock_at_startup` is false.
+/// Existing entries keep their name, device and passphrase. New ones are named after the
+/// container's LUKS `uuid`, as UDisks names them, and have no keyfile.
+pub(crate) fn crypttab_item<'a>(
+    existing: Option<&'a ConfigurationItem>,
+    uuid: &str,
+    unlock_at_startup: bool,
+) -> (&'static str, HashMap<&'a str, Value<'a>>) {
+    let options = match unlock_at_startup {
+        true => "",
+        false => "noauto",
+    };
+
+    let mut details = match existing {
+        Some(item) => as_item(item).1,
+        None => HashMap::from([
+            ("name", to_bytestring(&format!("luks-{uuid}"))),
+            ("device", to_bytestring(&format!("UUID={uuid}"))),
+            ("passphrase-path", to_bytestring("")),
+            ("passphrase-contents", to_bytestring("")),
+        ]),
+    };
+    details.insert("options", to_bytestring(options));
+
+    ("crypttab", details)
+}
@@ -1,16 +1,26 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fs::OpenOptions};
 
 use anyhow::Result;
 use tracing::{error, info, warn};
 use udisks2::{
-    Client, block::BlockProxy, drive::DriveProxy, partition::PartitionProxy,
-    partitiontable::PartitionTableProxy,
+    Client, block::BlockProxy, drive::DriveProxy, r#loop::LoopProxy, manager::ManagerProxy,
+    partition::PartitionProxy, partitiontable::PartitionTableProxy,
+};
+use zbus::{
+    Connection,
+    zvariant::{Fd, OwnedObjectPath, Value},
 };
-use zbus::{Connection, zvariant::OwnedObjectPath};
 
-use hardware_common::{COMMON_PARTITION_TYPES, CreatePartitionInfo, Drive, get_usage_data};
+use hardware_common::{
+    AlignedRange, BenchmarkOptions, BenchmarkResult, BenchmarkWrite, ByteSize, CancelToken,
+    ChangePlan, CopyProgress, CreatePartitionInfo, DeviceNumber, DiskImage, Drive, DriveGeometry,
+    DriveSettings, DriveSettingsSupport, EraseMode, FilesystemType, PartitionTypeInfo,
+    SelfTestType, SmartReport, Usage, get_usage_data, validate_label,
+};
 
-use super::{PartitionModel, manager::UDisks2ManagerProxy};
+use super::{
+    DiskError, PartitionModel, benchmark, image, manager::UDisks2ManagerProxy, power, smart,
+};
 
 #[derive(Debug, Clone)]
 pub struct DriveModel {
@@ -33,13 +43,18 @@ pub struct DriveModel {
     pub partitions: Vec<PartitionModel>,
     pub path: String,
     pub partition_table_type: Option<String>,
+    pub geometry: DriveGeometry,
+    /// The image file backing a loop device. `None` for real drives.
+    pub backing_file: Option<String>,
     connection: Connection,
 }
 
 #[derive(Debug, Clone)]
 struct DriveBlockPair {
     block_path: OwnedObjectPath,
+    /// For loop devices, which have no drive object, this is the block path.
     drive_path: OwnedObjectPath,
+    is_loop: bool,
 }
 
 impl DriveModel {
@@ -48,10 +63,12 @@ impl DriveModel {
         block_path: &str,
         drive_proxy: &DriveProxy<'_>,
     ) -> Result<Self> {
+        let size = drive_proxy.size().await?;
+
         Ok(DriveModel {
             name: path.to_owned(),
             path: path.to_string(),
-            size: drive_proxy.size().await?,
+            size,
             id: drive_proxy.id().await?,
             model: drive_proxy.model().await?,
             serial: drive_proxy.serial().await?,
@@ -68,10 +85,191 @@ impl DriveModel {
             removable: drive_proxy.removable().await?,
             revision: drive_proxy.revision().await?,
             partition_table_type: None,
-            connection: Connection::system().await?,
+            geometry: geometry(block_path, size),
+            backing_file: None,
+            connection: drive_proxy.inner().connection().clone(),
         })
     }
 
+    /// Builds a model for a loop device. These have no drive object, so most of the drive
+    /// properties are left empty, and the drive is named after its backing file.
+    pub async fn from_loop(connection: &Connection, block_path: &OwnedObjectPath) -> Result<Self> {
+        let block_proxy = BlockProxy::builder(connection)
+            .path(block_path)?
+            .build()
+            .await?;
+        let loop_proxy = LoopProxy::builder(connection)
+            .path(block_path)?
+            .build()
+            .await?;
+
+        let backing_file = String::from_utf8_lossy(&loop_proxy.backing_file().await?)
+            .trim_end_matches('\0')
+            .to_owned();
+
+        let size = block_proxy.size().await?;
+
+        Ok(DriveModel {
+            name: backing_file.clone(),
+            path: block_path.to_string(),
+            size,
+            id: String::new(),
+            model: "Loop Device".into(),
+            serial: String::new(),
+            vendor: String::new(),
+            block_path: block_path.to_string(),
+            partitions: vec![],
+            can_power_off: false,
+            ejectable: false,
+            media_available: true,
+            media_change_detected: false,
+            media_removable: false,
+            optical: false,
+            optical_blank: false,
+            removable: true,
+            revision: String::new(),
+            partition_table_type: None,
+            geometry: geometry(block_path.as_str(), size),
+            backing_file: Some(backing_file),
+            connection: connection.clone(),
+        })
+    }
+
+    /// Whether any of the drive's partitions, or their unlocked contents, are mounted.
+    pub fn is_in_use(&self) -> bool {
+        self.partitions.iter().any(|p| p.is_in_use())
+    }
+
+    /// Whether this is a loop device backed by an image file.
+    pub fn is_loop(&self) -> bool {
+        self.backing_file.is_some()
+    }
+
+    /// The type of the partition `info` describes, checked against the partition table.
+    fn new_partition_type<'a>(&self, info: &'a CreatePartitionInfo) -> Result<&'a str> {
+        let table_type = self.partition_table_type.as_deref().unwrap_or_default();
+        let partition_type = info.partition_type_for(table_type);
+
+        if !partition_type.is_empty()
+            && PartitionTypeInfo::find_for_table(table_type, partition_type).is_none()
+        {
+            return Err(DiskError::InvalidPartitionType(
+                partition_type.to_owned(),
+                table_type.to_owned(),
+            )
+            .into());
+        }
+
+        Ok(partition_type)
+    }
+
+    /// Where the partition `info` describes will actually go, once aligned to the drive.
+    /// A partition may only overlap an extended partition by lying wholly inside it, as a
+    /// logical partition.
+    pub fn align_partition(&self, info: &CreatePartitionInfo) -> Result<AlignedRange> {
+        let end = info.offset.saturating_add(info.size);
+        let existing: Vec<_> = self
+            .partitions
+            .iter()
+            .filter(|p| !(p.is_container && p.offset <= info.offset && end <= p.offset + p.size))
+            .map(|p| p.offset..p.offset + p.size)
+            .collect();
+
+        Ok(self.geometry.align(
+            self.partition_table_type.as_deref().unwrap_or_default(),
+            info.offset,
+            info.size,
+            &existing,
+        )?)
+    }
+
+    /// Benchmarks the drive, reporting the samples collected so far as it goes. See
+    /// [`BenchmarkWrite`] for when writes are allowed.
+    pub async fn benchmark_with_progress(
+        &self,
+        options: BenchmarkOptions,
+        progress: impl Fn(BenchmarkResult) + Send + 'static,
+        cancel: CancelToken,
+    ) -> Result<BenchmarkResult> {
+        if options.write == BenchmarkWrite::PreserveData && self.is_in_use() {
+            return Err(DiskError::InUse(self.pretty_name()).into());
+        }
+
+        let used = self
+            .partitions
+            .iter()
+            .map(|p| p.offset..p.offset + p.size)
+            .collect();
+        let writable = benchmark::unallocated_regions(self.size, used);
+
+        let block_path: OwnedObjectPath = self.block_path.as_str().try_into()?;
+        benchmark::benchmark(
+            &self.connection,
+            &block_path,
+            writable,
+            options,
+            progress,
+            cancel,
+        )
+        .await
+    }
+
+    /// Which of the [`DriveSettings`] the drive supports.
+    pub async fn settings_support(&self) -> Result<DriveSettingsSupport> {
+        if self.is_loop() {
+            return Ok(DriveSettingsSupport::default());
+        }
+
+        power::settings_support(&self.connection, &self.path).await
+    }
+
+    /// Attaches the image file at `image_path` as a loop device, returning the object path of its
+    /// block device. Partitions on the image are scanned, so it appears in [`Self::get_drives`]
+    /// like any other drive.
+    pub async fn attach_image(image_path: &str, read_only: bool) -> Result<String> {
+        let connection = Connection::system().await?;
+        let proxy = ManagerProxy::new(&connection).await?;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(!read_only)
+            .open(image_path)?;
+
+        let mut options: HashMap<&str, Value<'_>> = HashMap::new();
+        options.insert("read-only", Value::from(read_only));
+
+        let block_path = proxy
+            .loop_setup(Fd::from(&file), options)
+            .await
+            .map_err(DiskError::from)?;
+
+        Ok(block_path.to_string())
+    }
+
+    /// Detaches a loop device created by [`Self::attach_image`]. Its partitions are unmounted
+    /// (and locked, if encrypted) first.
+    pub async fn detach(&self) -> Result<()> {
+        if !self.is_loop() {
+            return Err(DiskError::NotLoopDevice(self.pretty_name()).into());
+        }
+
+        for partition in &self.partitions {
+            partition.release().await?;
+        }
+
+        let proxy = LoopProxy::builder(&self.connection)
+            .path(self.block_path.clone())?
+            .build()
+            .await?;
+
+        proxy
+            .delete(HashMap::new())
+            .await
+            .map_err(DiskError::from)?;
+
+        Ok(())
+    }
+
     async fn get_drive_paths(connection: &Connection) -> Result<Vec<DriveBlockPair>> {
         let manager_proxy = UDisks2ManagerProxy::new(&connection).await?;
         let block_paths = manager_proxy.get_block_devices(HashMap::new()).await?;
@@ -79,61 +277,83 @@ impl DriveModel {
         let mut drive_paths: Vec<DriveBlockPair> = vec![];
 
         for path in block_paths {
-            let block_device = match BlockProxy::builder(&connection).path(&path)?.build().await {
-                Ok(d) => d,
-                Err(e) => {
-                    info!("Could not get block device: {}", e);
-                    continue;
-                }
-            };
+            if let Some(pair) = Self::get_drive_pair(connection, path).await? {
+                drive_paths.push(pair);
+            }
+        }
 
-            //Drive nodes don't have a .Partition interface assigned.
-            let _ = match PartitionProxy::builder(&connection)
-                .path(&path)?
-                .build()
-                .await
-            {
-                Ok(e) => match e.table().await {
-                    Ok(_) => {
-                        continue;
-                    }
-                    Err(_) => {} //We've found a drive
-                },
+        Ok(drive_paths)
+    }
+
+    /// Pairs the block device at `path` with its drive. `None` if the block device isn't a whole
+    /// drive, such as a partition or an unused loop device.
+    async fn get_drive_pair(
+        connection: &Connection,
+        path: OwnedObjectPath,
+    ) -> Result<Option<DriveBlockPair>> {
+        let block_device = match BlockProxy::builder(connection).path(&path)?.build().await {
+            Ok(d) => d,
+            Err(e) => {
+                info!("Could not get block device: {}", e);
+                return Ok(None);
+            }
+        };
+
+        //Drive nodes don't have a .Partition interface assigned.
+        let _ = match PartitionProxy::builder(connection)
+            .path(&path)?
+            .build()
+            .await
+        {
+            Ok(e) => match e.table().await {
+                Ok(_) => {
+                    return Ok(None);
+                }
                 Err(_) => {} //We've found a drive
-            };
+            },
+            Err(_) => {} //We've found a drive
+        };
+
+        match block_device.drive().await {
+            Ok(dp) if dp.as_str() != "/" => Ok(Some(DriveBlockPair {
+                block_path: path,
+                drive_path: dp,
+                is_loop: false,
+            })),
+            //Loop devices have no drive, but should still be listed. Unused loop devices
+            //have a size of 0.
+            _ => {
+                let is_loop = match LoopProxy::builder(connection).path(&path)?.build().await {
+                    Ok(l) => l.backing_file().await.is_ok(),
+                    Err(_) => false,
+                };
 
-            match block_device.drive().await {
-                Ok(dp) => drive_paths.push(DriveBlockPair {
-                    block_path: path,
-                    drive_path: dp,
-                }),
-                Err(_) => continue,
+                match is_loop && block_device.size().await.unwrap_or(0) > 0 {
+                    true => Ok(Some(DriveBlockPair {
+                        block_path: path.clone(),
+                        drive_path: path,
+                        is_loop: true,
+                    })),
+                    false => Ok(None),
+                }
             }
         }
-
-        Ok(drive_paths)
     }
 
     pub async fn get_drives() -> Result<Vec<DriveModel>> {
-        let connection = Connection::system().await?;
-        let client = Client::new_for_connection(Connection::system().await?).await?;
-        let drive_paths = Self::get_drive_paths(&connection).await?;
+        Self::get_drives_for_connection(&Connection::system().await?).await
+    }
+
+    /// Lists the drives known to the UDisks2 service on `connection`.
+    pub async fn get_drives_for_connection(connection: &Connection) -> Result<Vec<DriveModel>> {
+        let client = Client::new_for_connection(connection.clone()).await?;
+        let drive_paths = Self::get_drive_paths(connection).await?;
 
         let mut drives: HashMap<String, DriveModel> = HashMap::new();
-        let mut usage_data = get_usage_data()?;
+        let usage_data = get_usage_data()?;
 
         for pair in drive_paths {
-            let drive_proxy = DriveProxy::builder(&connection)
-                .path(&pair.drive_path)?
-                .build()
-                .await?;
-            let mut drive = match DriveModel::from_proxy(
-                &pair.drive_path,
-                &pair.block_path,
-                &drive_proxy,
-            )
-            .await
-            {
+            let drive = match Self::load(connection, &client, &usage_data, &pair).await {
                 Ok(d) => d,
                 Err(e) => {
                     warn!("Could not get drive: {}", e);
@@ -141,91 +361,277 @@ impl DriveModel {
                 }
             };
 
-            let partition_table_proxy = match PartitionTableProxy::builder(&connection)
-                .path(&pair.block_path)?
-                .build()
-                .await
-            {
-                Ok(p) => p,
-                Err(e) => {
-                    error!("Error getting partition table: {}", e);
-                    drives.insert(drive.name.clone(), drive);
-                    continue;
-                }
-            };
+            drives.insert(drive.name.clone(), drive);
+        }
 
-            drive.partition_table_type = Some(partition_table_proxy.type_().await?);
+        let mut drives: Vec<DriveModel> = drives.into_values().collect();
+        Self::sort(&mut drives);
 
-            let partition_paths = match partition_table_proxy.partitions().await {
-                Ok(p) => p,
-                Err(e) => {
-                    error!("Error getting partitions for {}: {}", pair.block_path, e);
-                    continue;
-                }
-            };
+        Ok(drives)
+    }
 
-            for partition_path in partition_paths {
-                let partition_proxy = match PartitionProxy::builder(&connection)
-                    .path(&partition_path)?
-                    .build()
-                    .await
-                {
-                    Ok(p) => p,
-                    Err(e) => {
-                        error!("Error getting partition info: {}", e);
-                        continue;
-                    }
-                };
+    /// Loads only the drive whose block device is at `block_path`. `None` if it isn't a drive.
+    pub async fn get_drive(
+        connection: &Connection,
+        block_path: &str,
+    ) -> Result<Option<DriveModel>> {
+        let path = OwnedObjectPath::try_from(block_path.to_owned())?;
 
-                let short_name = partition_path.as_str().split("/").last();
+        let Some(pair) = Self::get_drive_pair(connection, path).await? else {
+            return Ok(None);
+        };
 
-                let usage = match short_name {
-                    Some(sn) => match usage_data.iter_mut().find(|u| u.filesystem.ends_with(sn)) {
-                        Some(u) => Some(u.clone()),
-                        None => None,
-                    },
-                    None => None,
-                };
+        let client = Client::new_for_connection(connection.clone()).await?;
+        let usage_data = get_usage_data()?;
+
+        Ok(Some(
+            Self::load(connection, &client, &usage_data, &pair).await?,
+        ))
+    }
 
-                let block_proxy = BlockProxy::builder(&connection)
-                    .path(&partition_path)?
+    /// Orders drives the way they are listed, fixed drives first.
+    pub fn sort(drives: &mut [DriveModel]) {
+        drives.sort_by(|d1, d2| {
+            d1.removable.cmp(&d2.removable).then_with(|| {
+                d2.block_path.cmp(&d1.block_path) //TODO: understand this. d1 SHOULD come first in this compare...
+            })
+        });
+    }
+
+    async fn load(
+        connection: &Connection,
+        client: &Client,
+        usage_data: &[Usage],
+        pair: &DriveBlockPair,
+    ) -> Result<DriveModel> {
+        let mut drive = match pair.is_loop {
+            true => DriveModel::from_loop(connection, &pair.block_path).await?,
+            false => {
+                let drive_proxy = DriveProxy::builder(connection)
+                    .path(&pair.drive_path)?
                     .build()
                     .await?;
+                DriveModel::from_proxy(&pair.drive_path, &pair.block_path, &drive_proxy).await?
+            }
+        };
 
-                drive.partitions.push(
-                    PartitionModel::from_proxy(
-                        &client,
-                        pair.drive_path.to_string(),
-                        partition_path.clone(),
-                        usage,
-                        &partition_proxy,
-                        &block_proxy,
-                    )
-                    .await?,
-                );
+        let partition_table_proxy = match PartitionTableProxy::builder(connection)
+            .path(&pair.block_path)?
+            .build()
+            .await
+        {
+            Ok(p) => p,
+            Err(e) => {
+                error!("Error getting partition table: {}", e);
+                return Ok(drive);
             }
+        };
+
+        //Drives without a partition table have no PartitionTable interface.
+        let Ok(table_type) = partition_table_proxy.type_().await else {
+            return Ok(drive);
+        };
+        drive.partition_table_type = Some(table_type);
+
+        let partition_paths = match partition_table_proxy.partitions().await {
+            Ok(p) => p,
+            Err(e) => {
+                error!("Error getting partitions for {}: {}", pair.block_path, e);
+                return Ok(drive);
+            }
+        };
 
-            drives.insert(drive.name.clone(), drive);
+        for partition_path in partition_paths {
+            match Self::load_partition(connection, client, usage_data, &drive.path, partition_path)
+                .await
+            {
+                Ok(p) => drive.partitions.push(p),
+                Err(e) => error!("Error getting partition info: {}", e),
+            }
         }
 
-        //Order b
-        let mut drives: Vec<DriveModel> = drives.into_values().collect();
-        drives.sort_by(|d1, d2| {
-            d1.removable.cmp(&d2.removable).then_with(|| {
-                d2.block_path.cmp(&d1.block_path) //TODO: understand this. d1 SHOULD come first in this compare...
-            })
-        });
+        Ok(drive)
+    }
 
-        Ok(drives)
+    async fn load_partition(
+        connection: &Connection,
+        client: &Client,
+        usage_data: &[Usage],
+        drive_path: &str,
+        partition_path: OwnedObjectPath,
+    ) -> Result<PartitionModel> {
+        let partition_proxy = PartitionProxy::builder(connection)
+            .path(&partition_path)?
+            .build()
+            .await?;
+
+        let block_proxy = BlockProxy::builder(connection)
+            .path(&partition_path)?
+            .build()
+            .await?;
+
+        let device = DeviceNumber::from_dev_t(block_proxy.device_number().await?);
+        let usage = usage_data.iter().find(|u| u.device == device).cloned();
+
+        PartitionModel::from_proxy(
+            client,
+            drive_path.to_owned(),
+            partition_path.clone(),
+            usage,
+            &partition_proxy,
+            &block_proxy,
+        )
+        .await
+    }
+
+    /// Reloads the partition at `path`, or the one containing the unlocked device at `path`,
+    /// leaving the rest of the drive as it is. Returns false if neither is on this drive.
+    pub async fn reload_partition(&mut self, path: &str) -> Result<bool> {
+        let Some(index) = self.partitions.iter().position(|p| {
+            p.path.as_str() == path
+                || p.cleartext
+                    .as_ref()
+                    .is_some_and(|c| c.path.as_str() == path)
+        }) else {
+            return Ok(false);
+        };
+
+        let client = Client::new_for_connection(self.connection.clone()).await?;
+        let usage_data = get_usage_data()?;
+
+        self.partitions[index] = Self::load_partition(
+            &self.connection,
+            &client,
+            &usage_data,
+            &self.path,
+            self.partitions[index].path.clone(),
+        )
+        .await?;
+
+        Ok(true)
+    }
+
+    /// Creates a *.img of the whole drive, reporting progress as it goes. None of the drive's
+    /// partitions may be in use.
+    pub async fn create_image_with_progress(
+        &self,
+        output_path: String,
+        progress: impl Fn(CopyProgress) + Send + 'static,
+        cancel: CancelToken,
+    ) -> Result<DiskImage> {
+        if self.is_in_use() {
+            return Err(DiskError::InUse(self.pretty_name()).into());
+        }
+
+        let block_path: OwnedObjectPath = self.block_path.as_str().try_into()?;
+        image::create_image(&self.connection, &block_path, output_path, progress, cancel).await
+    }
+
+    /// Writes an image over the whole drive, reporting progress as it goes. See
+    /// [`Drive::restore_image`].
+    pub async fn restore_image_with_progress(
+        &self,
+        input_path: String,
+        progress: impl Fn(CopyProgress) + Send + 'static,
+        cancel: CancelToken,
+    ) -> Result<DiskImage> {
+        for partition in &self.partitions {
+            partition.release().await?;
+        }
+
+        let block_path: OwnedObjectPath = self.block_path.as_str().try_into()?;
+        image::restore_image(&self.connection, &block_path, input_path, progress, cancel).await
+    }
+}
+
+/// Reads the geometry of the drive behind a UDisks2 block object, which is named after the kernel
+/// device (e.g. `/org/freedesktop/UDisks2/block_devices/sda`).
+pub(crate) fn geometry(block_path: &str, size: u64) -> DriveGeometry {
+    let name = block_path.rsplit('/').next().unwrap_or_default();
+    DriveGeometry::from_sysfs(name, size)
+}
+
+/// Checks the passphrase of an encrypted partition before it is created.
+fn check_passphrase(info: &CreatePartitionInfo) -> Result<()> {
+    if !info.is_encrypted() {
+        return Ok(());
+    }
+
+    let filesystem = info.filesystem.info();
+    if info.password_protected && !filesystem.options.encrypt {
+        return Err(DiskError::EncryptionNotSupported(filesystem.name.to_owned()).into());
+    }
+
+    if info.password.is_empty() {
+        return Err(DiskError::PasswordRequired.into());
+    }
+
+    if info.password != info.confirmed_password {
+        return Err(DiskError::PasswordMismatch.into());
+    }
+
+    Ok(())
+}
+
+/// The Block.Format type for a new partition table. UDisks2 uses "empty" to wipe the drive
+/// without creating a table.
+fn format_type(table_type: &str) -> Result<&str> {
+    match table_type {
+        "gpt" | "dos" => Ok(table_type),
+        "" => Ok("empty"),
+        _ => Err(DiskError::InvalidTableType(table_type.to_owned()).into()),
     }
 }
 
 impl Drive for DriveModel {
+    type Partition = PartitionModel;
+
     fn pretty_name(&self) -> String {
         self.name.split("/").last().unwrap().replace("_", " ") //TODO: Handle unwrap
     }
 
+    fn block_path(&self) -> String {
+        self.block_path.clone()
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn model(&self) -> String {
+        self.model.clone()
+    }
+
+    fn serial(&self) -> String {
+        self.serial.clone()
+    }
+
+    fn removable(&self) -> bool {
+        self.removable
+    }
+
+    fn partition_table_type(&self) -> Option<String> {
+        self.partition_table_type.clone()
+    }
+
+    fn geometry(&self) -> DriveGeometry {
+        self.geometry
+    }
+
+    fn backing_file(&self) -> Option<String> {
+        self.backing_file.clone()
+    }
+
+    fn partitions(&self) -> &[PartitionModel] {
+        &self.partitions
+    }
+
+    /// Ejecting an attached image detaches its loop device.
     async fn eject(&self) -> Result<()> {
+        if self.is_loop() {
+            return self.detach().await;
+        }
+
         let proxy = DriveProxy::builder(&self.connection)
             .path(self.path.clone())?
             .build()
@@ -243,26 +649,216 @@ impl Drive for DriveModel {
         Ok(())
     }
 
+    async fn standby(&self) -> Result<()> {
+        power::standby(&self.connection, &self.path, &self.pretty_name()).await
+    }
+
+    async fn wakeup(&self) -> Result<()> {
+        power::wakeup(&self.connection, &self.path, &self.pretty_name()).await
+    }
+
+    async fn drive_settings(&self) -> Result<DriveSettings> {
+        power::settings(&self.connection, &self.path).await
+    }
+
+    async fn set_drive_settings(&self, settings: DriveSettings) -> Result<()> {
+        power::set_settings(&self.connection, &self.path, settings).await
+    }
+
     async fn create_partition(&self, info: CreatePartitionInfo) -> Result<()> {
         let partition_table_proxy = PartitionTableProxy::builder(&self.connection)
             .path(self.block_path.clone())?
             .build()
             .await?;
 
-        let partition_type = &COMMON_PARTITION_TYPES[info.selected_partitition_type].ty;
+        let range = self.align_partition(&info)?;
+        let partition_type = self.new_partition_type(&info)?;
+        let filesystem = info.filesystem.info();
+
+        let mut format_options: HashMap<&str, Value<'_>> = HashMap::new();
+
+        check_passphrase(&info)?;
+        if info.is_encrypted() {
+            format_options.insert("encrypt.passphrase", Value::from(info.password.as_str()));
+            format_options.insert("encrypt.type", Value::from("luks2"));
+        }
+
+        if filesystem.options.label && !info.name.is_empty() {
+            validate_label(filesystem.id_type, &info.name).map_err(DiskError::from)?;
+            format_options.insert("label", Value::from(info.name.as_str()));
+        }
+
+        if filesystem.options.take_ownership {
+            format_options.insert("take-ownership", Value::from(true));
+        }
+
+        if let Some(erase) = EraseMode::from(info.erase).udisks_option() {
+            format_options.insert("erase", Value::from(erase));
+        }
+
+        //Only GPT partitions have names.
+        let name = match self.partition_table_type.as_deref() {
+            Some("gpt") => info.name.as_str(),
+            _ => "",
+        };
 
         partition_table_proxy
             .create_partition_and_format(
-                info.offset,
-                info.size,
-                partition_type,
-                &info.name,
-                HashMap::new(),
+                range.offset,
+                range.size,
                 partition_type,
+                name,
                 HashMap::new(),
+                filesystem.format_type,
+                format_options,
             )
             .await?;
 
         Ok(())
     }
+
+    async fn plan_create_partition(&self, info: CreatePartitionInfo) -> Result<ChangePlan> {
+        check_passphrase(&info)?;
+        let range = self.align_partition(&info)?;
+
+        let name = match info.name.is_empty() {
+            true => String::new(),
+            false => format!(" named \"{}\"", info.name),
+        };
+
+        let mut plan = ChangePlan::new();
+        plan.push(format!(
+            "Create a {} partition{name} at {} on {}",
+            ByteSize(range.size).pretty(),
+            ByteSize(range.offset).pretty(),
+            self.pretty_name()
+        ));
+        if range.lost > 0 {
+            plan.push(format!(
+                "Leave {} unused, to align it to {}",
+                ByteSize(range.lost).pretty(),
+                ByteSize(self.geometry.alignment())
+            ));
+        }
+
+        let partition_type = self.new_partition_type(&info)?;
+        if !partition_type.is_empty() {
+            let table_type = self.partition_table_type.as_deref().unwrap_or_default();
+            let type_name = PartitionTypeInfo::find_for_table(table_type, partition_type)
+                .map_or(partition_type, |t| t.name());
+            plan.push(format!("Set its type to {type_name}"));
+        }
+
+        if let Some(erase) = EraseMode::from(info.erase).describe("it") {
+            plan.push(erase);
+        }
+
+        if info.is_encrypted() {
+            plan.push("Encrypt it with LUKS2");
+        }
+
+        if info.filesystem != FilesystemType::None && info.filesystem != FilesystemType::Luks {
+            plan.push(format!("Format it as {}", info.filesystem.info().name));
+        }
+
+        Ok(plan)
+    }
+
+    async fn format_disk(&self, table_type: String, erase: EraseMode) -> Result<()> {
+        let format_type = format_type(&table_type)?;
+
+        for partition in &self.partitions {
+            partition.release().await?;
+        }
+
+        let proxy = BlockProxy::builder(&self.connection)
+            .path(self.block_path.clone())?
+            .build()
+            .await?;
+
+        let mut options: HashMap<&str, Value<'_>> = HashMap::new();
+        if let Some(erase) = erase.udisks_option() {
+            options.insert("erase", Value::from(erase));
+        }
+
+        proxy
+            .format(format_type, options)
+            .await
+            .map_err(DiskError::from)?;
+
+        Ok(())
+    }
+
+    async fn plan_format_disk(&self, table_type: String, erase: EraseMode) -> Result<ChangePlan> {
+        format_type(&table_type)?;
+
+        let mut partitions = self.partitions.clone();
+        partitions.sort_by_key(|p| p.number);
+
+        let mut plan = ChangePlan::new();
+        for partition in &partitions {
+            partition.plan_release(&mut plan);
+        }
+
+        for partition in &partitions {
+            plan.push(format!("Destroy {}", partition.describe()));
+        }
+
+        if let Some(erase) = erase.describe(&self.pretty_name()) {
+            plan.push(erase);
+        }
+
+        match table_type.as_str() {
+            "" => plan.push(format!(
+                "Remove the partition table from {}",
+                self.pretty_name()
+            )),
+            _ => plan.push(format!(
+                "Write a new, empty {} partition table to {}",
+                table_type.to_uppercase(),
+                self.pretty_name()
+            )),
+        }
+
+        Ok(plan)
+    }
+
+    async fn create_image(&self, output_path: String) -> Result<DiskImage> {
+        self.create_image_with_progress(output_path, |_| {}, CancelToken::new())
+            .await
+    }
+
+    async fn restore_image(&self, input_path: String) -> Result<DiskImage> {
+        self.restore_image_with_progress(input_path, |_| {}, CancelToken::new())
+            .await
+    }
+
+    async fn benchmark(&self, options: BenchmarkOptions) -> Result<BenchmarkResult> {
+        self.benchmark_with_progress(options, |_| {}, CancelToken::new())
+            .await
+    }
+
+    async fn smart_report(&self) -> Result<SmartReport> {
+        if self.is_loop() {
+            return Err(DiskError::SmartNotSupported(self.pretty_name()).into());
+        }
+
+        smart::smart_report(&self.connection, &self.path, &self.pretty_name()).await
+    }
+
+    async fn start_self_test(&self, test: SelfTestType) -> Result<()> {
+        if self.is_loop() {
+            return Err(DiskError::SmartNotSupported(self.pretty_name()).into());
+        }
+
+        smart::start_self_test(&self.connection, &self.path, &self.pretty_name(), test).await
+    }
+
+    async fn abort_self_test(&self) -> Result<()> {
+        if self.is_loop() {
+            return Err(DiskError::SmartNotSupported(self.pretty_name()).into());
+        }
+
+        smart::abort_self_test(&self.connection, &self.path, &self.pretty_name()).await
+    }
 }
@@ -0,0 +1,286 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+};
+
+use anyhow::Result;
+use hardware_common::{CancelToken, CopyProgress, DiskImage, ImageCompression};
+use sha2::{Digest, Sha256};
+use udisks2::block::BlockProxy;
+use zbus::{Connection, zvariant::OwnedObjectPath};
+
+use super::DiskError;
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Images the block device at `block_path` into `output_path`, using a file descriptor passed
+/// over D-Bus by UDisks2.
+pub(crate) async fn create_image(
+    connection: &Connection,
+    block_path: &OwnedObjectPath,
+    output_path: String,
+    progress: impl Fn(CopyProgress) + Send + 'static,
+    cancel: CancelToken,
+) -> Result<DiskImage> {
+    let proxy = BlockProxy::builder(connection)
+        .path(block_path)?
+        .build()
+        .await?;
+
+    let size = proxy.size().await?;
+
+    #[allow(deprecated)]
+    let fd = proxy
+        .open_for_backup(HashMap::new())
+        .await
+        .map_err(DiskError::from)?;
+    let source = File::from(std::os::fd::OwnedFd::from(fd));
+
+    tokio::task::spawn_blocking(move || {
+        copy_to_image(source, size, &output_path, &progress, &cancel)
+    })
+    .await?
+}
+
+/// Copies `total_bytes` from `source` into a new file at `output_path`. If the copy fails or is
+/// cancelled, the partial image is removed.
+fn copy_to_image(
+    source: File,
+    total_bytes: u64,
+    output_path: &str,
+    progress: &impl Fn(CopyProgress),
+    cancel: &CancelToken,
+) -> Result<DiskImage> {
+    let output = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(output_path)?;
+
+    match copy_sparse(source, output, total_bytes, progress, cancel) {
+        Ok((size, sha256)) => Ok(DiskImage {
+            path: output_path.to_owned(),
+            size,
+            sha256,
+        }),
+        Err(e) => {
+            let _ = std::fs::remove_file(output_path);
+            Err(e)
+        }
+    }
+}
+
+/// Copies `total_bytes` from `source` into `output`, returning the number of bytes copied and
+/// their hex encoded sha256. Blocks that are entirely zero are skipped rather than written, so
+/// the output is sparse.
+fn copy_sparse(
+    mut source: File,
+    mut output: File,
+    total_bytes: u64,
+    progress: &impl Fn(CopyProgress),
+    cancel: &CancelToken,
+) -> Result<(u64, String)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut bytes_done = 0;
+
+    while bytes_done < total_bytes {
+        if cancel.is_cancelled() {
+            return Err(DiskError::Cancelled.into());
+        }
+
+        let read = source.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+
+        let chunk = &buffer[..read];
+        hasher.update(chunk);
+
+        if chunk.iter().all(|b| *b == 0) {
+            output.seek(SeekFrom::Current(read as i64))?;
+        } else {
+            output.write_all(chunk)?;
+        }
+
+        bytes_done += read as u64;
+        progress(CopyProgress {
+            bytes_done,
+            total_bytes,
+        });
+    }
+
+    //Trailing zero blocks were only seeked over, so make sure they're part of the file.
+    output.set_len(bytes_done)?;
+    output.sync_all()?;
+
+    Ok((bytes_done, format!("{:x}", hasher.finalize())))
+}
+
+/// Writes the image at `input_path` onto the block device at `block_path`, then reads the device
+/// back to verify it. `.xz` and `.zst` images are decompressed on the fly.
+///
+/// Progress covers both the write and the verification pass, so `total_bytes` is twice the size
+/// of the (uncompressed) image.
+pub(crate) async fn restore_image(
+    connection: &Connection,
+    block_path: &OwnedObjectPath,
+    input_path: String,
+    progress: impl Fn(CopyProgress) + Send + 'static,
+    cancel: CancelToken,
+) -> Result<DiskImage> {
+    let proxy = BlockProxy::builder(connection)
+        .path(block_path)?
+        .build()
+        .await?;
+
+    let target_size = proxy.size().await?;
+    let compression = ImageCompression::from_path(&input_path);
+
+    let path = input_path.clone();
+    let image_size =
+        tokio::task::spawn_blocking(move || uncompressed_size(&path, compression)).await??;
+
+    if image_size > target_size {
+        return Err(DiskError::ImageTooLarge(image_size, target_size).into());
+    }
+
+    #[allow(deprecated)]
+    let fd = proxy
+        .open_for_restore(HashMap::new())
+        .await
+        .map_err(DiskError::from)?;
+    let target = File::from(std::os::fd::OwnedFd::from(fd));
+
+    let path = input_path.clone();
+    let verify_cancel = cancel.clone();
+    let (written, progress) = tokio::task::spawn_blocking(move || {
+        let source = open_image(&path, compression);
+        let written =
+            source.and_then(|source| write_image(source, target, image_size, &progress, &cancel));
+        (written, progress)
+    })
+    .await?;
+    let sha256 = written?;
+
+    //UDisks opens the device exclusively, so the write descriptor has to be closed (above) before
+    //the device can be opened again for reading.
+    #[allow(deprecated)]
+    let fd = proxy
+        .open_for_backup(HashMap::new())
+        .await
+        .map_err(DiskError::from)?;
+    let written = File::from(std::os::fd::OwnedFd::from(fd));
+
+    let expected = sha256.clone();
+    tokio::task::spawn_blocking(move || {
+        verify_image(written, image_size, &expected, &progress, &verify_cancel)
+    })
+    .await??;
+
+    Ok(DiskImage {
+        path: input_path,
+        size: image_size,
+        sha256,
+    })
+}
+
+fn open_image(path: &str, compression: ImageCompression) -> Result<Box<dyn Read + Send>> {
+    let file = BufReader::new(File::open(path)?);
+
+    Ok(match compression {
+        ImageCompression::None => Box::new(file),
+        ImageCompression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(file)),
+        ImageCompression::Zstd => Box::new(zstd::stream::read::Decoder::with_buffer(file)?),
+    })
+}
+
+/// The size of the image once decompressed. Compressed formats don't reliably record this, so
+/// they have to be decompressed once up front.
+fn uncompressed_size(path: &str, compression: ImageCompression) -> Result<u64> {
+    match compression {
+        ImageCompression::None => Ok(std::fs::metadata(path)?.len()),
+        _ => Ok(io::copy(
+            &mut open_image(path, compression)?,
+            &mut io::sink(),
+        )?),
+    }
+}
+
+/// Copies `image_size` bytes from `source` onto `target`, returning the hex encoded sha256 of
+/// the data written.
+fn write_image(
+    mut source: Box<dyn Read + Send>,
+    mut target: File,
+    image_size: u64,
+    progress: &impl Fn(CopyProgress),
+    cancel: &CancelToken,
+) -> Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut bytes_done = 0;
+
+    loop {
+        if cancel.is_cancelled() {
+            return Err(DiskError::Cancelled.into());
+        }
+
+        let read = source.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+
+        let chunk = &buffer[..read];
+        hasher.update(chunk);
+        target.write_all(chunk)?;
+
+        bytes_done += read as u64;
+        progress(CopyProgress {
+            bytes_done,
+            total_bytes: image_size * 2,
+        });
+    }
+
+    target.sync_all()?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Reads back the first `image_size` bytes of `written` and compares their hash to `expected`.
+fn verify_image(
+    written: File,
+    image_size: u64,
+    expected: &str,
+    progress: &impl Fn(CopyProgress),
+    cancel: &CancelToken,
+) -> Result<()> {
+    let mut written = written.take(image_size);
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut bytes_done = 0;
+
+    loop {
+        if cancel.is_cancelled() {
+            return Err(DiskError::Cancelled.into());
+        }
+
+        let read = written.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..read]);
+
+        bytes_done += read as u64;
+        progress(CopyProgress {
+            bytes_done: image_size + bytes_done,
+            total_bytes: image_size * 2,
+        });
+    }
+
+    if bytes_done != image_size || format!("{:x}", hasher.finalize()) != expected {
+        return Err(DiskError::VerificationFailed.into());
+    }
+
+    Ok(())
+}
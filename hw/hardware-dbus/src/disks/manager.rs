@@ -1,14 +1,16 @@
 use anyhow::Result;
+use futures::StreamExt;
 use futures::stream::Stream;
 use futures::task::{Context, Poll};
+use hardware_common::DeviceEvent;
 use std::collections::HashMap;
-use std::time::Duration;
 use tokio::sync::mpsc;
-use tokio::time::sleep;
 use tracing::error;
+use udisks2::{block::BlockProxy, partition::PartitionProxy};
 use zbus::{
-    Connection,
-    zvariant::{self, Value},
+    Connection, MatchRule, Message, MessageStream,
+    message::Type,
+    zvariant::{OwnedObjectPath, OwnedValue, Value},
 };
 use zbus_macros::proxy;
 
@@ -23,17 +25,32 @@ pub trait UDisks2Manager {
     fn get_block_devices(
         &self,
         options: HashMap<String, Value<'_>>,
-    ) -> zbus::Result<Vec<zvariant::OwnedObjectPath>>;
+    ) -> zbus::Result<Vec<OwnedObjectPath>>;
 }
 
-pub struct DiskManager {
-    proxy: UDisks2ManagerProxy<'static>,
-}
+const UDISKS2_SERVICE: &str = "org.freedesktop.UDisks2";
+const UDISKS2_PATH: &str = "/org/freedesktop/UDisks2";
+const BLOCK_DEVICES_PATH: &str = "/org/freedesktop/UDisks2/block_devices/";
+const DRIVES_PATH: &str = "/org/freedesktop/UDisks2/drives/";
+
+const BLOCK_INTERFACE: &str = "org.freedesktop.UDisks2.Block";
+const DRIVE_INTERFACE: &str = "org.freedesktop.UDisks2.Drive";
+const FILESYSTEM_INTERFACE: &str = "org.freedesktop.UDisks2.Filesystem";
 
-#[derive(Debug, PartialEq)]
-pub enum DeviceEvent {
-    Added(String),
-    Removed(String),
+/// The interfaces the drive and partition models are loaded from. Others, such as the SMART data
+/// UDisks2 refreshes every few minutes, are read when needed and don't need a reload.
+const MODEL_INTERFACES: &[&str] = &[
+    BLOCK_INTERFACE,
+    DRIVE_INTERFACE,
+    FILESYSTEM_INTERFACE,
+    "org.freedesktop.UDisks2.Partition",
+    "org.freedesktop.UDisks2.PartitionTable",
+    "org.freedesktop.UDisks2.Encrypted",
+    "org.freedesktop.UDisks2.Loop",
+];
+
+pub struct DiskManager {
+    connection: Connection,
 }
 
 pub struct DeviceEventStream {
@@ -42,101 +59,269 @@ pub struct DeviceEventStream {
 
 impl DiskManager {
     pub async fn new() -> Result<Self> {
-        let connection = Connection::system().await?;
-        let proxy = UDisks2ManagerProxy::new(&connection).await?;
-        Ok(Self { proxy })
+        Ok(Self::new_for_connection(Connection::system().await?))
+    }
+
+    /// Uses the UDisks2 service on `connection`, rather than the one on the system bus.
+    pub fn new_for_connection(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
     }
 
-    pub fn device_event_stream(&self, interval: Duration) -> DeviceEventStream {
+    /// Listens for the signals UDisks2 emits as devices come and go, or change.
+    pub async fn device_event_stream(&self) -> Result<DeviceEventStream> {
+        //Covers the ObjectManager signals on the root object, and PropertiesChanged on every
+        //object below it. Only UDisks2 itself is trusted to send them.
+        let rule = MatchRule::builder()
+            .msg_type(Type::Signal)
+            .sender(UDISKS2_SERVICE)?
+            .path_namespace(UDISKS2_PATH)?
+            .build();
+
+        let mut signals = MessageStream::for_match_rule(rule, &self.connection, None).await?;
         let (sender, receiver) = mpsc::channel(32); // Channel capacity of 32
-        let proxy = self.proxy.clone();
 
         tokio::spawn(async move {
-            let mut previous_devices: Option<Vec<String>> = None;
-            loop {
-                let current_devices = match proxy.get_block_devices(HashMap::new()).await {
-                    Ok(paths) => paths.into_iter().map(|p| p.to_string()).collect(),
+            while let Some(message) = signals.next().await {
+                let message = match message {
+                    Ok(m) => m,
                     Err(e) => {
-                        error!("Failed to get block devices: {}", e);
-                        Vec::new()
+                        error!("Failed to receive signal: {}", e);
+                        continue;
                     }
                 };
 
-                let mut events = Vec::new();
-                if let Some(prev_devices) = &previous_devices {
-                    for device in &current_devices {
-                        if !prev_devices.contains(device) {
-                            events.push(DeviceEvent::Added(device.clone()));
-                        }
-                    }
-
-                    for device in prev_devices {
-                        if !current_devices.contains(device) {
-                            events.push(DeviceEvent::Removed(device.clone()));
-                        }
-                    }
-                }
-
-                for event in events {
+                for event in device_events(&message) {
                     if let Err(e) = sender.send(event).await {
                         error!("Failed to send event: {}", e);
-                        break; // Exit loop if sender is closed
+                        return; // Exit if the receiver is gone
                     }
                 }
-
-                previous_devices = Some(current_devices);
-                sleep(interval).await;
             }
         });
 
-        DeviceEventStream { receiver }
+        Ok(DeviceEventStream { receiver })
     }
 
+    /// Updates `drives` for a single event. Only the drive or partition the event is about is
+    /// reloaded, the rest of the tree is kept as it is.
     pub async fn apply_change(
+        &self,
         drives: &mut Vec<DriveModel>,
-        added: Option<String>,
-        removed: Option<String>,
+        event: DeviceEvent,
     ) -> Result<()> {
-        match removed {
-            Some(removed_str) => {
+        let path = match event {
+            DeviceEvent::Removed(path) => {
                 // Check for direct match on drive path or block path FIRST
                 if let Some(index) = drives
                     .iter()
-                    .position(|d| d.path == removed_str || d.block_path == removed_str)
+                    .position(|d| d.path == path || d.block_path == path)
                 {
                     drives.remove(index);
                     return Ok(()); // Early return after removing a drive
                 }
 
-                // If no direct match, THEN check partitions (using a reference!)
                 for drive in drives.iter_mut() {
                     if let Some(index) = drive
                         .partitions
                         .iter()
-                        .position(|p| p.path.as_str() == removed_str)
+                        .position(|p| p.path.as_str() == path)
                     {
                         drive.partitions.remove(index);
+                        return Ok(());
+                    }
+
+                    //A locked container loses its unlocked device.
+                    if drive.reload_partition(&path).await? {
+                        return Ok(());
                     }
                 }
+
+                return Ok(());
             }
-            None => {}
-        }
+            DeviceEvent::Mounted(path)
+            | DeviceEvent::Unmounted(path)
+            | DeviceEvent::Changed { path, .. } => {
+                for drive in drives.iter_mut() {
+                    if drive.reload_partition(&path).await? {
+                        return Ok(());
+                    }
+                }
 
-        match added {
-            Some(_) => {
-                let mut new_drives = DriveModel::get_drives().await?;
-                drives.retain(|drive| {
-                    !new_drives
-                        .iter()
-                        .any(|new_drive| new_drive.path == drive.path)
-                });
-                drives.append(&mut new_drives);
+                path
             }
-            None => {}
+            DeviceEvent::Added(path) | DeviceEvent::MediaChanged(path) => path,
+        };
+
+        //Anything else changes the drive as a whole, such as a partition being added.
+        let Some(block_path) = self.drive_block_path(drives, &path).await? else {
+            return Ok(());
+        };
+
+        let index = drives.iter().position(|d| d.block_path == block_path);
+
+        match (
+            DriveModel::get_drive(&self.connection, &block_path).await?,
+            index,
+        ) {
+            (Some(drive), Some(index)) => drives[index] = drive,
+            (Some(drive), None) => {
+                drives.push(drive);
+                DriveModel::sort(drives);
+            }
+            //Such as a loop device that has been detached.
+            (None, Some(index)) => {
+                drives.remove(index);
+            }
+            (None, None) => {}
         }
 
         Ok(())
     }
+
+    /// Finds the block path of the drive that the object at `path` belongs to. `None` for drive
+    /// objects that aren't listed yet, whose block device is announced separately.
+    async fn drive_block_path(&self, drives: &[DriveModel], path: &str) -> Result<Option<String>> {
+        if let Some(drive) = drives
+            .iter()
+            .find(|d| d.path == path || d.block_path == path)
+        {
+            return Ok(Some(drive.block_path.clone()));
+        }
+
+        if path.starts_with(DRIVES_PATH) {
+            return Ok(None);
+        }
+
+        let connection = &self.connection;
+        let mut path = OwnedObjectPath::try_from(path.to_owned())?;
+
+        let block = BlockProxy::builder(connection).path(&path)?.build().await?;
+
+        //An unlocked device belongs to the drive its encrypted partition is on. Objects without a
+        //Block interface, such as one that is still being set up, belong to no drive yet.
+        match block.crypto_backing_device().await {
+            Ok(backing) if backing.as_str() != "/" => path = backing,
+            Ok(_) => {}
+            Err(_) => return Ok(None),
+        }
+
+        let partition = PartitionProxy::builder(connection)
+            .path(&path)?
+            .build()
+            .await?;
+
+        match partition.table().await {
+            Ok(table) => Ok(Some(table.to_string())),
+            Err(_) => Ok(Some(path.to_string())),
+        }
+    }
+}
+
+/// Translates a UDisks2 signal into the events it represents. Jobs and other objects that aren't
+/// devices are ignored.
+fn device_events(message: &Message) -> Vec<DeviceEvent> {
+    let header = message.header();
+    let body = message.body();
+
+    match header.member().map(|m| m.as_str()) {
+        Some("InterfacesAdded") => {
+            match body.deserialize::<(
+                OwnedObjectPath,
+                HashMap<String, HashMap<String, OwnedValue>>,
+            )>() {
+                Ok((path, interfaces)) => interface_events(
+                    path.to_string(),
+                    interfaces.into_keys().collect(),
+                    DeviceEvent::Added,
+                ),
+                Err(_) => vec![],
+            }
+        }
+        Some("InterfacesRemoved") => match body.deserialize::<(OwnedObjectPath, Vec<String>)>() {
+            Ok((path, interfaces)) => {
+                interface_events(path.to_string(), interfaces, DeviceEvent::Removed)
+            }
+            Err(_) => vec![],
+        },
+        Some("PropertiesChanged") => {
+            let Some(path) = header.path().map(|p| p.to_string()) else {
+                return vec![];
+            };
+
+            match body.deserialize::<(String, HashMap<String, OwnedValue>, Vec<String>)>() {
+                Ok((interface, changed, _)) => property_events(path, interface, changed),
+                Err(_) => vec![],
+            }
+        }
+        _ => vec![],
+    }
+}
+
+fn is_device(path: &str) -> bool {
+    path.starts_with(BLOCK_DEVICES_PATH) || path.starts_with(DRIVES_PATH)
+}
+
+/// A whole device appearing or going away carries its Block or Drive interface, anything else is a
+/// change to an existing device, such as a filesystem being created.
+fn interface_events(
+    path: String,
+    interfaces: Vec<String>,
+    device_event: fn(String) -> DeviceEvent,
+) -> Vec<DeviceEvent> {
+    if !is_device(&path) {
+        return vec![];
+    }
+
+    if interfaces
+        .iter()
+        .any(|i| i == BLOCK_INTERFACE || i == DRIVE_INTERFACE)
+    {
+        return vec![device_event(path)];
+    }
+
+    interfaces
+        .into_iter()
+        .filter(|i| MODEL_INTERFACES.contains(&i.as_str()))
+        .map(|interface| DeviceEvent::Changed {
+            path: path.clone(),
+            interface,
+        })
+        .collect()
+}
+
+fn property_events(
+    path: String,
+    interface: String,
+    changed: HashMap<String, OwnedValue>,
+) -> Vec<DeviceEvent> {
+    if !is_device(&path) {
+        return vec![];
+    }
+
+    if interface == FILESYSTEM_INTERFACE
+        && let Some(mount_points) = changed.get("MountPoints")
+    {
+        return match &**mount_points {
+            Value::Array(a) if a.is_empty() => vec![DeviceEvent::Unmounted(path)],
+            _ => vec![DeviceEvent::Mounted(path)],
+        };
+    }
+
+    if interface == DRIVE_INTERFACE
+        && (changed.contains_key("MediaAvailable") || changed.contains_key("Media"))
+    {
+        return vec![DeviceEvent::MediaChanged(path)];
+    }
+
+    if !MODEL_INTERFACES.contains(&interface.as_str()) {
+        return vec![];
+    }
+
+    vec![DeviceEvent::Changed { path, interface }]
 }
 
 impl Stream for DeviceEventStream {
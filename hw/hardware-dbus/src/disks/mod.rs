@@ -1,7 +1,14 @@
+mod backend;
+mod benchmark;
+mod configuration;
 mod drive;
+mod image;
 mod manager;
 mod partition;
+mod power;
+mod smart;
 
+pub use backend::DbusBackend;
 pub use drive::*;
 pub use manager::*;
 pub use partition::PartitionModel;
@@ -27,4 +34,85 @@ pub enum DiskError {
 
     #[error("Zbus Error")]
     ZbusError(#[from] zbus::Error),
+
+    #[error("Partition type {0} is not valid for a {1} partition table")]
+    InvalidPartitionType(String, String),
+
+    #[error("Partition type {0} can only be used when creating a partition")]
+    CreateOnlyPartitionType(String),
+
+    #[error("Partition flags {0:#x} are not supported for a {1} partition table")]
+    InvalidPartitionFlags(u64, String),
+
+    #[error("Partition names are not supported for a {0} partition table")]
+    PartitionNameNotSupported(String),
+
+    #[error("Invalid label: {0}")]
+    InvalidLabel(#[from] hardware_common::LabelError),
+
+    #[error("Can not resize to {0} bytes, only {1} bytes are available")]
+    InsufficientSpace(u64, u64),
+
+    #[error("Can not shrink to {0} bytes, the filesystem is using {1} bytes")]
+    ShrinkBelowUsage(u64, u64),
+
+    #[error("Can not shrink {0} while it is not mounted, mount it so its usage can be checked")]
+    UsageUnknown(String),
+
+    #[error("Resizing {0} filesystems is not supported")]
+    ResizeNotSupported(String),
+
+    #[error("{0} is not an encrypted partition")]
+    NotEncrypted(String),
+
+    #[error("The passwords do not match")]
+    PasswordMismatch,
+
+    #[error("A password is required for encrypted partitions")]
+    PasswordRequired,
+
+    #[error("{0} is locked")]
+    Locked(String),
+
+    #[error("{0} is not a loop device")]
+    NotLoopDevice(String),
+
+    #[error("{0} does not support SMART")]
+    SmartNotSupported(String),
+
+    #[error("SMART is disabled on {0}")]
+    SmartDisabled(String),
+
+    #[error("{0} is in use, unmount it first")]
+    InUse(String),
+
+    #[error("A device of {0} bytes is too small to benchmark")]
+    TooSmallToBenchmark(u64),
+
+    #[error("There is no unallocated space to write to")]
+    NoUnallocatedSpace,
+
+    #[error("{0} does not support power management")]
+    PowerManagementNotSupported(String),
+
+    #[error("{1} is not a valid value for {0}")]
+    InvalidSetting(String, String),
+
+    #[error("{0} can not be encrypted")]
+    EncryptionNotSupported(String),
+
+    #[error("{0} is not a supported partition table type")]
+    InvalidTableType(String),
+
+    #[error("The operation was cancelled")]
+    Cancelled,
+
+    #[error("The image ({0} bytes) is larger than the target ({1} bytes)")]
+    ImageTooLarge(u64, u64),
+
+    #[error("The data read back from the device does not match the image")]
+    VerificationFailed,
+
+    #[error("UDisks2 Error: {0}")]
+    UDisksError(#[from] udisks2::Error),
 }
@@ -1,21 +1,46 @@
-use super::DiskError;
+use super::{
+    DiskError,
+    configuration::{self, find_item},
+    drive::geometry,
+    image,
+};
 use anyhow::Result;
 use enumflags2::{BitFlags, bitflags};
-use hardware_common::{Partition, Usage};
+use hardware_common::{
+    ByteSize, CancelToken, ChangePlan, CopyProgress, DeviceNumber, DiskImage, EraseMode,
+    FilesystemCheck, MountOptions, Partition, PartitionTypeInfo, Usage, get_usage_data,
+    validate_label,
+};
 use std::{collections::HashMap, path::Path};
 use udisks2::{
     Client,
     block::BlockProxy,
+    encrypted::EncryptedProxy,
     filesystem::FilesystemProxy,
+    manager::ManagerProxy,
     partition::{PartitionFlags, PartitionProxy},
+    partitiontable::PartitionTableProxy,
+};
+use zbus::{
+    Connection,
+    zvariant::{OwnedObjectPath, Value},
 };
-use zbus::{Connection, zvariant::OwnedObjectPath};
+
+// Resize modes reported by the UDisks2 Manager.CanResize method.
+const RESIZE_OFFLINE_SHRINK: u64 = 1 << 1;
+const RESIZE_OFFLINE_GROW: u64 = 1 << 2;
+const RESIZE_ONLINE_SHRINK: u64 = 1 << 3;
+const RESIZE_ONLINE_GROW: u64 = 1 << 4;
+
+/// `IdType` of a LUKS container.
+const LUKS_ID_TYPE: &str = "crypto_LUKS";
 
 #[derive(Debug, Clone)]
 pub struct PartitionModel {
     pub is_contained: bool,
     pub is_container: bool,
     pub table_path: OwnedObjectPath,
+    pub table_type: String,
     pub name: String,
     pub partition_type: String,
     pub id_type: String,
@@ -27,6 +52,9 @@ pub struct PartitionModel {
     pub path: OwnedObjectPath,
     pub device_path: Option<String>,
     pub usage: Option<Usage>,
+    /// The unlocked contents of an encrypted partition. `None` if the partition isn't encrypted,
+    /// or is locked.
+    pub cleartext: Option<Box<PartitionModel>>,
     connection: Option<Connection>,
     pub drive_path: String,
 }
@@ -53,24 +81,27 @@ impl PartitionModel {
         };
 
         let table_proxy = client.partition_table(&partition_proxy).await?;
-        let type_str = match client.partition_type_for_display(
-            &table_proxy.type_().await?,
-            &partition_proxy.type_().await?,
-        ) {
-            Some(val) => val
-                .to_owned()
-                .replace("part-type", "")
-                .replace("\u{004}", ""),
-            _ => partition_proxy.type_().await?,
-        };
+        let table_type = table_proxy.type_().await?;
+        let type_str =
+            match client.partition_type_for_display(&table_type, &partition_proxy.type_().await?) {
+                Some(val) => val
+                    .to_owned()
+                    .replace("part-type", "")
+                    .replace("\u{004}", ""),
+                _ => partition_proxy.type_().await?,
+            };
 
-        Ok(Self {
+        let id_type = block_proxy.id_type().await?;
+        let connection = partition_proxy.inner().connection().clone();
+
+        let mut model = Self {
             is_contained: partition_proxy.is_contained().await?,
             is_container: partition_proxy.is_container().await?,
             table_path: partition_proxy.table().await?,
+            table_type,
             name: partition_proxy.name().await?,
             partition_type: type_str,
-            id_type: block_proxy.id_type().await?,
+            id_type,
             uuid: partition_proxy.uuid().await?,
             number: partition_proxy.number().await?,
             flags: partition_proxy.flags().await?,
@@ -79,11 +110,177 @@ impl PartitionModel {
             path: partition_path.clone(),
             device_path: device_path,
             usage,
-            connection: Some(Connection::system().await?),
+            cleartext: None,
+            connection: Some(connection.clone()),
             drive_path: drive_path,
+        };
+
+        if model.is_encrypted() {
+            let encrypted_proxy = EncryptedProxy::builder(&connection)
+                .path(&model.path)?
+                .build()
+                .await?;
+            let cleartext_path = encrypted_proxy.cleartext_device().await?;
+
+            //The cleartext device is "/" while the container is locked.
+            if cleartext_path.as_str() != "/" {
+                model.cleartext = Some(Box::new(
+                    Self::from_cleartext(&connection, &model, cleartext_path).await?,
+                ));
+            }
+        }
+
+        Ok(model)
+    }
+
+    /// Builds the model of the unlocked device inside an encrypted partition. It shares its
+    /// position on the drive with `parent`, but has its own block device and filesystem.
+    async fn from_cleartext(
+        connection: &Connection,
+        parent: &PartitionModel,
+        cleartext_path: OwnedObjectPath,
+    ) -> Result<Self> {
+        let block_proxy = BlockProxy::builder(connection)
+            .path(&cleartext_path)?
+            .build()
+            .await?;
+
+        let device_path = String::from_utf8_lossy(&block_proxy.preferred_device().await?)
+            .trim_end_matches('\0')
+            .to_string();
+
+        let device = DeviceNumber::from_dev_t(block_proxy.device_number().await?);
+        let usage = get_usage_data()?.into_iter().find(|u| u.device == device);
+
+        Ok(Self {
+            is_contained: true,
+            is_container: false,
+            table_path: parent.table_path.clone(),
+            table_type: parent.table_type.clone(),
+            name: block_proxy.id_label().await?,
+            partition_type: parent.partition_type.clone(),
+            id_type: block_proxy.id_type().await?,
+            uuid: block_proxy.id_uuid().await?,
+            number: parent.number,
+            flags: BitFlags::empty(),
+            offset: parent.offset,
+            size: block_proxy.size().await?,
+            path: cleartext_path,
+            device_path: Some(device_path),
+            usage,
+            cleartext: None,
+            connection: Some(connection.clone()),
+            drive_path: parent.drive_path.clone(),
         })
     }
 
+    /// Whether the partition, or its unlocked contents, are mounted.
+    pub fn is_in_use(&self) -> bool {
+        self.usage.is_some() || self.cleartext.as_ref().is_some_and(|c| c.usage.is_some())
+    }
+
+    /// Creates a *.img of self, reporting progress as it goes. The partition must not be in use.
+    pub async fn create_image_with_progress(
+        &self,
+        output_path: String,
+        progress: impl Fn(CopyProgress) + Send + 'static,
+        cancel: CancelToken,
+    ) -> Result<DiskImage> {
+        if self.connection.is_none() {
+            return Err(DiskError::NotConnected(self.name.clone()).into());
+        }
+
+        if self.is_in_use() {
+            return Err(DiskError::InUse(self.name()).into());
+        }
+
+        image::create_image(
+            self.connection.as_ref().unwrap(),
+            &self.path,
+            output_path,
+            progress,
+            cancel,
+        )
+        .await
+    }
+
+    /// Writes an image over self, reporting progress as it goes. See [`Partition::restore_image`].
+    pub async fn restore_image_with_progress(
+        &self,
+        input_path: String,
+        progress: impl Fn(CopyProgress) + Send + 'static,
+        cancel: CancelToken,
+    ) -> Result<DiskImage> {
+        if self.connection.is_none() {
+            return Err(DiskError::NotConnected(self.name.clone()).into());
+        }
+
+        self.release().await?;
+
+        image::restore_image(
+            self.connection.as_ref().unwrap(),
+            &self.path,
+            input_path,
+            progress,
+            cancel,
+        )
+        .await
+    }
+
+    /// Makes sure nothing is using the partition, by unmounting it or, for an unlocked
+    /// container, unmounting its cleartext device and locking it.
+    pub(crate) async fn release(&self) -> Result<()> {
+        if self.is_encrypted() {
+            if self.cleartext.is_some() {
+                self.lock().await?;
+            }
+        } else if self.usage.is_some() {
+            self.unmount().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds what [`PartitionModel::release`] would do to `plan`.
+    pub(crate) fn plan_release(&self, plan: &mut ChangePlan) {
+        if self.is_encrypted() {
+            if let Some(cleartext) = &self.cleartext {
+                if cleartext.usage.is_some() {
+                    plan.push(format!("Unmount the unlocked contents of {}", self.name()));
+                }
+                plan.push(format!("Lock {}", self.name()));
+            }
+        } else if self.usage.is_some() {
+            plan.push(format!("Unmount {}", self.name()));
+        }
+    }
+
+    /// The name, size and contents of the partition, for a [`ChangePlan`].
+    pub(crate) fn describe(&self) -> String {
+        let contents = match self.id_type.is_empty() {
+            true => "unknown contents".to_owned(),
+            false => self.id_type.clone(),
+        };
+
+        format!(
+            "{} ({}, {contents})",
+            self.name(),
+            ByteSize(self.size).pretty()
+        )
+    }
+
+    /// The model holding the filesystem, which is the cleartext device for encrypted partitions.
+    fn filesystem_model(&self) -> Result<&PartitionModel> {
+        if !self.is_encrypted() {
+            return Ok(self);
+        }
+
+        match &self.cleartext {
+            Some(cleartext) => Ok(cleartext),
+            None => Err(DiskError::Locked(self.name()).into()),
+        }
+    }
+
     /// Returns informating about the given partition that is suitable for presentation in an user
     /// interface in a single line of text.
     ///
@@ -108,6 +305,95 @@ impl PartitionModel {
 
         Ok(type_str)
     }
+
+    /// Returns the largest size this partition can grow to, which is bounded by the start of the
+    /// next partition on the drive, or the end of the space the partition table leaves usable.
+    pub async fn max_size(&self) -> Result<u64> {
+        if self.connection.is_none() {
+            return Err(DiskError::NotConnected(self.name.clone()).into());
+        }
+        let connection = self.connection.as_ref().unwrap();
+
+        let table_proxy = PartitionTableProxy::builder(connection)
+            .path(&self.table_path)?
+            .build()
+            .await?;
+        let table_block_proxy = BlockProxy::builder(connection)
+            .path(&self.table_path)?
+            .build()
+            .await?;
+
+        let mut end = geometry(self.table_path.as_str(), table_block_proxy.size().await?)
+            .usable_range(&self.table_type)
+            .end;
+
+        for path in table_proxy.partitions().await? {
+            if path == self.path {
+                continue;
+            }
+
+            let offset = PartitionProxy::builder(connection)
+                .path(&path)?
+                .build()
+                .await?
+                .offset()
+                .await?;
+
+            if offset > self.offset && offset < end {
+                end = offset;
+            }
+        }
+
+        Ok(end.saturating_sub(self.offset))
+    }
+
+    /// Checks that the partition can be resized to `new_size_bytes`.
+    async fn check_resize(&self, new_size_bytes: u64) -> Result<()> {
+        if new_size_bytes < self.size && !self.id_type.is_empty() {
+            //How much of the filesystem is used is only known while it is mounted, and shrinking
+            //without knowing could cut off data.
+            let filesystem = self.filesystem_model()?;
+            let Some(usage) = &filesystem.usage else {
+                return Err(DiskError::UsageUnknown(filesystem.name()).into());
+            };
+
+            if new_size_bytes < usage.used {
+                return Err(DiskError::ShrinkBelowUsage(new_size_bytes, usage.used).into());
+            }
+        } else if new_size_bytes > self.size {
+            let max_size = self.max_size().await?;
+            if new_size_bytes > max_size {
+                return Err(DiskError::InsufficientSpace(new_size_bytes, max_size).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the filesystem can be resized, and returns whether it has to be unmounted
+    /// first.
+    async fn filesystem_resize_needs_unmount(
+        &self,
+        connection: &Connection,
+        shrink: bool,
+    ) -> Result<bool> {
+        let manager_proxy = ManagerProxy::new(connection).await?;
+        let (available, modes, _missing_util) = manager_proxy
+            .can_resize(&self.id_type)
+            .await
+            .map_err(DiskError::from)?;
+
+        let (online, offline) = match shrink {
+            true => (RESIZE_ONLINE_SHRINK, RESIZE_OFFLINE_SHRINK),
+            false => (RESIZE_ONLINE_GROW, RESIZE_OFFLINE_GROW),
+        };
+
+        if !available || modes & (online | offline) == 0 {
+            return Err(DiskError::ResizeNotSupported(self.id_type.clone()).into());
+        }
+
+        Ok(self.usage.is_some() && modes & online == 0)
+    }
 }
 
 impl Partition for PartitionModel {
@@ -123,6 +409,51 @@ impl Partition for PartitionModel {
         format!("Partition {}", &self.number)
     }
 
+    fn label(&self) -> String {
+        self.name.clone()
+    }
+
+    fn number(&self) -> u32 {
+        self.number
+    }
+
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn id_type(&self) -> String {
+        self.id_type.clone()
+    }
+
+    fn partition_type(&self) -> String {
+        self.partition_type.clone()
+    }
+
+    fn uuid(&self) -> String {
+        self.uuid.clone()
+    }
+
+    fn device_path(&self) -> Option<String> {
+        self.device_path.clone()
+    }
+
+    fn usage(&self) -> Option<&Usage> {
+        self.usage.as_ref()
+    }
+
+    /// Whether this partition is a LUKS container.
+    fn is_encrypted(&self) -> bool {
+        self.id_type == LUKS_ID_TYPE
+    }
+
+    fn cleartext(&self) -> Option<&Self> {
+        self.cleartext.as_deref()
+    }
+
     async fn connect(&mut self) -> Result<()> {
         if self.connection.is_none() {
             self.connection = Some(Connection::system().await?);
@@ -166,9 +497,8 @@ impl Partition for PartitionModel {
             return Err(DiskError::NotConnected(self.name.clone()).into());
         }
 
-        //try to unmount first. If it fails, it's likely because it's already unmounted.
-        //any other error with the partition should be caught by the delete operation.
-        let _ = self.unmount().await;
+        //UDisks refuses to delete a mounted filesystem or an unlocked container.
+        self.release().await?;
 
         let proxy = PartitionProxy::builder(&self.connection.as_ref().unwrap())
             .path(&self.path)?
@@ -180,64 +510,447 @@ impl Partition for PartitionModel {
         Ok(())
     }
 
-    async fn format(&self, name: String, erase: bool, partion_type: String) -> Result<()> {
+    async fn plan_delete(&self) -> Result<ChangePlan> {
         if self.connection.is_none() {
             return Err(DiskError::NotConnected(self.name.clone()).into());
         }
 
+        let mut plan = ChangePlan::new();
+        self.plan_release(&mut plan);
+        plan.push(format!("Delete {}, and everything on it", self.describe()));
+
+        Ok(plan)
+    }
+
+    async fn format(&self, name: String, erase: EraseMode, filesystem_type: String) -> Result<()> {
+        if self.connection.is_none() {
+            return Err(DiskError::NotConnected(self.name.clone()).into());
+        }
+
+        //UDisks refuses to format a mounted filesystem or an unlocked container.
+        self.release().await?;
+
+        let proxy = BlockProxy::builder(&self.connection.as_ref().unwrap())
+            .path(&self.path)?
+            .build()
+            .await?;
+
+        let mut options: HashMap<&str, Value<'_>> = HashMap::new();
+        options.insert("update-partition-type", Value::from(true));
+
+        if !name.is_empty() {
+            options.insert("label", Value::from(name.as_str()));
+        }
+
+        if let Some(erase) = erase.udisks_option() {
+            options.insert("erase", Value::from(erase));
+        }
+
+        proxy
+            .format(&filesystem_type, options)
+            .await
+            .map_err(DiskError::from)?;
+
         Ok(())
     }
 
-    //TODO: implement
+    async fn plan_format(
+        &self,
+        name: String,
+        erase: EraseMode,
+        filesystem_type: String,
+    ) -> Result<ChangePlan> {
+        if self.connection.is_none() {
+            return Err(DiskError::NotConnected(self.name.clone()).into());
+        }
+
+        let mut plan = ChangePlan::new();
+        self.plan_release(&mut plan);
+
+        if let Some(erase) = erase.describe(&self.describe()) {
+            plan.push(erase);
+        } else {
+            plan.push(format!("Destroy everything on {}", self.describe()));
+        }
+
+        match name.is_empty() {
+            true => plan.push(format!("Create a new {filesystem_type} filesystem")),
+            false => plan.push(format!(
+                "Create a new {filesystem_type} filesystem labelled \"{name}\""
+            )),
+        }
+
+        Ok(plan)
+    }
+
     async fn edit_partition(&self, partition_type: String, name: String, flags: u64) -> Result<()> {
         if self.connection.is_none() {
             return Err(DiskError::NotConnected(self.name.clone()).into());
         }
 
+        let type_info = match PartitionTypeInfo::find_for_table(&self.table_type, &partition_type) {
+            Some(t) => t,
+            None => {
+                return Err(DiskError::InvalidPartitionType(
+                    partition_type,
+                    self.table_type.clone(),
+                )
+                .into());
+            }
+        };
+
+        if type_info.is_create_only() {
+            return Err(DiskError::CreateOnlyPartitionType(partition_type).into());
+        }
+
+        let supported_flags = match self.table_type.as_str() {
+            "gpt" => {
+                PartitionFlags::SystemPartition
+                    | PartitionFlags::LegacyBIOSBootable
+                    | PartitionFlags::ReadOnly
+                    | PartitionFlags::Hidden
+                    | PartitionFlags::NoAutoMount
+            }
+            "dos" => BitFlags::from(PartitionFlags::Bootable),
+            _ => BitFlags::empty(),
+        };
+
+        let flags = match BitFlags::<PartitionFlags>::from_bits(flags) {
+            Ok(f) if supported_flags.contains(f) => f,
+            _ => {
+                return Err(
+                    DiskError::InvalidPartitionFlags(flags, self.table_type.clone()).into(),
+                );
+            }
+        };
+
+        if self.table_type != "gpt" && !name.is_empty() {
+            return Err(DiskError::PartitionNameNotSupported(self.table_type.clone()).into());
+        }
+
+        let proxy = PartitionProxy::builder(&self.connection.as_ref().unwrap())
+            .path(&self.path)?
+            .build()
+            .await?;
+
+        proxy
+            .set_type(type_info.ty, HashMap::new())
+            .await
+            .map_err(DiskError::from)?;
+
+        if self.table_type == "gpt" {
+            proxy
+                .set_name(&name, HashMap::new())
+                .await
+                .map_err(DiskError::from)?;
+        }
+
+        proxy
+            .set_flags(flags, HashMap::new())
+            .await
+            .map_err(DiskError::from)?;
+
         Ok(())
     }
 
-    //TODO: implement
     async fn edit_filesystem_label(&self, label: String) -> Result<()> {
         if self.connection.is_none() {
             return Err(DiskError::NotConnected(self.name.clone()).into());
         }
 
+        let filesystem = self.filesystem_model()?;
+        validate_label(&filesystem.id_type, &label).map_err(DiskError::from)?;
+
+        let proxy = FilesystemProxy::builder(&self.connection.as_ref().unwrap())
+            .path(&filesystem.path)?
+            .build()
+            .await?;
+
+        proxy
+            .set_label(&label, HashMap::new())
+            .await
+            .map_err(DiskError::from)?;
+
         Ok(())
     }
 
-    //TODO: implement
-    async fn change_passphrase(&self) -> Result<()> {
+    async fn unlock(&self, passphrase: String) -> Result<()> {
         if self.connection.is_none() {
             return Err(DiskError::NotConnected(self.name.clone()).into());
         }
+
+        if !self.is_encrypted() {
+            return Err(DiskError::NotEncrypted(self.name()).into());
+        }
+
+        let proxy = EncryptedProxy::builder(&self.connection.as_ref().unwrap())
+            .path(&self.path)?
+            .build()
+            .await?;
+
+        proxy
+            .unlock(&passphrase, HashMap::new())
+            .await
+            .map_err(DiskError::from)?;
+
         Ok(())
     }
 
-    //TODO: implement
-    async fn resize(&self, new_size_bytes: u64) -> Result<()> {
+    async fn lock(&self) -> Result<()> {
         if self.connection.is_none() {
             return Err(DiskError::NotConnected(self.name.clone()).into());
         }
+
+        if !self.is_encrypted() {
+            return Err(DiskError::NotEncrypted(self.name()).into());
+        }
+
+        //The cleartext filesystem must be unmounted before the container can be locked.
+        if let Some(cleartext) = &self.cleartext
+            && cleartext.usage.is_some()
+        {
+            cleartext.unmount().await?;
+        }
+
+        let proxy = EncryptedProxy::builder(&self.connection.as_ref().unwrap())
+            .path(&self.path)?
+            .build()
+            .await?;
+
+        proxy.lock(HashMap::new()).await.map_err(DiskError::from)?;
+
         Ok(())
     }
 
-    //TODO: implement
-    async fn check_filesystem(&self) -> Result<()> {
+    async fn change_passphrase(&self, passphrase: String, new_passphrase: String) -> Result<()> {
         if self.connection.is_none() {
             return Err(DiskError::NotConnected(self.name.clone()).into());
         }
+
+        if !self.is_encrypted() {
+            return Err(DiskError::NotEncrypted(self.name()).into());
+        }
+
+        let proxy = EncryptedProxy::builder(&self.connection.as_ref().unwrap())
+            .path(&self.path)?
+            .build()
+            .await?;
+
+        proxy
+            .change_passphrase(&passphrase, &new_passphrase, HashMap::new())
+            .await
+            .map_err(DiskError::from)?;
+
         Ok(())
     }
 
-    //TODO: implement
-    async fn repair_filesystem(&self) -> Result<()> {
+    async fn resize(&self, new_size_bytes: u64) -> Result<()> {
         if self.connection.is_none() {
             return Err(DiskError::NotConnected(self.name.clone()).into());
         }
+        let connection = self.connection.as_ref().unwrap();
+        let shrink = new_size_bytes < self.size;
+
+        self.check_resize(new_size_bytes).await?;
+
+        let partition_proxy = PartitionProxy::builder(connection)
+            .path(&self.path)?
+            .build()
+            .await?;
+
+        //No filesystem, so only the partition needs resizing.
+        if self.id_type.is_empty() {
+            partition_proxy
+                .resize(new_size_bytes, HashMap::new())
+                .await
+                .map_err(DiskError::from)?;
+            return Ok(());
+        }
+
+        let filesystem = self.filesystem_model()?;
+        if filesystem
+            .filesystem_resize_needs_unmount(connection, shrink)
+            .await?
+        {
+            filesystem.unmount().await?;
+        }
+
+        let filesystem_proxy = FilesystemProxy::builder(connection)
+            .path(&filesystem.path)?
+            .build()
+            .await?;
+
+        //The LUKS header takes up the start of an encrypted partition, so the cleartext device is
+        //smaller than the partition by that much.
+        let cleartext_size =
+            new_size_bytes.saturating_sub(self.size.saturating_sub(filesystem.size));
+        let encrypted_proxy = match self.is_encrypted() {
+            true => Some(
+                EncryptedProxy::builder(connection)
+                    .path(&self.path)?
+                    .build()
+                    .await?,
+            ),
+            false => None,
+        };
+
+        //The filesystem has to shrink before the partition does, and the partition has to grow
+        //before the filesystem can. An encrypted container sits between the two.
+        if shrink {
+            filesystem_proxy
+                .resize(cleartext_size, HashMap::new())
+                .await
+                .map_err(DiskError::from)?;
+            if let Some(encrypted_proxy) = &encrypted_proxy {
+                encrypted_proxy
+                    .resize(cleartext_size, HashMap::new())
+                    .await
+                    .map_err(DiskError::from)?;
+            }
+            partition_proxy
+                .resize(new_size_bytes, HashMap::new())
+                .await
+                .map_err(DiskError::from)?;
+        } else {
+            partition_proxy
+                .resize(new_size_bytes, HashMap::new())
+                .await
+                .map_err(DiskError::from)?;
+            if let Some(encrypted_proxy) = &encrypted_proxy {
+                encrypted_proxy
+                    .resize(cleartext_size, HashMap::new())
+                    .await
+                    .map_err(DiskError::from)?;
+            }
+
+            //A size of 0 fills the partition, which may have been rounded up for alignment.
+            filesystem_proxy
+                .resize(0, HashMap::new())
+                .await
+                .map_err(DiskError::from)?;
+        }
+
         Ok(())
     }
 
+    async fn plan_resize(&self, new_size_bytes: u64) -> Result<ChangePlan> {
+        if self.connection.is_none() {
+            return Err(DiskError::NotConnected(self.name.clone()).into());
+        }
+        let connection = self.connection.as_ref().unwrap();
+        let shrink = new_size_bytes < self.size;
+
+        self.check_resize(new_size_bytes).await?;
+
+        let name = self.name();
+        let size = ByteSize(new_size_bytes).pretty();
+        let mut plan = ChangePlan::new();
+
+        if self.id_type.is_empty() {
+            plan.push(format!(
+                "Resize {name} from {} to {size}",
+                ByteSize(self.size).pretty()
+            ));
+            return Ok(plan);
+        }
+
+        let filesystem = self.filesystem_model()?;
+        if filesystem
+            .filesystem_resize_needs_unmount(connection, shrink)
+            .await?
+        {
+            plan.push(format!("Unmount {name}"));
+        }
+
+        let id_type = &filesystem.id_type;
+        if shrink {
+            plan.push(format!("Shrink the {id_type} filesystem to fit {size}"));
+            if self.is_encrypted() {
+                plan.push("Shrink the encrypted container to match".to_owned());
+            }
+            plan.push(format!("Shrink {name} to {size}"));
+        } else {
+            plan.push(format!("Grow {name} to {size}"));
+            if self.is_encrypted() {
+                plan.push("Grow the encrypted container to fill it".to_owned());
+            }
+            plan.push(format!("Grow the {id_type} filesystem to fill it"));
+        }
+
+        Ok(plan)
+    }
+
+    async fn check_filesystem(&self) -> Result<FilesystemCheck> {
+        if self.connection.is_none() {
+            return Err(DiskError::NotConnected(self.name.clone()).into());
+        }
+        let connection = self.connection.as_ref().unwrap();
+        let filesystem = self.filesystem_model()?;
+
+        let manager_proxy = ManagerProxy::new(connection).await?;
+        let (available, missing_util) = manager_proxy
+            .can_check(&filesystem.id_type)
+            .await
+            .map_err(DiskError::from)?;
+
+        if !available {
+            return Ok(FilesystemCheck::Unsupported(missing_util));
+        }
+
+        //Filesystems have to be unmounted to be checked.
+        if filesystem.usage.is_some() {
+            return Err(DiskError::InUse(self.name()).into());
+        }
+
+        let proxy = FilesystemProxy::builder(connection)
+            .path(&filesystem.path)?
+            .build()
+            .await?;
+
+        match proxy.check(HashMap::new()).await.map_err(DiskError::from)? {
+            true => Ok(FilesystemCheck::Clean),
+            false => Ok(FilesystemCheck::ErrorsFound),
+        }
+    }
+
+    async fn repair_filesystem(&self) -> Result<FilesystemCheck> {
+        if self.connection.is_none() {
+            return Err(DiskError::NotConnected(self.name.clone()).into());
+        }
+        let connection = self.connection.as_ref().unwrap();
+        let filesystem = self.filesystem_model()?;
+
+        //Avoid touching a filesystem that doesn't need it.
+        match self.check_filesystem().await? {
+            FilesystemCheck::ErrorsFound => {}
+            result => return Ok(result),
+        }
+
+        let manager_proxy = ManagerProxy::new(connection).await?;
+        let (available, missing_util) = manager_proxy
+            .can_repair(&filesystem.id_type)
+            .await
+            .map_err(DiskError::from)?;
+
+        if !available {
+            return Ok(FilesystemCheck::Unsupported(missing_util));
+        }
+
+        let proxy = FilesystemProxy::builder(connection)
+            .path(&filesystem.path)?
+            .build()
+            .await?;
+
+        match proxy
+            .repair(HashMap::new())
+            .await
+            .map_err(DiskError::from)?
+        {
+            true => Ok(FilesystemCheck::Repaired),
+            false => Ok(FilesystemCheck::ErrorsFound),
+        }
+    }
+
     //TODO: implement
     async fn take_ownership(&self, recursive: bool) -> Result<()> {
         if self.connection.is_none() {
@@ -246,15 +959,43 @@ impl Partition for PartitionModel {
         Ok(())
     }
 
-    //TODO: implement. See how edit mount options -> User session defaults works in gnome-disks.
     async fn default_mount_options(&self) -> Result<()> {
         if self.connection.is_none() {
             return Err(DiskError::NotConnected(self.name.clone()).into());
         }
+
+        let proxy = BlockProxy::builder(&self.connection.as_ref().unwrap())
+            .path(&self.filesystem_model()?.path)?
+            .build()
+            .await?;
+
+        let config = proxy.configuration().await?;
+
+        if let Some(item) = find_item(&config, "fstab") {
+            proxy
+                .remove_configuration_item(&configuration::as_item(item), HashMap::new())
+                .await
+                .map_err(DiskError::from)?;
+        }
+
         Ok(())
     }
 
-    //TODO: implement. Look at gnome-disks -> partition -> edit mount options. Likely make all params optional.
+    async fn mount_options(&self) -> Result<Option<MountOptions>> {
+        if self.connection.is_none() {
+            return Err(DiskError::NotConnected(self.name.clone()).into());
+        }
+
+        let proxy = BlockProxy::builder(&self.connection.as_ref().unwrap())
+            .path(&self.filesystem_model()?.path)?
+            .build()
+            .await?;
+
+        let config = proxy.configuration().await?;
+
+        Ok(find_item(&config, "fstab").map(|(_, details)| configuration::parse_fstab(details)))
+    }
+
     async fn edit_mount_options(
         &self,
         mount_at_startup: bool,
@@ -271,6 +1012,72 @@ impl Partition for PartitionModel {
         if self.connection.is_none() {
             return Err(DiskError::NotConnected(self.name.clone()).into());
         }
+        let connection = self.connection.as_ref().unwrap();
+
+        let mount_options = MountOptions {
+            mount_at_startup,
+            show_in_ui,
+            require_auth: requre_auth,
+            display_name,
+            icon_name,
+            symbolic_icon_name,
+            options,
+            mount_point,
+            identify_as,
+            filesystem_type: file_system_type,
+        };
+
+        let proxy = BlockProxy::builder(connection)
+            .path(&self.filesystem_model()?.path)?
+            .build()
+            .await?;
+
+        let config = proxy.configuration().await?;
+        let new_item = configuration::fstab_item(&mount_options);
+
+        match find_item(&config, "fstab") {
+            Some(old_item) => proxy
+                .update_configuration_item(
+                    &configuration::as_item(old_item),
+                    &new_item,
+                    HashMap::new(),
+                )
+                .await
+                .map_err(DiskError::from)?,
+            None => proxy
+                .add_configuration_item(&new_item, HashMap::new())
+                .await
+                .map_err(DiskError::from)?,
+        }
+
+        //The container also needs a crypttab entry, so it is unlocked before it is mounted.
+        if self.is_encrypted() {
+            let proxy = BlockProxy::builder(connection)
+                .path(&self.path)?
+                .build()
+                .await?;
+
+            let config = proxy.configuration().await?;
+            let old_item = find_item(&config, "crypttab");
+            let uuid = proxy.id_uuid().await?;
+            let new_item = configuration::crypttab_item(old_item, &uuid, mount_at_startup);
+
+            match old_item {
+                Some(old_item) => proxy
+                    .update_configuration_item(
+                        &configuration::as_item(old_item),
+                        &new_item,
+                        HashMap::new(),
+                    )
+                    .await
+                    .map_err(DiskError::from)?,
+                None => proxy
+                    .add_configuration_item(&new_item, HashMap::new())
+                    .await
+                    .map_err(DiskError::from)?,
+            }
+        }
+
         Ok(())
     }
 
@@ -282,11 +1089,13 @@ impl Partition for PartitionModel {
         Ok(())
     }
 
-    //TODO: implement. creates a *.img of self.
-    async fn create_image(&self, output_path: String) -> Result<()> {
-        if self.connection.is_none() {
-            return Err(DiskError::NotConnected(self.name.clone()).into());
-        }
-        Ok(())
+    async fn create_image(&self, output_path: String) -> Result<DiskImage> {
+        self.create_image_with_progress(output_path, |_| {}, CancelToken::new())
+            .await
+    }
+
+    async fn restore_image(&self, input_path: String) -> Result<DiskImage> {
+        self.restore_image_with_progress(input_path, |_| {}, CancelToken::new())
+            .await
     }
 }
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use hardware_common::{
+    AAM_LEVEL_MAX, AAM_LEVEL_MIN, APM_LEVEL_MIN, DriveSettings, DriveSettingsSupport,
+};
+use udisks2::{ata::AtaProxy, drive::DriveProxy};
+use zbus::{Connection, zvariant::Value};
+
+use super::DiskError;
+
+/// Keys of the UDisks2 `Drive.Configuration` property.
+const STANDBY_KEY: &str = "ata-pm-standby";
+const APM_KEY: &str = "ata-apm-level";
+const AAM_KEY: &str = "ata-aam-level";
+const WRITE_CACHE_KEY: &str = "ata-write-cache-enabled";
+
+async fn ata_proxy<'a>(connection: &Connection, drive_path: &'a str) -> Result<AtaProxy<'a>> {
+    Ok(AtaProxy::builder(connection)
+        .path(drive_path)?
+        .build()
+        .await?)
+}
+
+/// Fails unless the drive supports ATA power management.
+async fn pm_proxy<'a>(
+    connection: &Connection,
+    drive_path: &'a str,
+    name: &str,
+) -> Result<AtaProxy<'a>> {
+    let proxy = ata_proxy(connection, drive_path).await?;
+
+    match proxy.pm_supported().await {
+        Ok(true) => Ok(proxy),
+        _ => Err(DiskError::PowerManagementNotSupported(name.to_owned()).into()),
+    }
+}
+
+pub(crate) async fn standby(connection: &Connection, drive_path: &str, name: &str) -> Result<()> {
+    pm_proxy(connection, drive_path, name)
+        .await?
+        .pm_standby(HashMap::new())
+        .await
+        .map_err(DiskError::from)?;
+
+    Ok(())
+}
+
+pub(crate) async fn wakeup(connection: &Connection, drive_path: &str, name: &str) -> Result<()> {
+    pm_proxy(connection, drive_path, name)
+        .await?
+        .pm_wakeup(HashMap::new())
+        .await
+        .map_err(DiskError::from)?;
+
+    Ok(())
+}
+
+/// Reads which settings the drive supports. Drives without the ATA interface support none.
+pub(crate) async fn settings_support(
+    connection: &Connection,
+    drive_path: &str,
+) -> Result<DriveSettingsSupport> {
+    let proxy = ata_proxy(connection, drive_path).await?;
+
+    //Every property fails the same way on a drive that isn't ATA.
+    if proxy.pm_supported().await.is_err() {
+        return Ok(DriveSettingsSupport::default());
+    }
+
+    let recommended = proxy.aam_vendor_recommended_value().await?;
+
+    Ok(DriveSettingsSupport {
+        standby: proxy.pm_supported().await?,
+        apm: proxy.apm_supported().await?,
+        aam: proxy.aam_supported().await?,
+        write_cache: proxy.write_cache_supported().await?,
+        aam_vendor_recommended: u8::try_from(recommended).ok().filter(|v| *v != 0),
+    })
+}
+
+pub(crate) async fn settings(connection: &Connection, drive_path: &str) -> Result<DriveSettings> {
+    let proxy = DriveProxy::builder(connection)
+        .path(drive_path)?
+        .build()
+        .await?;
+    let configuration = proxy.configuration().await?;
+
+    let level = |key: &str| match configuration.get(key).map(|v| &**v) {
+        Some(Value::I32(v)) => u8::try_from(*v).ok(),
+        _ => None,
+    };
+
+    Ok(DriveSettings {
+        standby_timeout: level(STANDBY_KEY),
+        apm_level: level(APM_KEY),
+        aam_level: level(AAM_KEY),
+        write_cache: match configuration.get(WRITE_CACHE_KEY).map(|v| &**v) {
+            Some(Value::Bool(v)) => Some(*v),
+            _ => None,
+        },
+    })
+}
+
+/// Replaces the drive's configuration. UDisks2 stores it, and applies it now and every time the
+/// drive is connected.
+pub(crate) async fn set_settings(
+    connection: &Connection,
+    drive_path: &str,
+    settings: DriveSettings,
+) -> Result<()> {
+    if let Some(level) = settings.apm_level
+        && level < APM_LEVEL_MIN
+    {
+        return Err(DiskError::InvalidSetting(APM_KEY.into(), level.to_string()).into());
+    }
+
+    if let Some(level) = settings.aam_level
+        && !(AAM_LEVEL_MIN..=AAM_LEVEL_MAX).contains(&level)
+    {
+        return Err(DiskError::InvalidSetting(AAM_KEY.into(), level.to_string()).into());
+    }
+
+    let mut configuration: HashMap<&str, Value<'_>> = HashMap::new();
+
+    for (key, level) in [
+        (STANDBY_KEY, settings.standby_timeout),
+        (APM_KEY, settings.apm_level),
+        (AAM_KEY, settings.aam_level),
+    ] {
+        if let Some(level) = level {
+            configuration.insert(key, Value::from(level as i32));
+        }
+    }
+
+    if let Some(write_cache) = settings.write_cache {
+        configuration.insert(WRITE_CACHE_KEY, Value::from(write_cache));
+    }
+
+    let proxy = DriveProxy::builder(connection)
+        .path(drive_path)?
+        .build()
+        .await?;
+
+    proxy
+        .set_configuration(configuration, HashMap::new())
+        .await
+        .map_err(DiskError::from)?;
+
+    Ok(())
+}
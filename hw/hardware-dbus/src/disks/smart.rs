@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use hardware_common::{
+    ByteSize, SelfTestStatus, SelfTestType, SmartAssessment, SmartAttribute, SmartProtocol,
+    SmartReport, kelvin_to_celsius,
+};
+use udisks2::{ata::AtaProxy, nvme::controller::ControllerProxy};
+use zbus::{
+    Connection,
+    zvariant::{OwnedValue, Value},
+};
+
+use super::DiskError;
+
+/// ATA attribute units, as reported in the `pretty_unit` field of `SmartGetAttributes`.
+const ATA_UNIT_DIMENSIONLESS: i32 = 1;
+const ATA_UNIT_MILLISECONDS: i32 = 2;
+const ATA_UNIT_SECTORS: i32 = 3;
+const ATA_UNIT_MILLIKELVIN: i32 = 4;
+
+const ATA_REALLOCATED_SECTOR_COUNT: u8 = 5;
+
+/// NVMe data units are thousands of 512 byte blocks.
+const NVME_DATA_UNIT: u64 = 512 * 1000;
+
+/// An NVMe attribute key, its display name, and how to format its value.
+type NvmeField = (&'static str, &'static str, fn(u64) -> String);
+
+/// Critical warnings that only mean the drive is running hot, rather than failing.
+const NVME_TEMPERATURE_WARNING: &str = "temperature";
+
+async fn ata_proxy<'a>(connection: &Connection, drive_path: &'a str) -> Result<AtaProxy<'a>> {
+    Ok(AtaProxy::builder(connection)
+        .path(drive_path)?
+        .build()
+        .await?)
+}
+
+async fn nvme_proxy<'a>(
+    connection: &Connection,
+    drive_path: &'a str,
+) -> Result<ControllerProxy<'a>> {
+    Ok(ControllerProxy::builder(connection)
+        .path(drive_path)?
+        .build()
+        .await?)
+}
+
+/// Works out which SMART interface the drive implements. Proxies can be built for interfaces
+/// an object doesn't have, so this checks whether their properties can actually be read.
+async fn protocol(connection: &Connection, drive_path: &str, name: &str) -> Result<SmartProtocol> {
+    if let Ok(ata) = ata_proxy(connection, drive_path).await
+        && ata.smart_supported().await.unwrap_or(false)
+    {
+        if !ata.smart_enabled().await? {
+            return Err(DiskError::SmartDisabled(name.to_owned()).into());
+        }
+
+        return Ok(SmartProtocol::Ata);
+    }
+
+    if let Ok(nvme) = nvme_proxy(connection, drive_path).await
+        && nvme.state().await.is_ok()
+    {
+        return Ok(SmartProtocol::Nvme);
+    }
+
+    Err(DiskError::SmartNotSupported(name.to_owned()).into())
+}
+
+pub(crate) async fn smart_report(
+    connection: &Connection,
+    drive_path: &str,
+    name: &str,
+) -> Result<SmartReport> {
+    match protocol(connection, drive_path, name).await? {
+        SmartProtocol::Ata => ata_report(&ata_proxy(connection, drive_path).await?).await,
+        SmartProtocol::Nvme => nvme_report(&nvme_proxy(connection, drive_path).await?).await,
+    }
+}
+
+pub(crate) async fn start_self_test(
+    connection: &Connection,
+    drive_path: &str,
+    name: &str,
+    test: SelfTestType,
+) -> Result<()> {
+    let test = test.udisks_option();
+
+    match protocol(connection, drive_path, name).await? {
+        SmartProtocol::Ata => ata_proxy(connection, drive_path)
+            .await?
+            .smart_selftest_start(test, HashMap::new())
+            .await
+            .map_err(DiskError::from)?,
+        SmartProtocol::Nvme => nvme_proxy(connection, drive_path)
+            .await?
+            .smart_selftest_start(test, HashMap::new())
+            .await
+            .map_err(DiskError::from)?,
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn abort_self_test(
+    connection: &Connection,
+    drive_path: &str,
+    name: &str,
+) -> Result<()> {
+    match protocol(connection, drive_path, name).await? {
+        SmartProtocol::Ata => ata_proxy(connection, drive_path)
+            .await?
+            .smart_selftest_abort(HashMap::new())
+            .await
+            .map_err(DiskError::from)?,
+        SmartProtocol::Nvme => nvme_proxy(connection, drive_path)
+            .await?
+            .smart_selftest_abort(HashMap::new())
+            .await
+            .map_err(DiskError::from)?,
+    }
+
+    Ok(())
+}
+
+async fn ata_report(ata: &AtaProxy<'_>) -> Result<SmartReport> {
+    //Refresh the data, unless that would mean spinning up a sleeping disk. If the update fails
+    //the last known data is still valid.
+    let mut update_options: HashMap<&str, Value<'_>> = HashMap::new();
+    update_options.insert("nowakeup", Value::from(true));
+    let _ = ata.smart_update(update_options).await;
+
+    let attributes: Vec<SmartAttribute> = ata
+        .smart_get_attributes(HashMap::new())
+        .await
+        .map_err(DiskError::from)?
+        .into_iter()
+        .map(
+            |(id, name, _flags, value, worst, threshold, pretty, pretty_unit, _expansion)| {
+                //-1 means the drive doesn't report a normalized value.
+                let normalized = |v: i32| if v < 0 { None } else { Some(v) };
+
+                SmartAttribute {
+                    id: Some(id),
+                    name,
+                    value: normalized(value),
+                    worst: normalized(worst),
+                    threshold: normalized(threshold),
+                    pretty: format_ata_value(pretty, pretty_unit),
+                    failing: threshold > 0 && value > 0 && value <= threshold,
+                    failed_in_past: threshold > 0 && worst > 0 && worst <= threshold,
+                }
+            },
+        )
+        .collect();
+
+    let bad_sectors = ata.smart_num_bad_sectors().await?.max(0) as u64;
+    let reallocated_sectors = match attributes
+        .iter()
+        .find(|a| a.id == Some(ATA_REALLOCATED_SECTOR_COUNT))
+    {
+        Some(a) => a.pretty.split(' ').next().and_then(|v| v.parse().ok()),
+        None => Some(bad_sectors),
+    };
+
+    let assessment = if ata.smart_failing().await? {
+        SmartAssessment::Failing
+    } else if ata.smart_num_attributes_failing().await? > 0
+        || ata.smart_num_attributes_failed_in_the_past().await? > 0
+        || bad_sectors > 0
+    {
+        SmartAssessment::Warning
+    } else {
+        SmartAssessment::Healthy
+    };
+
+    Ok(SmartReport {
+        protocol: SmartProtocol::Ata,
+        assessment,
+        temperature_celsius: kelvin_to_celsius(ata.smart_temperature().await?),
+        power_on_hours: Some(ata.smart_power_on_seconds().await? / 3600),
+        reallocated_sectors,
+        self_test: SelfTestStatus::from_udisks(
+            &ata.smart_selftest_status().await?,
+            ata.smart_selftest_percent_remaining().await?,
+        ),
+        updated: ata.smart_updated().await?,
+        attributes,
+    })
+}
+
+fn format_ata_value(pretty: i64, unit: i32) -> String {
+    match unit {
+        ATA_UNIT_DIMENSIONLESS => pretty.to_string(),
+        ATA_UNIT_MILLISECONDS => match pretty {
+            ms if ms >= 3_600_000 => format!("{:.1} hours", ms as f64 / 3_600_000.),
+            ms if ms >= 60_000 => format!("{:.1} minutes", ms as f64 / 60_000.),
+            ms => format!("{} ms", ms),
+        },
+        ATA_UNIT_SECTORS => format!("{} sectors", pretty),
+        ATA_UNIT_MILLIKELVIN => format!("{:.1} °C", pretty as f64 / 1000. - 273.15),
+        _ => format!("{} (unknown unit)", pretty),
+    }
+}
+
+async fn nvme_report(nvme: &ControllerProxy<'_>) -> Result<SmartReport> {
+    let _ = nvme.smart_update(HashMap::new()).await;
+
+    let values = nvme
+        .smart_get_attributes(HashMap::new())
+        .await
+        .map_err(DiskError::from)?;
+
+    let mut attributes = vec![];
+
+    if let (Some(spare), Some(threshold)) = (
+        nvme_number(&values, "avail_spare"),
+        nvme_number(&values, "spare_thresh"),
+    ) {
+        attributes.push(SmartAttribute {
+            id: None,
+            name: "Available Spare".into(),
+            value: Some(spare as i32),
+            worst: None,
+            threshold: Some(threshold as i32),
+            pretty: format!("{}%", spare),
+            failing: spare < threshold,
+            failed_in_past: false,
+        });
+    }
+
+    let fields: [NvmeField; 11] = [
+        ("percent_used", "Percentage Used", |v| format!("{}%", v)),
+        ("total_data_read", "Data Read", |v| {
+            ByteSize(v * NVME_DATA_UNIT).pretty()
+        }),
+        ("total_data_written", "Data Written", |v| {
+            ByteSize(v * NVME_DATA_UNIT).pretty()
+        }),
+        ("ctrl_busy_time", "Controller Busy Time", |v| {
+            format!("{} minutes", v)
+        }),
+        ("power_cycles", "Power Cycles", |v| v.to_string()),
+        ("unsafe_shutdowns", "Unsafe Shutdowns", |v| v.to_string()),
+        ("media_errors", "Media and Data Integrity Errors", |v| {
+            v.to_string()
+        }),
+        ("num_err_log_entries", "Error Log Entries", |v| {
+            v.to_string()
+        }),
+        ("warning_temp_time", "Warning Temperature Time", |v| {
+            format!("{} minutes", v)
+        }),
+        ("critical_temp_time", "Critical Temperature Time", |v| {
+            format!("{} minutes", v)
+        }),
+        ("wctemp", "Warning Temperature Threshold", |v| {
+            format!("{:.1} °C", v as f64 - 273.15)
+        }),
+    ];
+
+    for (key, name, format) in fields {
+        if let Some(value) = nvme_number(&values, key) {
+            attributes.push(SmartAttribute {
+                id: None,
+                name: name.into(),
+                value: None,
+                worst: None,
+                threshold: None,
+                pretty: format(value),
+                failing: false,
+                failed_in_past: false,
+            });
+        }
+    }
+
+    let critical_warnings = nvme.smart_critical_warning().await?;
+    let media_errors = nvme_number(&values, "media_errors").unwrap_or(0);
+    let percent_used = nvme_number(&values, "percent_used").unwrap_or(0);
+
+    let assessment = if critical_warnings
+        .iter()
+        .any(|w| w != NVME_TEMPERATURE_WARNING)
+    {
+        SmartAssessment::Failing
+    } else if !critical_warnings.is_empty()
+        || media_errors > 0
+        || percent_used >= 100
+        || attributes.iter().any(|a| a.failing)
+    {
+        SmartAssessment::Warning
+    } else {
+        SmartAssessment::Healthy
+    };
+
+    Ok(SmartReport {
+        protocol: SmartProtocol::Nvme,
+        assessment,
+        temperature_celsius: kelvin_to_celsius(nvme.smart_temperature().await? as f64),
+        power_on_hours: Some(nvme.smart_power_on_hours().await?),
+        reallocated_sectors: None,
+        self_test: SelfTestStatus::from_udisks(
+            &nvme.smart_selftest_status().await?,
+            nvme.smart_selftest_percent_remaining().await?,
+        ),
+        updated: nvme.smart_updated().await?,
+        attributes,
+    })
+}
+
+/// Reads an unsigned value from the NVMe attributes, which use a mix of integer widths.
+fn nvme_number(values: &HashMap<String, OwnedValue>, key: &str) -> Option<u64> {
+    match &**values.get(key)? {
+        Value::U8(v) => Some(*v as u64),
+        Value::U16(v) => Some(*v as u64),
+        Value::U32(v) => Some(*v as u64),
+        Value::U64(v) => Some(*v),
+        _ => None,
+    }
+}
@@ -0,0 +1,184 @@
+use std::{collections::VecDeque, time::Duration};
+
+use anyhow::Result;
+use futures::{StreamExt, stream::BoxStream};
+use hardware_common::{
+    ChangePlan, CreatePartitionInfo, DeviceEvent, DiskBackend, Drive, EraseMode, Partition,
+};
+
+use super::{DriveModel, PartitionModel};
+
+/// How often the block devices are rescanned, as nothing tells us when they change.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub type DeviceEventStream = BoxStream<'static, DeviceEvent>;
+
+/// The disks-rs backend. It reads and writes partition tables itself, so it needs root, but not
+/// udisksd. See [`DriveModel`] and [`PartitionModel`] for what it can do.
+pub struct DisksRsBackend;
+
+/// The events that turn `old` into `new`. Drives are told apart by their device node, and a
+/// drive whose partitions differ is reported as changed. Partitions that were mounted or
+/// unmounted since, by this app or anything else, are reported too.
+fn diff(old: &[DriveModel], new: &[DriveModel]) -> Vec<DeviceEvent> {
+    let partitions = |d: &DriveModel| {
+        d.partitions
+            .iter()
+            .map(|p| (p.path.clone(), p.offset, p.size))
+            .collect::<Vec<_>>()
+    };
+    let mounted = |p: &PartitionModel| p.usage.as_ref().map(|u| (u.device, u.mount_point.clone()));
+
+    let mut events = vec![];
+
+    for drive in old {
+        if !new.iter().any(|d| d.path == drive.path) {
+            events.push(DeviceEvent::Removed(drive.path.clone()));
+        }
+    }
+
+    for drive in new {
+        let Some(old) = old.iter().find(|d| d.path == drive.path) else {
+            events.push(DeviceEvent::Added(drive.path.clone()));
+            continue;
+        };
+
+        if partitions(old) != partitions(drive) {
+            events.push(DeviceEvent::Changed {
+                path: drive.path.clone(),
+                interface: "partitions".into(),
+            });
+        }
+
+        for partition in &drive.partitions {
+            let Some(previous) = old.partitions.iter().find(|p| p.path == partition.path) else {
+                continue;
+            };
+
+            let now = mounted(partition);
+            if now == mounted(previous) {
+                continue;
+            }
+
+            events.push(match now {
+                Some(_) => DeviceEvent::Mounted(partition.path.clone()),
+                None => DeviceEvent::Unmounted(partition.path.clone()),
+            });
+        }
+    }
+
+    events
+}
+
+impl DiskBackend for DisksRsBackend {
+    type Drive = DriveModel;
+    type Partition = PartitionModel;
+    type EventStream = DeviceEventStream;
+
+    //Mounting is left to the system. Partitions only report where they are mounted.
+    const CAN_MOUNT: bool = false;
+
+    async fn connect() -> Result<Self> {
+        Ok(Self)
+    }
+
+    async fn drives(&self) -> Result<Vec<DriveModel>> {
+        DriveModel::get_drives().await
+    }
+
+    async fn watch(&self) -> Result<DeviceEventStream> {
+        let known = DriveModel::get_drives().await?;
+
+        Ok(
+            futures::stream::unfold((known, VecDeque::new()), |(mut known, mut pending)| async {
+                loop {
+                    if let Some(event) = pending.pop_front() {
+                        return Some((event, (known, pending)));
+                    }
+
+                    tokio::time::sleep(POLL_INTERVAL).await;
+
+                    //A failed scan is retried on the next poll.
+                    if let Ok(drives) = DriveModel::get_drives().await {
+                        pending.extend(diff(&known, &drives));
+                        known = drives;
+                    }
+                }
+            })
+            .boxed(),
+        )
+    }
+
+    /// Rescanning is as cheap as working out what a single event changed, so every event
+    /// reloads all of the drives.
+    async fn apply_event(&self, drives: &mut Vec<DriveModel>, _event: DeviceEvent) -> Result<()> {
+        *drives = DriveModel::get_drives().await?;
+        Ok(())
+    }
+
+    async fn create_partition(&self, drive: &DriveModel, info: CreatePartitionInfo) -> Result<()> {
+        drive.create_partition(info).await
+    }
+
+    async fn plan_create_partition(
+        &self,
+        drive: &DriveModel,
+        info: CreatePartitionInfo,
+    ) -> Result<ChangePlan> {
+        drive.plan_create_partition(info).await
+    }
+
+    async fn delete_partition(&self, partition: &PartitionModel) -> Result<()> {
+        partition.delete().await
+    }
+
+    async fn plan_delete_partition(&self, partition: &PartitionModel) -> Result<ChangePlan> {
+        partition.plan_delete().await
+    }
+
+    async fn format_partition(
+        &self,
+        partition: &PartitionModel,
+        name: String,
+        erase: EraseMode,
+        filesystem_type: String,
+    ) -> Result<()> {
+        partition.format(name, erase, filesystem_type).await
+    }
+
+    async fn plan_format_partition(
+        &self,
+        partition: &PartitionModel,
+        name: String,
+        erase: EraseMode,
+        filesystem_type: String,
+    ) -> Result<ChangePlan> {
+        partition.plan_format(name, erase, filesystem_type).await
+    }
+
+    async fn format_disk(
+        &self,
+        drive: &DriveModel,
+        table_type: String,
+        erase: EraseMode,
+    ) -> Result<()> {
+        drive.format_disk(table_type, erase).await
+    }
+
+    async fn plan_format_disk(
+        &self,
+        drive: &DriveModel,
+        table_type: String,
+        erase: EraseMode,
+    ) -> Result<ChangePlan> {
+        drive.plan_format_disk(table_type, erase).await
+    }
+
+    async fn mount(&self, partition: &PartitionModel) -> Result<()> {
+        partition.mount().await
+    }
+
+    async fn unmount(&self, partition: &PartitionModel) -> Result<()> {
+        partition.unmount().await
+    }
+}
@@ -0,0 +1,401 @@
+use std::{
+    fs::{self, File},
+    io::Read,
+};
+
+use anyhow::Result;
+use disks_rs::BlockDevice;
+use disks_rs_partitioning::{
+    GptAttributes, PartitionAttributes, TableAttributes, blkpg, gpt::partition_types::LINUX_FS,
+    planner::Planner, writer::DiskWriter,
+};
+use hardware_common::{
+    AlignedRange, BenchmarkOptions, BenchmarkResult, ByteSize, ChangePlan, CreatePartitionInfo,
+    DiskImage, Drive, DriveGeometry, DriveSettings, EraseMode, FilesystemType, SelfTestType,
+    SmartReport, Usage, get_usage_data,
+};
+
+use super::{DiskError, PartitionModel, format, not_supported, partition::find_usage};
+
+/// A drive found by disks-rs. Partitions can be created and deleted on drives with a GPT partition
+/// table, and any drive given a new one; everything else needs the UDisks2 backend.
+#[derive(Debug, Clone)]
+pub struct DriveModel {
+    pub name: String,
+    /// The device node, e.g. /dev/sda.
+    pub path: String,
+    pub size: u64,
+    pub model: String,
+    pub removable: bool,
+    /// The image file backing a loop device. `None` for real drives.
+    pub backing_file: Option<String>,
+    /// "gpt" or "dos", as UDisks2 names them. `None` if there is no partition table.
+    pub partition_table_type: Option<String>,
+    pub partitions: Vec<PartitionModel>,
+    pub geometry: DriveGeometry,
+}
+
+impl DriveModel {
+    pub(crate) fn from_block_device(device: &BlockDevice, usage_data: &[Usage]) -> Self {
+        let path = device.device().to_string_lossy().into_owned();
+        let name = device.name().to_string();
+        let geometry = DriveGeometry::from_sysfs(device.name(), device.size());
+
+        Self {
+            partitions: device
+                .partitions()
+                .iter()
+                .map(|p| PartitionModel::from_partition(p, &path, usage_data))
+                .collect(),
+            size: device.size(),
+            model: read_sysfs(&name, "device/model").unwrap_or_default(),
+            removable: read_sysfs(&name, "removable").is_some_and(|r| r == "1"),
+            backing_file: read_sysfs(&name, "loop/backing_file"),
+            partition_table_type: read_table_type(&path, geometry.logical_sector_size),
+            geometry,
+            name,
+            path,
+        }
+    }
+
+    /// Scans sysfs for block devices. This blocks, so callers on an async runtime should run it
+    /// with `spawn_blocking`.
+    pub fn discover() -> Result<Vec<BlockDevice>> {
+        Ok(BlockDevice::discover()?)
+    }
+
+    pub async fn get_drives() -> Result<Vec<DriveModel>> {
+        tokio::task::spawn_blocking(|| {
+            let usage_data = get_usage_data()?;
+
+            Ok(Self::discover()?
+                .iter()
+                .map(|d| Self::from_block_device(d, &usage_data))
+                .collect())
+        })
+        .await?
+    }
+
+    /// Finds the block device behind `path` again, as disks-rs needs it for every change.
+    pub(crate) fn find_device(path: &str) -> Result<BlockDevice> {
+        Self::discover()?
+            .into_iter()
+            .find(|d| d.device().to_string_lossy() == path)
+            .ok_or_else(|| DiskError::DeviceNotFound(path.to_owned()).into())
+    }
+
+    /// Where the partition `info` describes will actually go, once aligned to the drive.
+    pub fn align_partition(&self, info: &CreatePartitionInfo) -> Result<AlignedRange> {
+        let existing: Vec<_> = self
+            .partitions
+            .iter()
+            .map(|p| p.offset..p.offset + p.size)
+            .collect();
+
+        let table_type = self.partition_table_type.as_deref().unwrap_or_default();
+
+        Ok(self
+            .geometry
+            .align(table_type, info.offset, info.size, &existing)?)
+    }
+
+    /// Plans a change to the block device at `path` with `plan`, and simulates it. If `write` is
+    /// set, it is then written, and the kernel made to pick up the new partitions.
+    ///
+    /// Fails if any partition on the device is mounted, as the kernel won't let go of it, or
+    /// unless `new_table` is set, if the device doesn't have a GPT partition table.
+    pub(crate) async fn run_plan(
+        path: String,
+        write: bool,
+        new_table: bool,
+        plan: impl FnOnce(&BlockDevice, &mut Planner) -> Result<()> + Send + 'static,
+    ) -> Result<()> {
+        tokio::task::spawn_blocking(move || {
+            let device = Self::find_device(&path)?;
+            check_not_in_use(&device)?;
+
+            if !new_table {
+                let geometry = DriveGeometry::from_sysfs(device.name(), device.size());
+                check_gpt(read_table_type(&path, geometry.logical_sector_size).as_deref())?;
+            }
+
+            let mut planner = Planner::new(&device);
+            plan(&device, &mut planner)?;
+
+            let writer = DiskWriter::new(&device, &planner);
+            writer.simulate()?;
+            if !write {
+                return Ok(());
+            }
+
+            writer.write()?;
+            blkpg::sync_gpt_partitions(device.device())?;
+            Ok(())
+        })
+        .await?
+    }
+}
+
+/// Reads `attribute` of the block device `name` (e.g. `sda`) from sysfs, trimmed. `None` if it
+/// doesn't exist or is empty.
+fn read_sysfs(name: &str, attribute: &str) -> Option<String> {
+    fs::read_to_string(format!("/sys/class/block/{name}/{attribute}"))
+        .ok()
+        .map(|v| v.trim().to_owned())
+        .filter(|v| !v.is_empty())
+}
+
+/// Reads the partition table type of the device at `path` from its first two sectors. `None` if
+/// there is no partition table, or the device can't be read.
+fn read_table_type(path: &str, logical_sector_size: u64) -> Option<String> {
+    let sector = logical_sector_size as usize;
+    let mut start = vec![0; sector * 2];
+    File::open(path).ok()?.read_exact(&mut start).ok()?;
+
+    //A GPT keeps a protective MBR in its first sector, so its own header is looked for first.
+    if start[sector..].starts_with(b"EFI PART") {
+        Some("gpt".to_owned())
+    } else if start[510..512] == [0x55, 0xaa] {
+        Some("dos".to_owned())
+    } else {
+        None
+    }
+}
+
+/// Refuses to change partitions on anything but a GPT partition table, the only kind disks-rs
+/// reads and writes.
+fn check_gpt(table_type: Option<&str>) -> Result<()> {
+    match table_type {
+        Some("gpt") => Ok(()),
+        Some(table_type) => Err(DiskError::UnsupportedTableType(table_type.to_owned()).into()),
+        None => Err(not_supported("Changing drives without a partition table")),
+    }
+}
+
+/// Refuses to change the partition table of `device` while any of its partitions are mounted.
+fn check_not_in_use(device: &BlockDevice) -> Result<()> {
+    let usage_data = get_usage_data()?;
+
+    match device
+        .partitions()
+        .iter()
+        .find(|p| find_usage(&p.device, &usage_data).is_some())
+    {
+        Some(p) => Err(DiskError::InUse(p.device.to_string_lossy().into_owned()).into()),
+        None => Ok(()),
+    }
+}
+
+/// The GPT type of the partitions disks-rs creates.
+const LINUX_FS_TYPE: &str = "0fc63daf-8483-4772-8e79-3d69d8477de4";
+
+/// Refuses what disks-rs can't do for a new partition: it can't format or encrypt, and only
+/// creates Linux filesystem partitions in GPT partition tables.
+fn check_create(table_type: Option<&str>, info: &CreatePartitionInfo) -> Result<()> {
+    check_gpt(table_type)?;
+
+    if info.filesystem != FilesystemType::None {
+        return Err(not_supported("Formatting new partitions"));
+    }
+
+    if info.is_encrypted() {
+        return Err(not_supported("Encrypting new partitions"));
+    }
+
+    let partition_type = info.partition_type_for("gpt");
+    if !partition_type.is_empty() && !partition_type.eq_ignore_ascii_case(LINUX_FS_TYPE) {
+        return Err(not_supported("Partition types other than Linux filesystem"));
+    }
+
+    Ok(())
+}
+
+/// Plans the partition described by `info` at `range`. disks-rs only creates Linux filesystem
+/// partitions.
+fn plan_partition(
+    planner: &mut Planner,
+    info: CreatePartitionInfo,
+    range: AlignedRange,
+) -> Result<()> {
+    planner.plan_add_partition_with_attributes(
+        range.offset,
+        range.offset + range.size,
+        Some(PartitionAttributes {
+            table: TableAttributes::Gpt(GptAttributes {
+                name: Some(info.name),
+                uuid: None,
+                type_guid: LINUX_FS,
+            }),
+            role: None,
+            filesystem: None,
+        }),
+    )?;
+
+    Ok(())
+}
+
+impl Drive for DriveModel {
+    type Partition = PartitionModel;
+
+    fn pretty_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn block_path(&self) -> String {
+        self.path.clone()
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn model(&self) -> String {
+        self.model.clone()
+    }
+
+    /// sysfs doesn't report serial numbers for every kind of drive, so none are shown.
+    fn serial(&self) -> String {
+        String::new()
+    }
+
+    fn removable(&self) -> bool {
+        self.removable
+    }
+
+    fn partition_table_type(&self) -> Option<String> {
+        self.partition_table_type.clone()
+    }
+
+    fn geometry(&self) -> DriveGeometry {
+        self.geometry
+    }
+
+    fn backing_file(&self) -> Option<String> {
+        self.backing_file.clone()
+    }
+
+    fn partitions(&self) -> &[PartitionModel] {
+        &self.partitions
+    }
+
+    async fn eject(&self) -> Result<()> {
+        Err(not_supported("Ejecting"))
+    }
+
+    async fn power_off(&self) -> Result<()> {
+        Err(not_supported("Powering off"))
+    }
+
+    async fn standby(&self) -> Result<()> {
+        Err(not_supported("Standby"))
+    }
+
+    async fn wakeup(&self) -> Result<()> {
+        Err(not_supported("Waking up"))
+    }
+
+    async fn drive_settings(&self) -> Result<DriveSettings> {
+        Err(not_supported("Drive settings"))
+    }
+
+    async fn set_drive_settings(&self, _settings: DriveSettings) -> Result<()> {
+        Err(not_supported("Drive settings"))
+    }
+
+    async fn create_partition(&self, info: CreatePartitionInfo) -> Result<()> {
+        check_create(self.partition_table_type.as_deref(), &info)?;
+        let range = self.align_partition(&info)?;
+
+        Self::run_plan(self.path.clone(), true, false, move |_, planner| {
+            plan_partition(planner, info, range)
+        })
+        .await
+    }
+
+    async fn plan_create_partition(&self, info: CreatePartitionInfo) -> Result<ChangePlan> {
+        check_create(self.partition_table_type.as_deref(), &info)?;
+        let range = self.align_partition(&info)?;
+        let name = match info.name.is_empty() {
+            true => String::new(),
+            false => format!(" named \"{}\"", info.name),
+        };
+
+        let mut plan = ChangePlan::new();
+        plan.push(format!(
+            "Create a {} partition{name} at {} on {}",
+            ByteSize(range.size).pretty(),
+            ByteSize(range.offset).pretty(),
+            self.name
+        ));
+        if range.lost > 0 {
+            plan.push(format!(
+                "Leave {} unused, to align it to {}",
+                ByteSize(range.lost).pretty(),
+                ByteSize(self.geometry.alignment())
+            ));
+        }
+        plan.push("Set its type to Linux filesystem");
+
+        Self::run_plan(self.path.clone(), false, false, move |_, planner| {
+            plan_partition(planner, info, range)
+        })
+        .await?;
+
+        Ok(plan)
+    }
+
+    async fn format_disk(&self, table_type: String, erase: EraseMode) -> Result<()> {
+        format::check_format(&table_type, erase)?;
+
+        Self::run_plan(self.path.clone(), true, true, |_, planner| {
+            planner.plan_initialize_disk()?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn plan_format_disk(&self, table_type: String, erase: EraseMode) -> Result<ChangePlan> {
+        format::check_format(&table_type, erase)?;
+
+        let mut plan = ChangePlan::new();
+        for partition in &self.partitions {
+            plan.push(format!("Destroy {}", partition.describe()));
+        }
+        plan.push(format!(
+            "Write a new, empty GPT partition table to {}",
+            self.name
+        ));
+
+        Self::run_plan(self.path.clone(), false, true, |_, planner| {
+            planner.plan_initialize_disk()?;
+            Ok(())
+        })
+        .await?;
+
+        Ok(plan)
+    }
+
+    async fn create_image(&self, _output_path: String) -> Result<DiskImage> {
+        Err(not_supported("Creating images"))
+    }
+
+    async fn restore_image(&self, _input_path: String) -> Result<DiskImage> {
+        Err(not_supported("Restoring images"))
+    }
+
+    async fn smart_report(&self) -> Result<SmartReport> {
+        Err(not_supported("SMART"))
+    }
+
+    async fn start_self_test(&self, _test: SelfTestType) -> Result<()> {
+        Err(not_supported("SMART"))
+    }
+
+    async fn abort_self_test(&self) -> Result<()> {
+        Err(not_supported("SMART"))
+    }
+
+    async fn benchmark(&self, _options: BenchmarkOptions) -> Result<BenchmarkResult> {
+        Err(not_supported("Benchmarking"))
+    }
+}
@@ -0,0 +1,18 @@
+use anyhow::Result;
+use hardware_common::EraseMode;
+
+use super::DiskError;
+
+/// Checks that a drive can be given a new `table_type` partition table. disks-rs only writes
+/// GPT, and can't erase, so anything else is refused before the disk is touched.
+pub fn check_format(table_type: &str, erase: EraseMode) -> Result<()> {
+    if table_type != "gpt" {
+        return Err(DiskError::UnsupportedTableType(table_type.to_owned()).into());
+    }
+
+    if erase != EraseMode::None {
+        return Err(DiskError::EraseNotSupported.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+mod backend;
+mod drive;
+mod format;
+mod partition;
+
+pub use backend::*;
+pub use drive::DriveModel;
+pub use partition::PartitionModel;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DiskError {
+    #[error("{0} partition tables are not supported by disks-rs")]
+    UnsupportedTableType(String),
+
+    #[error("Erasing a disk is not supported by disks-rs")]
+    EraseNotSupported,
+
+    #[error("No block device was found at {0}")]
+    DeviceNotFound(String),
+
+    #[error("{0} is not supported by disks-rs")]
+    NotSupported(String),
+
+    #[error("{0} is in use, unmount it first")]
+    InUse(String),
+}
+
+pub(crate) fn not_supported(what: &str) -> anyhow::Error {
+    DiskError::NotSupported(what.to_owned()).into()
+}
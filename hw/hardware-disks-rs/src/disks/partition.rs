@@ -0,0 +1,258 @@
+use std::{fs, os::unix::fs::MetadataExt, path::Path};
+
+use anyhow::Result;
+use disks_rs::partition::Partition as BlockPartition;
+use hardware_common::{
+    ByteSize, ChangePlan, DeviceNumber, DiskImage, EraseMode, FilesystemCheck, MountOptions,
+    Partition, Usage,
+};
+
+use super::{DiskError, DriveModel, not_supported};
+
+#[derive(Debug, Clone)]
+pub struct PartitionModel {
+    pub name: String,
+    pub number: u32,
+    /// The device node, e.g. /dev/sda1.
+    pub path: String,
+    /// The device node of the drive the partition is on.
+    pub drive_path: String,
+    pub offset: u64,
+    pub size: u64,
+    /// Where the partition is mounted, if it is.
+    pub usage: Option<Usage>,
+}
+
+/// The entry in `usage_data` for the block device at `path`, matched by its major:minor number.
+pub(crate) fn find_usage(path: &Path, usage_data: &[Usage]) -> Option<Usage> {
+    let device = DeviceNumber::from_dev_t(fs::metadata(path).ok()?.rdev());
+    usage_data.iter().find(|u| u.device == device).cloned()
+}
+
+impl PartitionModel {
+    pub(crate) fn from_partition(
+        partition: &BlockPartition,
+        drive_path: &str,
+        usage_data: &[Usage],
+    ) -> Self {
+        Self {
+            name: partition.name.clone(),
+            number: partition.number,
+            path: partition.device.to_string_lossy().into_owned(),
+            drive_path: drive_path.to_owned(),
+            //sysfs counts the start in 512 byte sectors, whatever the drive's sector size.
+            offset: partition.start * 512,
+            size: partition.size,
+            usage: find_usage(&partition.device, usage_data),
+        }
+    }
+
+    /// The name and size of the partition, for a [`ChangePlan`].
+    pub(crate) fn describe(&self) -> String {
+        format!(
+            "Partition {} ({})",
+            self.number,
+            ByteSize(self.size).pretty()
+        )
+    }
+
+    /// Plans deleting the partition, writing the change if `write` is set.
+    async fn run_delete(&self, write: bool) -> Result<()> {
+        let path = self.path.clone();
+
+        DriveModel::run_plan(
+            self.drive_path.clone(),
+            write,
+            false,
+            move |device, planner| {
+                //The planner numbers partitions by their position on the device.
+                let index = device
+                    .partitions()
+                    .iter()
+                    .position(|p| p.device.to_string_lossy() == path)
+                    .ok_or_else(|| DiskError::DeviceNotFound(path.clone()))?;
+
+                planner.plan_delete_partition(index)?;
+                Ok(())
+            },
+        )
+        .await
+    }
+}
+
+impl Partition for PartitionModel {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// disks-rs doesn't read partition names, so this is always empty.
+    fn label(&self) -> String {
+        String::new()
+    }
+
+    fn number(&self) -> u32 {
+        self.number
+    }
+
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// disks-rs doesn't probe the contents of partitions, so they are always unknown.
+    fn id_type(&self) -> String {
+        String::new()
+    }
+
+    fn partition_type(&self) -> String {
+        String::new()
+    }
+
+    fn uuid(&self) -> String {
+        String::new()
+    }
+
+    fn device_path(&self) -> Option<String> {
+        Some(self.path.clone())
+    }
+
+    fn usage(&self) -> Option<&Usage> {
+        self.usage.as_ref()
+    }
+
+    fn is_encrypted(&self) -> bool {
+        false
+    }
+
+    fn cleartext(&self) -> Option<&Self> {
+        None
+    }
+
+    /// Everything is read up front by [`DriveModel::get_drives`], so there is nothing to
+    /// connect to.
+    async fn connect(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn mount(&self) -> Result<()> {
+        Err(not_supported("Mounting"))
+    }
+
+    async fn unmount(&self) -> Result<()> {
+        Err(not_supported("Unmounting"))
+    }
+
+    async fn delete(&self) -> Result<()> {
+        self.run_delete(true).await
+    }
+
+    async fn plan_delete(&self) -> Result<ChangePlan> {
+        self.run_delete(false).await?;
+
+        let mut plan = ChangePlan::new();
+        plan.push(format!("Delete {}, and everything on it", self.describe()));
+        Ok(plan)
+    }
+
+    async fn format(
+        &self,
+        _name: String,
+        _erase: EraseMode,
+        _filesystem_type: String,
+    ) -> Result<()> {
+        Err(not_supported("Formatting partitions"))
+    }
+
+    async fn plan_format(
+        &self,
+        _name: String,
+        _erase: EraseMode,
+        _filesystem_type: String,
+    ) -> Result<ChangePlan> {
+        Err(not_supported("Formatting partitions"))
+    }
+
+    async fn edit_partition(
+        &self,
+        _partition_type: String,
+        _name: String,
+        _flags: u64,
+    ) -> Result<()> {
+        Err(not_supported("Editing partitions"))
+    }
+
+    async fn edit_filesystem_label(&self, _label: String) -> Result<()> {
+        Err(not_supported("Editing filesystem labels"))
+    }
+
+    async fn unlock(&self, _passphrase: String) -> Result<()> {
+        Err(not_supported("Encryption"))
+    }
+
+    async fn lock(&self) -> Result<()> {
+        Err(not_supported("Encryption"))
+    }
+
+    async fn change_passphrase(&self, _passphrase: String, _new_passphrase: String) -> Result<()> {
+        Err(not_supported("Encryption"))
+    }
+
+    async fn resize(&self, _new_size_bytes: u64) -> Result<()> {
+        Err(not_supported("Resizing"))
+    }
+
+    async fn plan_resize(&self, _new_size_bytes: u64) -> Result<ChangePlan> {
+        Err(not_supported("Resizing"))
+    }
+
+    async fn check_filesystem(&self) -> Result<FilesystemCheck> {
+        Err(not_supported("Checking filesystems"))
+    }
+
+    async fn repair_filesystem(&self) -> Result<FilesystemCheck> {
+        Err(not_supported("Repairing filesystems"))
+    }
+
+    async fn take_ownership(&self, _recursive: bool) -> Result<()> {
+        Err(not_supported("Taking ownership"))
+    }
+
+    async fn default_mount_options(&self) -> Result<()> {
+        Err(not_supported("Mount options"))
+    }
+
+    async fn mount_options(&self) -> Result<Option<MountOptions>> {
+        Err(not_supported("Mount options"))
+    }
+
+    async fn edit_mount_options(
+        &self,
+        _mount_at_startup: bool,
+        _show_in_ui: bool,
+        _requre_auth: bool,
+        _display_name: Option<String>,
+        _icon_name: Option<String>,
+        _symbolic_icon_name: Option<String>,
+        _options: String,
+        _mount_point: String,
+        _identify_as: String,
+        _file_system_type: String,
+    ) -> Result<()> {
+        Err(not_supported("Mount options"))
+    }
+
+    async fn edit_encrytion_options(&self) -> Result<()> {
+        Err(not_supported("Encryption"))
+    }
+
+    async fn create_image(&self, _output_path: String) -> Result<DiskImage> {
+        Err(not_supported("Creating images"))
+    }
+
+    async fn restore_image(&self, _input_path: String) -> Result<DiskImage> {
+        Err(not_supported("Restoring images"))
+    }
+}
@@ -0,0 +1,3 @@
+//! Disk management on top of disks-rs, working with the block devices directly so that it can
+//! run without udisksd.
+pub mod disks;
@@ -1,2 +1,18 @@
 pub use hardware_common::*;
+
+#[cfg(feature = "dbus")]
 pub use hardware_dbus::*;
+
+#[cfg(all(feature = "disks-rs", not(feature = "dbus")))]
+pub use hardware_disks_rs::*;
+
+/// The [`DiskBackend`] selected by the crate features.
+#[cfg(feature = "dbus")]
+pub type Backend = hardware_dbus::disks::DbusBackend;
+
+/// The [`DiskBackend`] selected by the crate features.
+#[cfg(all(feature = "disks-rs", not(feature = "dbus")))]
+pub type Backend = hardware_disks_rs::disks::DisksRsBackend;
+
+#[cfg(not(any(feature = "dbus", feature = "disks-rs")))]
+compile_error!("Enable the dbus or disks-rs feature to select a disk backend");